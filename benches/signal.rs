@@ -0,0 +1,67 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::{hint::black_box, sync::Arc, thread};
+
+use axcpu::uspace::UserContext;
+use starry_signal::{SignalDisposition, SignalInfo, SignalSet, Signo};
+use test::Bencher;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+use common::*;
+
+#[bench]
+fn send_signal(b: &mut Bencher) {
+    let (_proc, thr) = new_test_env();
+    let mask = !SignalSet::default();
+    b.iter(|| {
+        let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR1));
+        black_box(thr.dequeue_signal(&mask))
+    });
+}
+
+#[bench]
+fn send_signal_remote(b: &mut Bencher) {
+    let (proc, thr) = new_test_env();
+    let sender = {
+        let thr = Arc::clone(&thr);
+        move || {
+            for _ in 0..64 {
+                let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR1));
+            }
+        }
+    };
+    let mask = !SignalSet::default();
+    b.iter(|| {
+        thread::scope(|s| {
+            s.spawn(sender.clone());
+            while thr.dequeue_signal(&mask).is_some() {}
+        });
+    });
+    drop(proc);
+}
+
+#[bench]
+fn check_signals_fast_path(b: &mut Bencher) {
+    let (_proc, thr) = new_test_env();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    b.iter(|| black_box(thr.check_signals(&mut uctx, None)));
+}
+
+#[bench]
+fn deliver_to_handler(b: &mut Bencher) {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    b.iter(|| {
+        let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR1));
+        black_box(thr.check_signals(&mut uctx, None));
+        let new_sp = uctx.sp() + 8;
+        uctx.set_sp(new_sp);
+        thr.restore(&mut uctx).unwrap();
+    });
+}
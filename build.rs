@@ -1,4 +1,7 @@
 fn main() {
+    // Set with `RUSTFLAGS="--cfg loom"` to model-check with loom.
+    println!("cargo:rustc-check-cfg=cfg(loom)");
+
     let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap();
     alias(
         "sa_restorer",
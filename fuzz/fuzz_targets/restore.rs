@@ -0,0 +1,30 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use starry_signal::{
+    api::{SIGNAL_FRAME_MAGIC, SIGNAL_FRAME_MAGIC_OFFSET},
+    test_util::{TestEnv, initial_sp},
+};
+use starry_vm::VmMutPtr;
+
+const FRAME_AREA: usize = 0x10000;
+
+// Parses arbitrary bytes as the signal frame `sigreturn` restores from.
+fuzz_target!(|frame: &[u8]| {
+    let env = TestEnv::new();
+    let mut uctx = env.user_context();
+    let sp = initial_sp() - FRAME_AREA;
+    let mut bytes = vec![0; FRAME_AREA];
+    let len = frame.len().min(FRAME_AREA);
+    bytes[..len].copy_from_slice(&frame[..len]);
+    for (i, byte) in bytes.into_iter().enumerate() {
+        ((sp + i) as *mut u8).vm_write(byte).unwrap();
+    }
+    // Get past the magic check to the frame contents.
+    ((sp + SIGNAL_FRAME_MAGIC_OFFSET) as *mut u64)
+        .vm_write(SIGNAL_FRAME_MAGIC)
+        .unwrap();
+
+    uctx.set_sp(sp);
+    let _ = env.thread.restore(&mut uctx);
+});
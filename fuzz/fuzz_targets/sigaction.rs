@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use starry_signal::{SignalAction, k_sigaction};
+
+fuzz_target!(|kact: k_sigaction| {
+    let action = SignalAction::from(kact);
+    let again = SignalAction::from(k_sigaction::from(action.clone()));
+    assert_eq!(action.flags.bits(), again.flags.bits());
+    assert_eq!(action.mask.bits(), again.mask.bits());
+});
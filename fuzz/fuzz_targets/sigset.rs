@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use linux_raw_sys::general::kernel_sigset_t;
+use starry_signal::SignalSet;
+
+fuzz_target!(|set: SignalSet| {
+    let raw: kernel_sigset_t = set.into();
+    assert_eq!(SignalSet::from(raw).bits(), set.bits());
+
+    let mut rest = set;
+    let mut count = 0;
+    while let Some(signo) = rest.dequeue(&!SignalSet::default()) {
+        assert!(set.has(signo));
+        count += 1;
+    }
+    assert_eq!(count, set.bits().count_ones());
+});
@@ -0,0 +1,75 @@
+//! Generates a C header of the signal frame, ucontext and machine context
+//! layouts that the crate sets up on the user stack, for one architecture.
+//!
+//! ```sh
+//! cargo run --manifest-path header/Cargo.toml -- <arch> [output]
+//! ```
+//!
+//! `<arch>` is one of `x86_64`, `aarch64`, `riscv64` and `loongarch64`. The
+//! header is written to `output`, or `starry_signal_<arch>.h` by default.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+fn usage() -> ! {
+    eprintln!("usage: header <x86_64|aarch64|riscv64|loongarch64> [output]");
+    process::exit(2);
+}
+
+/// Gets the source directory of a dependency of the crate.
+fn package_dir(root: &Path, name: &str) -> PathBuf {
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(root.join("Cargo.toml"))
+        .output()
+        .expect("failed to run cargo metadata");
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("invalid cargo metadata");
+    let manifest = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|package| package["name"] == name)
+        .and_then(|package| package["manifest_path"].as_str())
+        .unwrap_or_else(|| panic!("no package {name}"));
+    Path::new(manifest).parent().unwrap().to_owned()
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let arch = args.next().unwrap_or_else(|| usage());
+    let output = args
+        .next()
+        .map_or_else(|| format!("starry_signal_{arch}.h").into(), PathBuf::from);
+    // The arch module is picked with `cfg_if!`, which cbindgen does not
+    // expand, so the sources are given file by file. The general registers
+    // of riscv and loongarch64 are defined by axcpu.
+    let (arch_src, axcpu_src) = match arch.as_str() {
+        "x86_64" => ("x86_64.rs", None),
+        "aarch64" => ("aarch64.rs", None),
+        "riscv64" => ("riscv.rs", Some("riscv/context.rs")),
+        "loongarch64" => ("loongarch64.rs", Some("loongarch64/context.rs")),
+        _ => usage(),
+    };
+
+    let header = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let root = header.parent().unwrap();
+    let config =
+        cbindgen::Config::from_file(header.join("cbindgen.toml")).expect("invalid cbindgen.toml");
+    let mut builder = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root.join("src/types.rs"))
+        .with_src(root.join("src/arch").join(arch_src))
+        .with_src(root.join("src/api/thread.rs"));
+    if let Some(axcpu_src) = axcpu_src {
+        builder = builder.with_src(package_dir(root, "axcpu").join("src").join(axcpu_src));
+    }
+    builder
+        .generate()
+        .expect("failed to generate the header")
+        .write_to_file(&output);
+    println!("{}", output.display());
+}
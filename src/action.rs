@@ -1,15 +1,16 @@
-use core::ffi::c_ulong;
+use core::{ffi::c_ulong, mem};
 
 use bitflags::bitflags;
 use linux_raw_sys::{
     general::{
-        __kernel_sighandler_t, __sigrestore_t, SA_NODEFER, SA_ONSTACK, SA_RESETHAND, SA_RESTART,
-        SA_SIGINFO, kernel_sigaction,
+        __kernel_sighandler_t, __sigrestore_t, SA_EXPOSE_TAGBITS, SA_NOCLDSTOP, SA_NOCLDWAIT,
+        SA_NODEFER, SA_NOMASK, SA_ONESHOT, SA_ONSTACK, SA_RESETHAND, SA_RESTART, SA_SIGINFO,
+        SA_UNSUPPORTED, kernel_sigaction, siginfo_t,
     },
     signal_macros::sig_ign,
 };
 
-use crate::SignalSet;
+use crate::{SignalError, SignalResult, SignalSet, arch::UContext};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DefaultSignalAction {
@@ -41,31 +42,125 @@ pub enum SignalOSAction {
     /// A signal handler is pushed into the signal stack. The OS doesn't need to
     /// do anything.
     Handler,
+    /// The tracer of the thread requested a signal-delivery-stop. The signal
+    /// has not been acted on.
+    TraceStop,
 }
 
 bitflags! {
-    #[derive(Default, Debug, Clone, Copy)]
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
     pub struct SignalActionFlags: c_ulong {
+        const NOCLDSTOP = SA_NOCLDSTOP as _;
+        const NOCLDWAIT = SA_NOCLDWAIT as _;
         const SIGINFO = SA_SIGINFO as _;
         const NODEFER = SA_NODEFER as _;
         const RESETHAND = SA_RESETHAND as _;
         const RESTART = SA_RESTART as _;
         const ONSTACK = SA_ONSTACK as _;
         const RESTORER = 0x4000000;
+        /// Never set in an action read back from the kernel, so that user
+        /// space can detect whether unknown flags are cleared.
+        const UNSUPPORTED = SA_UNSUPPORTED as _;
+        /// Keep the tag bits of `si_addr` on architectures with address
+        /// tagging.
+        const EXPOSE_TAGBITS = SA_EXPOSE_TAGBITS as _;
+        /// Historical flag with no effect, accepted and reported back for
+        /// old binaries.
+        const INTERRUPT = 0x20000000;
+
+        /// Historical alias of [`Self::NODEFER`].
+        const NOMASK = SA_NOMASK as _;
+        /// Historical alias of [`Self::RESETHAND`].
+        const ONESHOT = SA_ONESHOT as _;
     }
 }
 
 // FIXME: replace with `kernel_sigaction` after finishing above "TODO"s for `SignalSet`
+/// `struct sigaction` as passed to the `rt_sigaction` syscall. The
+/// `sa_restorer` field only exists on architectures whose ABI has it.
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 #[allow(non_camel_case_types)]
 pub struct k_sigaction {
     handler: __kernel_sighandler_t,
     flags: c_ulong,
+    #[cfg(sa_restorer)]
     restorer: __sigrestore_t,
     pub mask: SignalSet,
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for k_sigaction {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        // SAFETY: `Option` of a function pointer can hold any address. The
+        // pointers are never called.
+        let handler =
+            unsafe { core::mem::transmute::<usize, __kernel_sighandler_t>(u.arbitrary()?) };
+        Ok(Self {
+            handler,
+            flags: u.arbitrary()?,
+            #[cfg(sa_restorer)]
+            restorer: unsafe { core::mem::transmute::<usize, __sigrestore_t>(u.arbitrary()?) },
+            mask: u.arbitrary()?,
+        })
+    }
+}
+
+/// Builds a `kernel_sigaction` from its fields.
+fn new_kernel_sigaction(
+    handler: __kernel_sighandler_t,
+    flags: c_ulong,
+    #[cfg(sa_restorer)] restorer: __sigrestore_t,
+    mask: SignalSet,
+) -> kernel_sigaction {
+    // FIXME: Zeroable
+    let mut result: kernel_sigaction = unsafe { core::mem::zeroed() };
+    result.sa_handler_kernel = handler;
+    result.sa_flags = flags as _;
+    #[cfg(sa_restorer)]
+    {
+        result.sa_restorer = restorer;
+    }
+    result.sa_mask = mask.into();
+    result
+}
+
+impl From<k_sigaction> for kernel_sigaction {
+    fn from(value: k_sigaction) -> Self {
+        new_kernel_sigaction(
+            value.handler,
+            value.flags,
+            #[cfg(sa_restorer)]
+            value.restorer,
+            value.mask,
+        )
+    }
+}
+
+impl From<kernel_sigaction> for k_sigaction {
+    fn from(value: kernel_sigaction) -> Self {
+        Self {
+            handler: value.sa_handler_kernel,
+            flags: value.sa_flags,
+            #[cfg(sa_restorer)]
+            restorer: value.sa_restorer,
+            mask: value.sa_mask.into(),
+        }
+    }
+}
+
+impl From<SignalAction> for k_sigaction {
+    fn from(value: SignalAction) -> Self {
+        kernel_sigaction::from(value).into()
+    }
+}
+
+impl From<k_sigaction> for SignalAction {
+    fn from(value: k_sigaction) -> Self {
+        kernel_sigaction::from(value).into()
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub enum SignalDisposition {
     #[default]
@@ -75,6 +170,41 @@ pub enum SignalDisposition {
     Ignore,
     /// Custom signal handler.
     Handler(unsafe extern "C" fn(i32)),
+    /// Custom signal handler taking the siginfo and the ucontext, installed
+    /// with [`SignalActionFlags::SIGINFO`].
+    SigactionHandler(unsafe extern "C" fn(i32, *mut siginfo_t, *mut UContext)),
+}
+
+/// Handlers are compared by address.
+impl PartialEq for SignalDisposition {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Default, Self::Default) | (Self::Ignore, Self::Ignore) => true,
+            (Self::Handler(_), Self::Handler(_))
+            | (Self::SigactionHandler(_), Self::SigactionHandler(_)) => {
+                self.handler() == other.handler()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SignalDisposition {}
+
+impl SignalDisposition {
+    /// Gets the address of the handler, if any.
+    pub fn handler(&self) -> Option<usize> {
+        match self {
+            Self::Default | Self::Ignore => None,
+            Self::Handler(handler) => Some(*handler as usize),
+            Self::SigactionHandler(handler) => Some(*handler as usize),
+        }
+    }
+
+    /// Checks if a handler is installed.
+    pub fn is_handler(&self) -> bool {
+        self.handler().is_some()
+    }
 }
 
 /// Signal action. Corresponds to `struct sigaction` in libc.
@@ -86,36 +216,122 @@ pub struct SignalAction {
     pub restorer: __sigrestore_t,
 }
 
-impl From<SignalAction> for kernel_sigaction {
-    fn from(value: SignalAction) -> Self {
-        // FIXME: Zeroable
-        let mut result: kernel_sigaction = unsafe { core::mem::zeroed() };
-
-        result.sa_flags = value.flags.bits() as _;
-        result.sa_mask = value.mask.into();
-        match &value.disposition {
-            SignalDisposition::Default => {
-                result.sa_handler_kernel = None;
-            }
-            SignalDisposition::Ignore => {
-                result.sa_handler_kernel = sig_ign();
-            }
-            SignalDisposition::Handler(handler) => {
-                result.sa_handler_kernel = Some(*handler);
-            }
-        }
-        #[cfg(sa_restorer)]
+impl SignalAction {
+    /// Creates a builder.
+    pub fn builder() -> SignalActionBuilder {
+        SignalActionBuilder::default()
+    }
+}
+
+/// Builder of [`SignalAction`], checking that the flags match the
+/// disposition.
+#[derive(Debug, Default)]
+pub struct SignalActionBuilder {
+    action: SignalAction,
+}
+
+impl SignalActionBuilder {
+    /// Sets the disposition.
+    pub fn disposition(mut self, disposition: SignalDisposition) -> Self {
+        self.action.disposition = disposition;
+        self
+    }
+
+    /// Sets a handler taking the signal number only.
+    pub fn handler(self, handler: unsafe extern "C" fn(i32)) -> Self {
+        self.disposition(SignalDisposition::Handler(handler))
+    }
+
+    /// Sets a handler taking the siginfo and the ucontext, along with
+    /// [`SignalActionFlags::SIGINFO`].
+    pub fn sigaction(
+        mut self,
+        handler: unsafe extern "C" fn(i32, *mut siginfo_t, *mut UContext),
+    ) -> Self {
+        self.action.flags.insert(SignalActionFlags::SIGINFO);
+        self.disposition(SignalDisposition::SigactionHandler(handler))
+    }
+
+    /// Sets the signals blocked while the handler runs.
+    pub fn mask(mut self, mask: SignalSet) -> Self {
+        self.action.mask = mask;
+        self
+    }
+
+    /// Adds flags.
+    pub fn flags(mut self, flags: SignalActionFlags) -> Self {
+        self.action.flags.insert(flags);
+        self
+    }
+
+    /// Sets the restorer, along with [`SignalActionFlags::RESTORER`].
+    pub fn restorer(mut self, restorer: unsafe extern "C" fn()) -> Self {
+        self.action.flags.insert(SignalActionFlags::RESTORER);
+        self.action.restorer = Some(restorer);
+        self
+    }
+
+    /// Builds the action.
+    ///
+    /// Fails with [`SignalError::InvalidArgument`] if
+    /// [`SignalActionFlags::SIGINFO`] is set without a
+    /// [`SignalDisposition::SigactionHandler`] or the other way round, if
+    /// [`SignalActionFlags::RESTORER`] is set without a restorer, or if
+    /// [`SignalActionFlags::UNSUPPORTED`] is set.
+    pub fn build(self) -> SignalResult<SignalAction> {
+        let action = self.action;
+        let siginfo = action.flags.contains(SignalActionFlags::SIGINFO);
+        let sigaction = matches!(action.disposition, SignalDisposition::SigactionHandler(_));
+        if (action.disposition.is_handler() && siginfo != sigaction)
+            || (action.flags.contains(SignalActionFlags::RESTORER) && action.restorer.is_none())
+            || action.flags.contains(SignalActionFlags::UNSUPPORTED)
         {
-            result.sa_restorer = value.restorer;
+            return Err(SignalError::InvalidArgument);
         }
+        Ok(action)
+    }
+}
 
-        result
+/// The restorer is compared by address.
+impl PartialEq for SignalAction {
+    fn eq(&self, other: &Self) -> bool {
+        self.flags == other.flags
+            && self.mask == other.mask
+            && self.disposition == other.disposition
+            && self.restorer.map(|f| f as usize) == other.restorer.map(|f| f as usize)
+    }
+}
+
+impl Eq for SignalAction {}
+
+impl From<SignalAction> for kernel_sigaction {
+    fn from(value: SignalAction) -> Self {
+        let handler = match &value.disposition {
+            SignalDisposition::Default => None,
+            SignalDisposition::Ignore => sig_ign(),
+            SignalDisposition::Handler(handler) => Some(*handler),
+            // SAFETY: Only the calling convention of the handler differs.
+            SignalDisposition::SigactionHandler(handler) => Some(unsafe {
+                mem::transmute::<
+                    unsafe extern "C" fn(i32, *mut siginfo_t, *mut UContext),
+                    unsafe extern "C" fn(i32),
+                >(*handler)
+            }),
+        };
+        new_kernel_sigaction(
+            handler,
+            value.flags.bits(),
+            #[cfg(sa_restorer)]
+            value.restorer,
+            value.mask,
+        )
     }
 }
 
 impl From<kernel_sigaction> for SignalAction {
     fn from(value: kernel_sigaction) -> Self {
-        let flags = SignalActionFlags::from_bits_truncate(value.sa_flags);
+        let mut flags = SignalActionFlags::from_bits_truncate(value.sa_flags);
+        flags.remove(SignalActionFlags::UNSUPPORTED);
         let disposition = {
             match value.sa_handler_kernel {
                 None => {
@@ -126,6 +342,16 @@ impl From<kernel_sigaction> for SignalAction {
                     // SIG_IGN
                     SignalDisposition::Ignore
                 }
+                Some(h) if flags.contains(SignalActionFlags::SIGINFO) => {
+                    // SAFETY: Only the calling convention of the handler
+                    // differs.
+                    SignalDisposition::SigactionHandler(unsafe {
+                        mem::transmute::<
+                            unsafe extern "C" fn(i32),
+                            unsafe extern "C" fn(i32, *mut siginfo_t, *mut UContext),
+                        >(h)
+                    })
+                }
                 Some(h) => {
                     // Custom signal handler
                     SignalDisposition::Handler(h)
@@ -0,0 +1,291 @@
+use alloc::sync::Arc;
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use kspin::SpinNoIrq;
+
+use crate::{SignalInfo, SignalOSAction, SignalSet, Signo};
+
+/// Callbacks invoked on signal events of a process.
+///
+/// Register one with [`ProcessSignalManager::set_listener`] for auditing or
+/// tracing. The callbacks are invoked without holding any lock of the signal
+/// managers, so they may call back into them.
+///
+/// [`ProcessSignalManager::set_listener`]: super::ProcessSignalManager::set_listener
+pub trait SignalEventListener: Send + Sync {
+    /// Called when a signal is queued. `tid` is the thread it is directed or
+    /// routed to, if any.
+    ///
+    /// Not called for a signal that is not queued: a standard signal already
+    /// pending, a real-time signal over the queue limit, or a timer expiry
+    /// counted as an overrun.
+    fn on_send(&self, tid: Option<u32>, sig: &SignalInfo) {
+        let _ = (tid, sig);
+    }
+
+    /// Called when a signal is delivered to thread `tid`, either by setting
+    /// up a handler or by returning an action to the OS.
+    fn on_deliver(&self, tid: u32, sig: &SignalInfo, action: SignalOSAction) {
+        let _ = (tid, sig, action);
+    }
+
+    /// Called when a signal is discarded because it is ignored, either when
+    /// sent or when delivered.
+    fn on_ignore(&self, tid: Option<u32>, sig: &SignalInfo) {
+        let _ = (tid, sig);
+    }
+
+    /// Called when the delivery of a signal to thread `tid` results in a core
+    /// dump. [`Self::on_deliver`] is not called in this case.
+    fn on_coredump(&self, tid: u32, sig: &SignalInfo) {
+        let _ = (tid, sig);
+    }
+
+    /// Called when thread `tid` returns from a signal handler.
+    fn on_sigreturn(&self, tid: u32) {
+        let _ = tid;
+    }
+}
+
+/// Consumer waiting for signals to arrive, such as a `signalfd`.
+///
+/// This lets the OS poll subsystem wait on signal arrival without polling
+/// the pending sets. Register one with
+/// [`ProcessSignalManager::register_readiness`]. Consumers are held weakly
+/// and forgotten once dropped. [`Self::notify_ready`] is invoked without
+/// holding any lock of the signal managers.
+///
+/// [`ProcessSignalManager::register_readiness`]: super::ProcessSignalManager::register_readiness
+pub trait SignalReadiness: Send + Sync {
+    /// Gets the signals the consumer waits for.
+    fn interest(&self) -> SignalSet;
+
+    /// Called when a signal of interest is made pending, blocked or not.
+    /// `tid` is the thread it is directed to, or `None` if it is directed to
+    /// the process.
+    fn notify_ready(&self, tid: Option<u32>, signo: Signo);
+}
+
+/// Knowledge of the OS about the user stacks of a process, consulted before
+/// a signal frame is written.
+///
+/// Register one with [`ProcessSignalManager::set_stack_hook`]. Without it,
+/// a frame landing in a guard page or outside the stack is only noticed
+/// when writing it fails, if at all.
+///
+/// [`ProcessSignalManager::set_stack_hook`]: super::ProcessSignalManager::set_stack_hook
+pub trait UserStackHook: Send + Sync {
+    /// Gets the bounds of the writable stack area of thread `tid` containing
+    /// `addr`, excluding guard pages, or `None` if `addr` is not in one.
+    fn stack_bounds(&self, tid: u32, addr: usize) -> Option<Range<usize>>;
+
+    /// Prepares `range` for a signal frame of thread `tid` to be written,
+    /// e.g. by faulting in or breaking copy-on-write of its pages, or growing
+    /// the stack area. Called before [`Self::stack_bounds`].
+    ///
+    /// Returns `false` if the memory can't be made writable, in which case
+    /// delivery fails as with a fatal stack error.
+    fn prepare_frame(&self, tid: u32, range: Range<usize>) -> bool {
+        let _ = (tid, range);
+        true
+    }
+}
+
+/// Timer subsystem of the OS, told when the signal of a POSIX timer leaves
+/// the pending queues.
+///
+/// Each timer has at most one signal in flight: further expiries are counted
+/// in the overrun of the pending signal instead of queueing another one.
+/// Register one with [`ProcessSignalManager::set_timer_hook`] to learn when
+/// the signal is taken. [`Self::on_dequeue`] is invoked without holding any
+/// lock of the signal managers.
+///
+/// [`ProcessSignalManager::set_timer_hook`]: super::ProcessSignalManager::set_timer_hook
+pub trait TimerSignalHook: Send + Sync {
+    /// Called when the signal of POSIX timer `timer_id` is dequeued, with
+    /// the number of expiries counted in its overrun. The next expiry queues
+    /// a new signal.
+    fn on_dequeue(&self, timer_id: i32, overrun: i32);
+}
+
+/// Accounting of queued signals by the user who sent them, across all
+/// processes, to enforce the per-user limit of `RLIMIT_SIGPENDING`.
+///
+/// Only signals sent by a user (`SI_USER`, `SI_QUEUE`, `SI_TKILL` and
+/// `SI_MESGQ`) are accounted; those generated by the kernel are always
+/// queued. Register one with [`set_pending_accounting`]. The callbacks are
+/// invoked with a pending queue locked, so they must not call into the
+/// signal managers.
+pub trait PendingAccounting: Send + Sync {
+    /// Charges a signal about to be queued to user `uid`. Returns `false` if
+    /// the user is over its limit, in which case the signal is dropped.
+    fn charge(&self, uid: u32) -> bool;
+
+    /// Releases a signal charged to user `uid`, when it is dequeued or
+    /// discarded.
+    fn release(&self, uid: u32);
+}
+
+static PENDING_ACCOUNTING: SpinNoIrq<Option<Arc<dyn PendingAccounting>>> = SpinNoIrq::new(None);
+/// Whether an accounting is set, checked before taking its lock.
+static PENDING_ACCOUNTED: AtomicBool = AtomicBool::new(false);
+
+/// Sets the accounting of queued signals, or removes it with `None`.
+///
+/// This should be done before any signal is sent, as signals already queued
+/// are released to the new accounting.
+pub fn set_pending_accounting(accounting: Option<Arc<dyn PendingAccounting>>) {
+    let mut slot = PENDING_ACCOUNTING.lock();
+    PENDING_ACCOUNTED.store(accounting.is_some(), Ordering::Release);
+    *slot = accounting;
+}
+
+pub(crate) fn pending_accounting() -> Option<Arc<dyn PendingAccounting>> {
+    if !PENDING_ACCOUNTED.load(Ordering::Acquire) {
+        return None;
+    }
+    PENDING_ACCOUNTING.lock().clone()
+}
+
+/// Scheduler of the OS, told when a thread should be interrupted to take a
+/// signal.
+///
+/// Without it, the OS must act on the threads returned by the send
+/// functions, and a thread running on another CPU only notices the signal at
+/// its next kernel entry. Register one with [`set_resched_hook`]. It is
+/// invoked without holding any lock of the signal managers, in addition to
+/// the threads being returned.
+pub trait ReschedHook: Send + Sync {
+    /// Called when thread `tid` should take a signal as soon as possible:
+    /// wake it up if it sleeps interruptibly, or send an IPI or set
+    /// need-resched on its CPU if it runs.
+    fn kick(&self, tid: u32);
+}
+
+static RESCHED_HOOK: SpinNoIrq<Option<Arc<dyn ReschedHook>>> = SpinNoIrq::new(None);
+/// Whether a rescheduling hook is set, checked before taking its lock.
+static RESCHED_HOOKED: AtomicBool = AtomicBool::new(false);
+
+/// Sets the rescheduling hook, or removes it with `None`.
+pub fn set_resched_hook(hook: Option<Arc<dyn ReschedHook>>) {
+    let mut slot = RESCHED_HOOK.lock();
+    RESCHED_HOOKED.store(hook.is_some(), Ordering::Release);
+    *slot = hook;
+}
+
+pub(crate) fn kick(tids: &[u32]) {
+    if tids.is_empty() || !RESCHED_HOOKED.load(Ordering::Acquire) {
+        return;
+    }
+    let hook = RESCHED_HOOK.lock().clone();
+    if let Some(hook) = hook {
+        for &tid in tids {
+            hook.kick(tid);
+        }
+    }
+}
+
+/// Decision of a [`SignalTracer`] about a dequeued signal.
+#[derive(Debug, Clone)]
+pub enum TraceDecision {
+    /// Deliver the signal as usual.
+    Deliver,
+    /// Enter signal-delivery-stop instead of delivering the signal.
+    ///
+    /// [`ThreadSignalManager::check_signals`] returns the signal along with
+    /// [`SignalOSAction::TraceStop`].
+    ///
+    /// [`ThreadSignalManager::check_signals`]: super::ThreadSignalManager::check_signals
+    Stop,
+    /// Discard the signal.
+    Suppress,
+    /// Deliver the given signal instead. If it is blocked, it is queued to
+    /// the thread instead.
+    Replace(SignalInfo),
+}
+
+/// Tracer of a thread, consulted before a dequeued signal is acted on.
+///
+/// This is the core of `ptrace` signal-delivery-stop. Register one with
+/// [`ThreadSignalManager::set_tracer`]. `SIGKILL` is never reported.
+///
+/// [`ThreadSignalManager::set_tracer`]: super::ThreadSignalManager::set_tracer
+pub trait SignalTracer: Send + Sync {
+    /// Decides what to do with a signal dequeued by thread `tid`.
+    fn on_signal(&self, tid: u32, sig: &SignalInfo) -> TraceDecision;
+}
+
+/// Decision of a [`SignalFilter`] about a dequeued signal.
+#[derive(Debug, Clone)]
+pub enum FilterDecision {
+    /// Let the signal through.
+    Allow,
+    /// Discard the signal.
+    Drop,
+    /// Act on the given signal instead, such as `SIGSYS` or `SIGKILL`. If it
+    /// is blocked, it is queued to the thread instead.
+    Rewrite(SignalInfo),
+}
+
+/// Security filter of a process, consulted on every signal a thread dequeues
+/// for delivery, before its action is looked up.
+///
+/// This lets seccomp-style policies and sandbox supervisors be layered on
+/// top of the crate. Register one with [`ProcessSignalManager::set_filter`].
+/// The filter runs before the tracer of the thread, without holding any lock
+/// of the signal managers. `SIGKILL` and signals injected by the tracer are
+/// not filtered, nor is a rewritten signal acted on right away.
+///
+/// [`ProcessSignalManager::set_filter`]: super::ProcessSignalManager::set_filter
+pub trait SignalFilter: Send + Sync {
+    /// Decides what to do with a signal dequeued by thread `tid`.
+    fn filter(&self, tid: u32, sig: &SignalInfo) -> FilterDecision;
+}
+
+/// Thresholds of signal pressure on a process, past which its
+/// [`SignalPressureHook`] is called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PressureThresholds {
+    /// Number of signals queued to the process and all of its threads.
+    pub total: usize,
+    /// Number of queued instances of one real-time signal in one queue.
+    pub rt_depth: usize,
+}
+
+impl Default for PressureThresholds {
+    fn default() -> Self {
+        Self {
+            total: usize::MAX,
+            rt_depth: usize::MAX,
+        }
+    }
+}
+
+/// Threshold of [`PressureThresholds`] crossed, with the value reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalPressure {
+    /// The total number of queued signals reached [`PressureThresholds::total`].
+    Total(usize),
+    /// The queue of a real-time signal reached
+    /// [`PressureThresholds::rt_depth`].
+    RtDepth(usize),
+}
+
+/// Callback invoked when signals pile up in a process, such as when a
+/// runaway sender floods it.
+///
+/// This lets the OS log, throttle the sender or kill it before the queues
+/// exhaust memory. Register one with
+/// [`ProcessSignalManager::set_pressure_hook`]. It is called once each time
+/// a threshold is crossed upwards, by the send that crosses it, without
+/// holding any lock of the signal managers.
+///
+/// [`ProcessSignalManager::set_pressure_hook`]: super::ProcessSignalManager::set_pressure_hook
+pub trait SignalPressureHook: Send + Sync {
+    /// Called when queuing `sig` to thread `tid`, or to the process if
+    /// `None`, crosses a threshold.
+    fn on_pressure(&self, tid: Option<u32>, sig: &SignalInfo, pressure: SignalPressure);
+}
@@ -0,0 +1,226 @@
+//! Sending signals from interrupt context.
+//!
+//! The send path takes several locks and calls back into the OS, so it must
+//! not run in an interrupt handler that may have interrupted it. Instead,
+//! [`ProcessSignalManager::send_signal_from_irq`] and
+//! [`ThreadSignalManager::send_signal_from_irq`] only put the signal into a
+//! queue of the current CPU, and the OS sends the queued signals with
+//! [`flush_irq_signals`] at the next safe point, such as on return from the
+//! interrupt or before returning to user space.
+
+use alloc::{
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::{
+    cell::UnsafeCell,
+    mem::{self, MaybeUninit},
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+use crate::{
+    SignalError, SignalInfo, SignalResult,
+    api::{ProcessSignalManager, ThreadSignalManager},
+};
+
+/// Number of per-CPU queues. CPUs with higher ids share them.
+pub const MAX_CPUS: usize = 64;
+
+/// Capacity of the queue of each CPU.
+pub const IRQ_QUEUE_LEN: usize = 16;
+
+const _: () = assert!(IRQ_QUEUE_LEN.is_power_of_two());
+
+enum Target {
+    Process(Weak<ProcessSignalManager>),
+    Thread(Weak<ThreadSignalManager>),
+}
+
+struct Slot {
+    /// Position of the slot in the queue: equal to the position to push
+    /// next when the slot is free, and one past it once written.
+    seq: AtomicUsize,
+    signal: UnsafeCell<MaybeUninit<(Target, SignalInfo)>>,
+}
+
+/// Bounded lock-free queue, after Dmitry Vyukov's.
+///
+/// Neither side ever waits for the other: a push finding the queue full
+/// fails, and a pop finding the next slot still being written stops there,
+/// leaving it to the next pop. So an interrupt can push while the queue is
+/// being pushed to or popped from on the CPU it interrupted.
+struct IrqQueue {
+    slots: [Slot; IRQ_QUEUE_LEN],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+// SAFETY: A slot is only accessed by the side that claimed its position.
+unsafe impl Sync for IrqQueue {}
+
+impl IrqQueue {
+    const fn new() -> Self {
+        let mut slots = [const {
+            Slot {
+                seq: AtomicUsize::new(0),
+                signal: UnsafeCell::new(MaybeUninit::uninit()),
+            }
+        }; IRQ_QUEUE_LEN];
+        let mut i = 0;
+        while i < IRQ_QUEUE_LEN {
+            slots[i].seq = AtomicUsize::new(i);
+            i += 1;
+        }
+        Self {
+            slots,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    fn push(&self, signal: (Target, SignalInfo)) -> SignalResult {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[pos % IRQ_QUEUE_LEN];
+            let seq = slot.seq.load(Ordering::Acquire);
+            if seq == pos {
+                match self.tail.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: The position is claimed, and the slot was
+                        // freed by the pop of the previous round.
+                        unsafe { (*slot.signal.get()).write(signal) };
+                        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if (seq.wrapping_sub(pos) as isize) < 0 {
+                // Not popped since the previous round.
+                return Err(SignalError::WouldBlock);
+            } else {
+                pos = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<(Target, SignalInfo)> {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[pos % IRQ_QUEUE_LEN];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let next = pos.wrapping_add(1);
+            if seq == next {
+                match self.head.compare_exchange_weak(
+                    pos,
+                    next,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: The position is claimed, and the slot was
+                        // written by the push that released it.
+                        let signal = unsafe { (*slot.signal.get()).assume_init_read() };
+                        slot.seq
+                            .store(pos.wrapping_add(IRQ_QUEUE_LEN), Ordering::Release);
+                        return Some(signal);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if (seq.wrapping_sub(next) as isize) < 0 {
+                // Empty, or still being written.
+                return None;
+            } else {
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn default_cpu_id() -> usize {
+    0
+}
+
+/// The function telling the id of the current CPU, or null for
+/// [`default_cpu_id`].
+static CPU_ID: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+static QUEUES: [IrqQueue; MAX_CPUS] = [const { IrqQueue::new() }; MAX_CPUS];
+
+/// Sets the function telling the id of the current CPU. All CPUs share one
+/// queue until this is called.
+pub fn set_cpu_id(cpu_id: fn() -> usize) {
+    CPU_ID.store(cpu_id as *mut (), Ordering::Release);
+}
+
+fn queue() -> &'static IrqQueue {
+    let cpu_id = CPU_ID.load(Ordering::Acquire);
+    let cpu_id = if cpu_id.is_null() {
+        default_cpu_id
+    } else {
+        // SAFETY: Only `set_cpu_id` stores a non-null pointer, from a
+        // `fn() -> usize`.
+        unsafe { mem::transmute::<*mut (), fn() -> usize>(cpu_id) }
+    };
+    &QUEUES[cpu_id() % MAX_CPUS]
+}
+
+/// Puts a signal into the queue of the current CPU without taking any lock.
+fn defer(target: Target, sig: SignalInfo) -> SignalResult {
+    queue().push((target, sig))
+}
+
+impl ProcessSignalManager {
+    /// Sends a signal to the process from interrupt context.
+    ///
+    /// The signal is sent by the next [`flush_irq_signals`] on this CPU.
+    /// Takes no lock: fails with [`SignalError::WouldBlock`] if the queue of
+    /// this CPU is full, in which case the signal is lost.
+    pub fn send_signal_from_irq(self: &Arc<Self>, sig: SignalInfo) -> SignalResult {
+        defer(Target::Process(Arc::downgrade(self)), sig)
+    }
+}
+
+impl ThreadSignalManager {
+    /// Sends a signal to the thread from interrupt context.
+    ///
+    /// See [`ProcessSignalManager::send_signal_from_irq`].
+    pub fn send_signal_from_irq(self: &Arc<Self>, sig: SignalInfo) -> SignalResult {
+        defer(Target::Thread(Arc::downgrade(self)), sig)
+    }
+}
+
+/// Sends the signals queued from interrupt context on the current CPU, in
+/// the order they were queued. Must not be called in interrupt context.
+///
+/// Signals to processes or threads gone since are dropped. Returns the
+/// threads to wake up.
+pub fn flush_irq_signals() -> Vec<u32> {
+    let queue = queue();
+    let signals: Vec<_> = core::iter::from_fn(|| queue.pop()).collect();
+
+    let mut wake = Vec::new();
+    for (target, sig) in signals {
+        match target {
+            Target::Process(proc) => {
+                if let Some(proc) = proc.upgrade() {
+                    wake.extend(proc.send_signal(sig).tids());
+                }
+            }
+            Target::Thread(thread) => {
+                if let Some(thread) = thread.upgrade()
+                    && thread.send_signal(sig)
+                {
+                    wake.push(thread.tid());
+                }
+            }
+        }
+    }
+    wake.sort_unstable();
+    wake.dedup();
+    wake
+}
@@ -1,5 +1,16 @@
+mod hooks;
+mod irq;
+mod owner;
+mod permission;
 mod process;
+pub mod syscalls;
 mod thread;
+mod timer;
 
+pub use hooks::*;
+pub use irq::*;
+pub use owner::*;
+pub use permission::*;
 pub use process::*;
 pub use thread::*;
+pub use timer::*;
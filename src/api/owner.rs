@@ -0,0 +1,85 @@
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::{
+    SignalInfo, Signo,
+    api::{ProcessSignalManager, ThreadSignalManager},
+};
+
+/// What the owner of a file refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnerTarget {
+    /// A single thread (`F_OWNER_TID`).
+    Thread(u32),
+    /// A process (`F_OWNER_PID`).
+    Process(u32),
+    /// A process group (`F_OWNER_PGRP`).
+    ProcessGroup(u32),
+}
+
+/// The owner of a file, set with `F_SETOWN` or `F_SETOWN_EX`, which gets
+/// `SIGIO` and `SIGURG` for the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileOwner {
+    /// The owner.
+    pub target: OwnerTarget,
+    /// The real user ID of the process that set the owner.
+    pub uid: u32,
+    /// The effective user ID of the process that set the owner.
+    pub euid: u32,
+    /// The signal set with `F_SETSIG`, sent instead of `SIGIO` with the file
+    /// descriptor and poll events attached.
+    pub signo: Option<Signo>,
+}
+
+/// Resolution of file owners, provided by the OS.
+pub trait OwnerLookup {
+    /// Gets a thread and the ID of its process.
+    fn thread(&self, tid: u32) -> Option<(u32, Arc<ThreadSignalManager>)>;
+
+    /// Gets the process with the ID, or all processes of the group, each with
+    /// its ID.
+    fn processes(&self, target: OwnerTarget) -> Vec<(u32, Arc<ProcessSignalManager>)>;
+
+    /// Checks if `owner` may send signals to process `pid`, i.e. if root or
+    /// its user IDs match those of the process.
+    fn permitted(&self, owner: &FileOwner, pid: u32) -> bool;
+}
+
+impl FileOwner {
+    /// Notifies the owner of an I/O event on file `fd`.
+    ///
+    /// Sends `SIGIO`, or the signal set with `F_SETSIG` carrying `fd`, the
+    /// `POLL_*` code and the poll events `band`. Returns the threads woken
+    /// up.
+    pub fn send_sigio(&self, lookup: &dyn OwnerLookup, fd: i32, code: i32, band: i64) -> Vec<u32> {
+        let sig = match self.signo {
+            Some(signo) => SignalInfo::new_poll(signo, code, fd, band),
+            None => SignalInfo::new_kernel(Signo::SIGIO),
+        };
+        self.send(lookup, sig)
+    }
+
+    /// Notifies the owner of urgent data on a socket by sending `SIGURG`.
+    /// Returns the threads woken up.
+    pub fn send_sigurg(&self, lookup: &dyn OwnerLookup) -> Vec<u32> {
+        self.send(lookup, SignalInfo::new_kernel(Signo::SIGURG))
+    }
+
+    fn send(&self, lookup: &dyn OwnerLookup, sig: SignalInfo) -> Vec<u32> {
+        match self.target {
+            OwnerTarget::Thread(tid) => lookup
+                .thread(tid)
+                .filter(|(pid, _)| lookup.permitted(self, *pid))
+                .filter(|(_, thread)| thread.send_signal(sig))
+                .map(|_| tid)
+                .into_iter()
+                .collect(),
+            target => lookup
+                .processes(target)
+                .into_iter()
+                .filter(|(pid, _)| lookup.permitted(self, *pid))
+                .flat_map(|(_, proc)| proc.send_signal(sig.clone()).tids().to_vec())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    SignalError, SignalInfo, SignalResult, Signo,
+    api::{ProcessSignalManager, SignalWake, ThreadSignalManager},
+};
+
+/// Credentials of a process relevant to signal permission checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials {
+    /// The real user ID.
+    pub uid: u32,
+    /// The effective user ID.
+    pub euid: u32,
+    /// The saved set-user-ID.
+    pub suid: u32,
+    /// Whether the process has `CAP_KILL` over the other process.
+    pub cap_kill: bool,
+    /// The session ID.
+    pub sid: u32,
+}
+
+/// Check of whether a process may send a signal to another, as done by
+/// `kill`, `tgkill` and `rt_sigqueueinfo`.
+pub trait KillPermission {
+    /// Checks if process `sender` may send `sig` to process `target`. `sig`
+    /// is `None` for the null signal, which only probes the target.
+    ///
+    /// Fails with [`SignalError::NoSuchProcess`] if a process doesn't exist,
+    /// and with [`SignalError::Permission`] if the signal is not allowed.
+    fn check_kill(&self, sender: u32, target: u32, sig: Option<&SignalInfo>) -> SignalResult;
+}
+
+/// Source of the credentials of processes, provided by the OS.
+pub trait CredentialsProvider {
+    /// Gets the credentials of process `pid`. `cap_kill` may be relative to
+    /// another process when user namespaces are supported.
+    fn credentials(&self, pid: u32) -> Option<Credentials>;
+}
+
+/// The Linux rules of kill permission, over the credentials from `P`.
+///
+/// A signal is allowed if:
+/// - it is generated by the kernel (`si_code > 0`), or sent within the same
+///   process;
+/// - the real or effective user ID of the sender matches the real or saved
+///   user ID of the target;
+/// - the sender has `CAP_KILL`; or
+/// - it is `SIGCONT` and both processes are in the same session.
+///
+/// A signal info from user space must have passed
+/// [`SignalInfo::check_user_code`] first: its `si_code` is trusted here.
+#[derive(Debug, Clone, Default)]
+pub struct LinuxKillPermission<P>(pub P);
+
+impl<P: CredentialsProvider> KillPermission for LinuxKillPermission<P> {
+    fn check_kill(&self, sender: u32, target: u32, sig: Option<&SignalInfo>) -> SignalResult {
+        let target_cred = self
+            .0
+            .credentials(target)
+            .ok_or(SignalError::NoSuchProcess)?;
+        if sig.is_some_and(|sig| sig.code() > 0) || sender == target {
+            return Ok(());
+        }
+        let cred = self
+            .0
+            .credentials(sender)
+            .ok_or(SignalError::NoSuchProcess)?;
+        if [cred.uid, cred.euid]
+            .iter()
+            .any(|id| [target_cred.uid, target_cred.suid].contains(id))
+            || cred.cap_kill
+        {
+            return Ok(());
+        }
+        if sig.is_some_and(|sig| sig.signo() == Signo::SIGCONT) && cred.sid == target_cred.sid {
+            return Ok(());
+        }
+        Err(SignalError::Permission)
+    }
+}
+
+/// Sends a signal from process `sender` to process `target`, as `kill` and
+/// `rt_sigqueueinfo` do once the OS has looked up `proc`, the manager of
+/// `target`. The permission is checked with `perm`.
+///
+/// `sig` is `None` for the null signal (see [`Signo::try_from_nullable`]):
+/// the target is probed with the same checks, but nothing is queued and
+/// [`SignalWake::None`] is returned, as `kill(pid, 0)` relies on. Fails with
+/// [`SignalError::NoSuchProcess`] if `proc` is `None`.
+pub fn kill_process(
+    perm: &dyn KillPermission,
+    sender: u32,
+    target: u32,
+    proc: Option<&ProcessSignalManager>,
+    sig: Option<SignalInfo>,
+) -> SignalResult<SignalWake> {
+    let proc = proc.ok_or(SignalError::NoSuchProcess)?;
+    perm.check_kill(sender, target, sig.as_ref())?;
+    Ok(sig.map_or(SignalWake::None, |sig| proc.send_signal(sig)))
+}
+
+/// Sends a signal from process `sender` to a thread of process `target`, as
+/// `tgkill` and `rt_tgsigqueueinfo` do, returning whether the thread needs to
+/// be woken up.
+///
+/// As with [`kill_process`], `sig` is `None` for the null signal, which
+/// `pthread_kill(thread, 0)` uses to probe the thread. Fails with
+/// [`SignalError::NoSuchProcess`] if `thread` is `None`.
+pub fn kill_thread(
+    perm: &dyn KillPermission,
+    sender: u32,
+    target: u32,
+    thread: Option<&ThreadSignalManager>,
+    sig: Option<SignalInfo>,
+) -> SignalResult<bool> {
+    let thread = thread.ok_or(SignalError::NoSuchProcess)?;
+    perm.check_kill(sender, target, sig.as_ref())?;
+    Ok(sig.is_some_and(|sig| thread.send_signal(sig)))
+}
@@ -3,25 +3,87 @@ use alloc::{
     vec::Vec,
 };
 use core::{
-    array,
+    array, mem,
     ops::{Index, IndexMut},
-    sync::atomic::{AtomicBool, Ordering},
+    slice,
+    time::Duration,
 };
 
-use kspin::SpinNoIrq;
+use bitflags::bitflags;
+use event_listener::{Event, EventListener};
+use linux_raw_sys::general::{WCONTINUED, WNOWAIT, WSTOPPED};
+use strum::IntoEnumIterator;
 
+#[cfg(feature = "journal")]
+use crate::journal::{JournalEntry, JournalEvent};
+#[cfg(feature = "lock-stats")]
+use crate::lockstat::{LockCounters, LockStats};
+#[cfg(feature = "tracepoint")]
+use crate::tracepoint::{GenerateResult, SignalGenerate};
 use crate::{
-    DefaultSignalAction, PendingSignals, SignalAction, SignalActionFlags, SignalDisposition,
-    SignalInfo, SignalSet, Signo, api::ThreadSignalManager,
+    ChildCode, DefaultSignalAction, LibcRtReserve, NSIG, PendingSignals, RestartKind,
+    RestartPolicy, SignalAction, SignalActionFlags, SignalDisposition, SignalError, SignalInfo,
+    SignalResult, SignalSet, Signo, SleepRestart,
+    api::{
+        CpuItimer, CpuTimer, Itimer, PressureThresholds, RealItimer, SignalEventListener,
+        SignalFilter, SignalPressure, SignalPressureHook, SignalReadiness, ThreadSignalManager,
+        TimerSignalHook, UserStackHook, kick,
+    },
+    sync::{
+        SpinNoIrq, StatLock,
+        atomic::{AtomicBool, AtomicU8, AtomicU64, AtomicUsize, Ordering},
+    },
 };
 
 /// Signal actions for a process.
+///
+/// Cloning the table is cheap: the entries are shared until either copy is
+/// modified.
 #[derive(Clone)]
-pub struct SignalActions(pub(crate) [SignalAction; 64]);
+pub struct SignalActions(pub(crate) Arc<[SignalAction; NSIG]>);
 
 impl Default for SignalActions {
     fn default() -> Self {
-        Self(array::from_fn(|_| SignalAction::default()))
+        Self(Arc::new(array::from_fn(|_| SignalAction::default())))
+    }
+}
+
+impl SignalActions {
+    /// Creates an independent copy of the table, copying the entries
+    /// immediately instead of on the first modification.
+    pub fn deep_clone(&self) -> Self {
+        Self(Arc::new((*self.0).clone()))
+    }
+
+    /// Replaces the whole table, returning the old one.
+    ///
+    /// The entries of `SIGKILL` and `SIGSTOP` are kept at the default
+    /// disposition, as `sigaction` can't change them.
+    pub fn replace(&mut self, mut new: SignalActions) -> SignalActions {
+        for signo in [Signo::SIGKILL, Signo::SIGSTOP] {
+            if !matches!(new[signo].disposition, SignalDisposition::Default) {
+                new[signo] = SignalAction::default();
+            }
+        }
+        mem::replace(self, new)
+    }
+
+    /// Resets the actions as required by `execve`.
+    ///
+    /// Caught signals are reset to the default disposition, while ignored
+    /// signals stay ignored. Flags, masks and restorers are cleared for every
+    /// signal.
+    pub fn reset_for_exec(&mut self) {
+        for action in Arc::make_mut(&mut self.0) {
+            let disposition = match action.disposition {
+                SignalDisposition::Ignore => SignalDisposition::Ignore,
+                _ => SignalDisposition::Default,
+            };
+            *action = SignalAction {
+                disposition,
+                ..SignalAction::default()
+            };
+        }
     }
 }
 
@@ -35,36 +97,291 @@ impl Index<Signo> for SignalActions {
 
 impl IndexMut<Signo> for SignalActions {
     fn index_mut(&mut self, signo: Signo) -> &mut SignalAction {
-        &mut self.0[signo as usize - 1]
+        &mut Arc::make_mut(&mut self.0)[signo as usize - 1]
     }
 }
 
+/// Computes the ignored and caught sets of an action table.
+pub(crate) fn actions_summary(actions: &SignalActions) -> (SignalSet, SignalSet) {
+    let mut ignored = SignalSet::default();
+    let mut caught = SignalSet::default();
+    for signo in Signo::iter() {
+        match actions[signo].disposition {
+            SignalDisposition::Ignore => {
+                ignored.add(signo);
+            }
+            SignalDisposition::Handler(_) | SignalDisposition::SigactionHandler(_) => {
+                caught.add(signo);
+            }
+            SignalDisposition::Default => {}
+        }
+    }
+    (ignored, caught)
+}
+
+#[cfg(feature = "tracepoint")]
+pub(crate) fn trace_generate(
+    sig: &SignalInfo,
+    tid: Option<u32>,
+    group: bool,
+    result: GenerateResult,
+) {
+    crate::tracepoint::emit(|tp| {
+        tp.signal_generate(&SignalGenerate {
+            signo: sig.signo(),
+            errno: sig.errno(),
+            code: sig.code(),
+            tid,
+            group,
+            result,
+        })
+    });
+}
+
+/// How a new process obtains its signal actions from the parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionsInheritance {
+    /// Share the parent's table, as with `CLONE_SIGHAND`.
+    Share,
+    /// Copy the parent's table immediately.
+    Copy,
+    /// Copy the parent's table lazily, when either side first modifies it.
+    CopyOnWrite,
+}
+
+/// Threads to wake up after sending a process-directed signal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignalWake {
+    /// No thread can take the signal now.
+    None,
+    /// The thread that will take the signal.
+    One(u32),
+    /// All threads of the process, as the signal affects every one of them:
+    /// it kills the process or stops it as a group. The thread that will
+    /// take the signal comes first. With `linux-strict`, these are also the
+    /// threads resumed from group-stop by `SIGCONT`.
+    All(Vec<u32>),
+}
+
+impl SignalWake {
+    /// Checks if no thread needs to be woken up.
+    pub fn is_none(&self) -> bool {
+        matches!(self, Self::None)
+    }
+
+    /// Gets the threads to wake up.
+    pub fn tids(&self) -> &[u32] {
+        match self {
+            Self::None => &[],
+            Self::One(tid) => slice::from_ref(tid),
+            Self::All(tids) => tids,
+        }
+    }
+}
+
+bitflags! {
+    /// Options of `wait` concerning stop and continue events. Other bits are
+    /// dropped by [`WaitOptions::from_bits_truncate`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct WaitOptions: u32 {
+        /// Report stopped children (`WUNTRACED`/`WSTOPPED`).
+        const STOPPED = WSTOPPED;
+        /// Report continued children (`WCONTINUED`).
+        const CONTINUED = WCONTINUED;
+        /// Leave the event to be reported again (`WNOWAIT`).
+        const NOWAIT = WNOWAIT;
+    }
+}
+
+/// A stop or continue of the process not yet reported to `wait`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopEvent {
+    /// The process was stopped by the signal.
+    Stopped(Signo),
+    /// The process was continued by `SIGCONT`.
+    Continued,
+}
+
+/// Kind of stop a thread is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopKind {
+    /// Job-control stop of the whole process by a stop signal, ended by
+    /// `SIGCONT`.
+    Group,
+    /// `ptrace` stop, ended by the tracer, e.g. with `PTRACE_CONT`.
+    Trace,
+}
+
+#[derive(Default)]
+struct StopState {
+    /// The signal the process is stopped by, if stopped.
+    stopped: Option<Signo>,
+    /// The last stop or continue, until reported.
+    event: Option<StopEvent>,
+    /// The threads in a stop, and the kind of it.
+    threads: Vec<(u32, StopKind)>,
+}
+
+/// Process-level signal state exported for checkpointing.
+///
+/// See [`ProcessSignalManager::export_state`].
+#[derive(Clone)]
+pub struct ProcessSignalState {
+    /// The signal actions.
+    pub actions: SignalActions,
+    /// The shared pending signals, in delivery order.
+    pub pending: Vec<SignalInfo>,
+    /// The default restorer function.
+    pub default_restorer: usize,
+    /// The signal the process is stopped by, if stopped.
+    pub stopped: Option<Signo>,
+    /// The stop or continue not yet reported to `wait`.
+    pub stop_event: Option<StopEvent>,
+}
+
+/// State of a POSIX timer that sent a signal.
+struct TimerState {
+    id: i32,
+    /// Whether a signal of the timer may be pending.
+    queued: bool,
+    /// Overrun count of the last delivered expiry.
+    overrun: i32,
+}
+
+/// Metrics of the locks of a process, from
+/// [`ProcessSignalManager::lock_stats`].
+#[cfg(feature = "lock-stats")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessLockStats {
+    /// The shared pending queue.
+    pub pending: LockStats,
+    /// The action table.
+    pub actions: LockStats,
+}
+
 /// Process-level signal manager.
 pub struct ProcessSignalManager {
     /// The process-level shared pending signals
-    pending: SpinNoIrq<PendingSignals>,
+    pub(crate) pending: StatLock<PendingSignals>,
 
-    /// The signal actions
-    pub actions: Arc<SpinNoIrq<SignalActions>>,
+    /// The signal actions, possibly shared with other processes. Replaced
+    /// with a copy when unshared.
+    actions: SpinNoIrq<Arc<SpinNoIrq<SignalActions>>>,
+    /// Metrics of the acquisitions of `actions` by the process.
+    #[cfg(feature = "lock-stats")]
+    actions_stats: LockCounters,
 
     /// The default restorer function.
-    pub(crate) default_restorer: usize,
+    pub(crate) default_restorer: AtomicUsize,
 
     /// Thread-level signal managers.
     pub(crate) children: SpinNoIrq<Vec<(u32, Weak<ThreadSignalManager>)>>,
+    /// Index in `children` where the search for a thread to take the next
+    /// process-directed signal starts, so that signals are spread across
+    /// threads.
+    next_target: AtomicUsize,
 
     pub(crate) possibly_has_signal: AtomicBool,
+
+    /// The CPU time in seconds at which the next `SIGXCPU` is due.
+    next_xcpu: AtomicU64,
+    /// Number of realtime signals reserved by the libc of the process.
+    libc_rt_reserve: AtomicU8,
+    /// Maximum number of queued instances of each real-time signal, in the
+    /// pending queues of the process and of its threads.
+    rt_queue_limit: AtomicUsize,
+    /// Whether standard signals are delivered in arrival order.
+    fifo_order: AtomicBool,
+    /// Secret mixed into the magic of signal frames.
+    frame_cookie: AtomicU64,
+    /// State of the POSIX timers that sent a signal.
+    timers: SpinNoIrq<Vec<TimerState>>,
+    /// POSIX timers on CPU-time clocks, by timer ID.
+    pub(crate) cpu_timers: SpinNoIrq<Vec<(i32, CpuTimer)>>,
+    /// `ITIMER_VIRTUAL` and `ITIMER_PROF`, indexed by [`CpuItimer`].
+    pub(crate) cpu_itimers: SpinNoIrq<[Itimer; 2]>,
+    /// `ITIMER_REAL`.
+    real_itimer: SpinNoIrq<RealItimer>,
+
+    /// Whether the process is frozen.
+    frozen: AtomicBool,
+    /// Whether the process is exiting, after which signals are discarded.
+    exiting: AtomicBool,
+    /// Whether the process is stopped, and the event to report to `wait`.
+    stop: SpinNoIrq<StopState>,
+    /// Notified when the process is thawed.
+    thaw_event: Event,
+    /// Notified when a signal is sent to the process or any of its threads.
+    pub(crate) arrival: Event,
+
+    /// The registered event listener.
+    listener: SpinNoIrq<Option<Arc<dyn SignalEventListener>>>,
+    /// Whether an event listener is registered, checked before taking its
+    /// lock on the send paths.
+    has_listener: AtomicBool,
+    /// The registered security filter.
+    filter: SpinNoIrq<Option<Arc<dyn SignalFilter>>>,
+    /// The registered pressure hook and its thresholds.
+    pressure: SpinNoIrq<Option<(Arc<dyn SignalPressureHook>, PressureThresholds)>>,
+    /// Whether a pressure hook is registered.
+    has_pressure: AtomicBool,
+    /// Whether the total number of queued signals was last seen over its
+    /// threshold.
+    pressure_high: AtomicBool,
+    /// The registered user stack hook.
+    stack_hook: SpinNoIrq<Option<Arc<dyn UserStackHook>>>,
+    /// The registered timer hook.
+    timer_hook: SpinNoIrq<Option<Arc<dyn TimerSignalHook>>>,
+    /// The registered readiness consumers.
+    readiness: SpinNoIrq<Vec<Weak<dyn SignalReadiness>>>,
+    /// Whether any readiness consumer is registered.
+    has_readiness: AtomicBool,
+
+    #[cfg(feature = "journal")]
+    journal: SpinNoIrq<crate::journal::SignalJournal>,
 }
 
 impl ProcessSignalManager {
     /// Creates a new process signal manager.
     pub fn new(actions: Arc<SpinNoIrq<SignalActions>>, default_restorer: usize) -> Self {
         Self {
-            pending: SpinNoIrq::new(PendingSignals::default()),
-            actions,
-            default_restorer,
+            pending: StatLock::new(PendingSignals::default()),
+            actions: SpinNoIrq::new(actions),
+            #[cfg(feature = "lock-stats")]
+            actions_stats: LockCounters::default(),
+            default_restorer: AtomicUsize::new(default_restorer),
             children: SpinNoIrq::new(Vec::new()),
+            next_target: AtomicUsize::new(0),
             possibly_has_signal: AtomicBool::new(false),
+            next_xcpu: AtomicU64::new(0),
+            libc_rt_reserve: AtomicU8::new(0),
+            rt_queue_limit: AtomicUsize::new(usize::MAX),
+            fifo_order: AtomicBool::new(false),
+            frame_cookie: AtomicU64::new(0),
+            timers: SpinNoIrq::new(Vec::new()),
+            cpu_timers: SpinNoIrq::new(Vec::new()),
+            cpu_itimers: SpinNoIrq::new([Itimer::default(); 2]),
+            real_itimer: SpinNoIrq::new(RealItimer::default()),
+
+            frozen: AtomicBool::new(false),
+            exiting: AtomicBool::new(false),
+            stop: SpinNoIrq::new(StopState::default()),
+            thaw_event: Event::new(),
+            arrival: Event::new(),
+
+            listener: SpinNoIrq::new(None),
+            has_listener: AtomicBool::new(false),
+            filter: SpinNoIrq::new(None),
+            pressure: SpinNoIrq::new(None),
+            has_pressure: AtomicBool::new(false),
+            pressure_high: AtomicBool::new(false),
+            stack_hook: SpinNoIrq::new(None),
+            timer_hook: SpinNoIrq::new(None),
+            readiness: SpinNoIrq::new(Vec::new()),
+            has_readiness: AtomicBool::new(false),
+
+            #[cfg(feature = "journal")]
+            journal: SpinNoIrq::new(Default::default()),
         }
     }
 
@@ -77,9 +394,270 @@ impl ProcessSignalManager {
         result
     }
 
+    /// Tracks the signal of a POSIX timer in flight, so that each timer has
+    /// at most one.
+    ///
+    /// Returns `true` if `sig` reports an expiry of a timer whose signal is
+    /// still pending, in which case the expiry has been counted in its
+    /// overrun and `sig` must not be queued.
+    pub(crate) fn coalesce_timer(&self, sig: &SignalInfo) -> bool {
+        if !sig.is_timer() {
+            return false;
+        }
+        let queued = self
+            .timers
+            .lock()
+            .iter()
+            .any(|t| t.id == sig.timer_id() && t.queued);
+        // `timers` is released before the pending queues are locked, as
+        // `on_dequeue` takes it after dequeuing.
+        if queued {
+            let mut coalesced = self.pending.lock().coalesce_timer(sig);
+            if !coalesced {
+                self.for_each_thread(|thread| {
+                    coalesced = coalesced || thread.pending.lock().coalesce_timer(sig);
+                });
+            }
+            // Otherwise the signal has been discarded, e.g. when it got
+            // ignored, and a new one is queued.
+            if coalesced {
+                return true;
+            }
+        }
+        let mut timers = self.timers.lock();
+        match timers.iter_mut().find(|t| t.id == sig.timer_id()) {
+            Some(timer) => timer.queued = true,
+            None => timers.push(TimerState {
+                id: sig.timer_id(),
+                queued: true,
+                overrun: 0,
+            }),
+        }
+        false
+    }
+
+    /// Records the delivery of a signal dequeued by one of the threads.
+    pub(crate) fn on_dequeue(&self, sig: &SignalInfo) {
+        if !sig.is_timer() {
+            return;
+        }
+        {
+            let mut timers = self.timers.lock();
+            let state = TimerState {
+                id: sig.timer_id(),
+                queued: false,
+                overrun: sig.overrun(),
+            };
+            match timers.iter_mut().find(|t| t.id == state.id) {
+                Some(timer) => *timer = state,
+                None => timers.push(state),
+            }
+        }
+        if let Some(hook) = self.timer_hook.lock().clone() {
+            hook.on_dequeue(sig.timer_id(), sig.overrun());
+        }
+    }
+
+    /// Gets the overrun count of the last delivered expiry of POSIX timer
+    /// `timer_id`, as in `timer_getoverrun`.
+    ///
+    /// Expiries of a timer whose signal is still pending are not queued, but
+    /// counted in the overrun of the pending signal, reported in its
+    /// `si_overrun` on delivery.
+    pub fn timer_overrun(&self, timer_id: i32) -> i32 {
+        self.timers
+            .lock()
+            .iter()
+            .find(|t| t.id == timer_id)
+            .map_or(0, |t| t.overrun)
+    }
+
+    /// Forgets the state of POSIX timer `timer_id`, when it is deleted.
+    pub fn forget_timer(&self, timer_id: i32) {
+        self.timers.lock().retain(|t| t.id != timer_id);
+    }
+
+    /// Checks the internal invariants of the process, panicking if any is
+    /// broken.
+    #[cfg(debug_assertions)]
+    pub(crate) fn assert_invariants(&self) {
+        {
+            let pending = self.pending.lock();
+            assert!(
+                pending.set.is_empty() || self.possibly_has_signal.load(Ordering::Acquire),
+                "signals {:?} pending on the process but not flagged",
+                pending.set
+            );
+        }
+        let children = self.children.lock();
+        for (i, (tid, thread)) in children.iter().enumerate() {
+            assert!(
+                thread.strong_count() == 0
+                    || !children[i + 1..]
+                        .iter()
+                        .any(|(other, thread)| other == tid && thread.strong_count() > 0),
+                "thread {tid} has several signal managers"
+            );
+        }
+    }
+
+    /// Creates the action table for a child process.
+    ///
+    /// Threads created with `CLONE_THREAD` belong to the same process and
+    /// should reuse this manager instead.
+    pub fn inherit_actions(&self, mode: ActionsInheritance) -> Arc<SpinNoIrq<SignalActions>> {
+        match mode {
+            ActionsInheritance::Share => self.actions(),
+            ActionsInheritance::Copy => Arc::new(SpinNoIrq::new(
+                self.with_actions(|actions| actions.deep_clone()),
+            )),
+            ActionsInheritance::CopyOnWrite => {
+                Arc::new(SpinNoIrq::new(self.with_actions(|actions| actions.clone())))
+            }
+        }
+    }
+
+    /// Creates a signal manager for a child process, inheriting the action
+    /// table according to `mode` and the default restorer.
+    ///
+    /// Pending signals are not inherited.
+    pub fn new_child(&self, mode: ActionsInheritance) -> Self {
+        let child = Self::new(self.inherit_actions(mode), self.default_restorer());
+        // The child returns from the handlers the parent is running.
+        child.set_frame_cookie(self.frame_cookie());
+        child
+    }
+
+    /// Gets the default restorer function.
+    pub fn default_restorer(&self) -> usize {
+        self.default_restorer.load(Ordering::Relaxed)
+    }
+
+    /// Installs a complete action table at once, returning the old one, so
+    /// that no intermediate state is ever seen, as for the attributes of
+    /// `posix_spawn` or a checkpoint restore. See [`SignalActions::replace`].
+    ///
+    /// Pending signals that the new table ignores are discarded, as with
+    /// `sigaction`.
+    pub fn replace_actions(&self, actions: SignalActions) -> SignalActions {
+        let old = self.with_actions(|old| old.replace(actions));
+        let mut ignored = SignalSet::default();
+        for signo in Signo::iter().filter(|&signo| self.signal_ignored(signo)) {
+            ignored.add(signo);
+        }
+        self.remove_signals(&ignored);
+        old
+    }
+
+    /// Gives the process an action table of its own, a copy of the current
+    /// one, so that changing it no longer affects the processes it was
+    /// shared with through [`ActionsInheritance::Share`], as Linux's
+    /// `unshare_sighand`.
+    ///
+    /// Tables obtained with [`actions`](Self::actions) before are no longer
+    /// those of the process.
+    pub fn unshare_actions(&self) {
+        let mut table = self.actions.lock();
+        let copy = table.lock().clone();
+        *table = Arc::new(SpinNoIrq::new(copy));
+    }
+
+    /// Applies the signal semantics of `execve` to the process.
+    ///
+    /// The action table is unshared (see [`unshare_actions`]) and reset
+    /// with [`SignalActions::reset_for_exec`], and the default restorer is
+    /// replaced, since the old one belongs to the discarded address space.
+    /// Pending signals are kept.
+    ///
+    /// See [`ThreadSignalManager::on_exec`] for the thread-level part.
+    ///
+    /// [`unshare_actions`]: Self::unshare_actions
+    pub fn on_exec(&self, default_restorer: usize) {
+        self.unshare_actions();
+        self.with_actions(|actions| actions.reset_for_exec());
+        self.default_restorer
+            .store(default_restorer, Ordering::Relaxed);
+    }
+
+    /// Sets the secret mixed into the magic of signal frames, checked by
+    /// [`ThreadSignalManager::restore`].
+    ///
+    /// The OS should set a random value when creating a process from
+    /// scratch or on `execve`, so that user space can't forge a frame
+    /// without reading a genuine one. Children created with
+    /// [`new_child`](Self::new_child) inherit it. Defaults to `0`.
+    pub fn set_frame_cookie(&self, cookie: u64) {
+        self.frame_cookie.store(cookie, Ordering::Relaxed);
+    }
+
+    /// Gets the secret mixed into the magic of signal frames.
+    pub fn frame_cookie(&self) -> u64 {
+        self.frame_cookie.load(Ordering::Relaxed)
+    }
+
+    /// Caps the number of queued instances of each real-time signal, in the
+    /// pending queues of the process and of its threads, to bound the memory
+    /// used by a flood of signals. `usize::MAX` means no cap, which is the
+    /// default.
+    ///
+    /// Instances beyond the cap are dropped, or refused by
+    /// [`Self::try_send_signal`]. Instances already queued are kept.
+    pub fn set_rt_queue_limit(&self, limit: usize) {
+        self.rt_queue_limit.store(limit, Ordering::Relaxed);
+        self.pending.lock().set_rt_limit(limit);
+        self.for_each_thread(|thread| thread.pending.lock().set_rt_limit(limit));
+    }
+
+    /// Gets the cap on the number of queued instances of each real-time
+    /// signal.
+    pub fn rt_queue_limit(&self) -> usize {
+        self.rt_queue_limit.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether standard signals are delivered in the order they arrived
+    /// instead of lowest-numbered first, in the pending queues of the
+    /// process and of its threads. Off by default.
+    ///
+    /// The order holds within each queue: signals directed to a thread are
+    /// still taken before those directed to the process. See
+    /// [`PendingSignals::set_fifo`].
+    pub fn set_fifo_order(&self, fifo: bool) {
+        self.fifo_order.store(fifo, Ordering::Relaxed);
+        self.pending.lock().set_fifo(fifo);
+        self.for_each_thread(|thread| thread.pending.lock().set_fifo(fifo));
+    }
+
+    /// Checks if standard signals are delivered in arrival order.
+    pub fn fifo_order(&self) -> bool {
+        self.fifo_order.load(Ordering::Relaxed)
+    }
+
+    /// Creates an empty pending queue configured as the process asks.
+    pub(crate) fn new_pending(&self) -> PendingSignals {
+        let mut pending = PendingSignals::default();
+        pending.set_rt_limit(self.rt_queue_limit());
+        pending.set_fifo(self.fifo_order());
+        pending
+    }
+
+    /// Sets the realtime signals reserved by the libc of the process.
+    ///
+    /// The OS sets this after `execve` when it knows which libc the image
+    /// uses. It's only informational: reserved signals are sent and handled
+    /// like any other realtime signal.
+    pub fn set_libc_rt_reserve(&self, reserve: LibcRtReserve) {
+        self.libc_rt_reserve
+            .store(reserve.count(), Ordering::Relaxed);
+    }
+
+    /// Gets the realtime signals reserved by the libc of the process.
+    pub fn libc_rt_reserve(&self) -> LibcRtReserve {
+        LibcRtReserve::new(self.libc_rt_reserve.load(Ordering::Relaxed))
+    }
+
     /// Checks if a signal is ignored by the process.
     pub fn signal_ignored(&self, signo: Signo) -> bool {
-        match &self.actions.lock()[signo].disposition {
+        match self.with_actions(|actions| actions[signo].disposition.clone()) {
             SignalDisposition::Ignore => true,
             SignalDisposition::Default => {
                 matches!(signo.default_action(), DefaultSignalAction::Ignore)
@@ -88,44 +666,1044 @@ impl ProcessSignalManager {
         }
     }
 
+    /// Gets the signals explicitly set to be ignored.
+    ///
+    /// Signals ignored by default are not included, matching `SigIgn` in
+    /// procfs. Use [`Self::signal_ignored`] to check the effective
+    /// disposition.
+    pub fn ignored(&self) -> SignalSet {
+        self.with_actions(|actions| actions_summary(actions).0)
+    }
+
+    /// Gets the signals caught by a user handler.
+    pub fn caught(&self) -> SignalSet {
+        self.with_actions(|actions| actions_summary(actions).1)
+    }
+
     /// Checks if syscalls interrupted by the given signal can be restarted.
     pub fn can_restart(&self, signo: Signo) -> bool {
-        self.actions.lock()[signo]
-            .flags
+        self.with_actions(|actions| actions[signo].flags)
             .contains(SignalActionFlags::RESTART)
     }
 
+    /// Checks if a syscall with the given policy is restarted when
+    /// interrupted by the given signal.
+    ///
+    /// Unlike [`can_restart`](Self::can_restart), this also accounts for the
+    /// syscall itself and for signals that don't run a handler, after which
+    /// syscalls are always restarted.
+    pub fn restarts(&self, signo: Signo, policy: RestartPolicy) -> bool {
+        let action = self.with_actions(|actions| actions[signo].clone());
+        let handler_restart = action
+            .disposition
+            .is_handler()
+            .then(|| action.flags.contains(SignalActionFlags::RESTART));
+        RestartKind::from(policy).should_restart(handler_restart)
+    }
+
+    /// Decides how an interruptible sleep with the given policy ends when
+    /// interrupted by the given signal.
+    ///
+    /// `remaining` is the time left of a relative timeout, or `None` if the
+    /// sleep has no timeout or an absolute one. A sleep that is restarted
+    /// with time left goes through `restart_syscall`, so that the time
+    /// already slept is not slept again.
+    pub fn sleep_restart(
+        &self,
+        signo: Signo,
+        policy: RestartPolicy,
+        remaining: Option<Duration>,
+    ) -> SleepRestart {
+        if !self.restarts(signo, policy) {
+            return SleepRestart::Interrupted;
+        }
+        match remaining {
+            Some(remaining) => SleepRestart::RestartBlock(remaining),
+            None => SleepRestart::Restart,
+        }
+    }
+
     /// Sends a signal to the process.
     ///
-    /// Returns `Some(tid)` if the signal wakes up a thread.
+    /// Returns the threads to wake up: exactly one thread that can take the
+    /// signal, not exiting and not blocking it, or none if there is no such
+    /// thread. When several threads can take the signal, they are picked in
+    /// turn. If the picked thread blocks the signal or exits before taking
+    /// it, another one is kicked then (see
+    /// [`ThreadSignalManager::set_blocked`] and
+    /// [`ThreadSignalManager::exit_signals`]). If the signal kills or stops
+    /// the whole process, all threads are returned so that the OS can
+    /// interrupt them immediately.
+    ///
+    /// A hardware fault (see [`SignalInfo::is_fault`]) is never placed on
+    /// the shared queue, where another thread could take it: it is dropped
+    /// with a warning, and must be sent to the faulting thread with
+    /// [`ThreadSignalManager::send_fault`] instead.
     ///
     /// See [`ThreadSignalManager::send_signal`] for the thread-level version.
     #[must_use]
-    pub fn send_signal(&self, sig: SignalInfo) -> Option<u32> {
+    pub fn send_signal(&self, sig: SignalInfo) -> SignalWake {
+        // Never fails when the signal may be dropped.
+        self.send_signal_inner(sig, false)
+            .unwrap_or(SignalWake::None)
+    }
+
+    /// Like [`send_signal`](Self::send_signal), but fails with
+    /// [`SignalError::WouldBlock`] instead of dropping a real-time signal
+    /// whose queue is full, as `sigqueue` does with `EAGAIN`.
+    pub fn try_send_signal(&self, sig: SignalInfo) -> SignalResult<SignalWake> {
+        self.send_signal_inner(sig, true)
+    }
+
+    /// Sends a signal to the process, failing if `fail_if_full` and its
+    /// queue is full, checked under the same lock as the signal is queued.
+    fn send_signal_inner(&self, sig: SignalInfo, fail_if_full: bool) -> SignalResult<SignalWake> {
+        if self.is_exiting() || Self::misrouted_fault(&sig) {
+            return Ok(SignalWake::None);
+        }
         let signo = sig.signo();
+        #[cfg(feature = "linux-strict")]
+        let resumed = self.prepare_signal(signo);
+        #[cfg(not(feature = "linux-strict"))]
+        let resumed = Vec::new();
+        let listener = self.listener();
         if self.signal_ignored(signo) {
-            return None;
+            self.on_ignored(listener.as_deref(), &sig);
+            return Ok(Self::add_resumed(SignalWake::None, resumed));
         }
 
-        if self.pending.lock().put_signal(sig) {
-            self.possibly_has_signal.store(true, Ordering::Release);
+        #[cfg(feature = "tracepoint")]
+        let traced = sig.clone();
+        #[cfg(feature = "journal")]
+        let code = sig.code();
+        let notified = listener.as_ref().map(|_| sig.clone());
+        let pressure = self.pressure_hook();
+        let pressured = pressure.as_ref().map(|_| sig.clone());
+        let depth = if self.coalesce_timer(&sig) {
+            None
+        } else {
+            let mut pending = self.pending.lock();
+            let queued = if fail_if_full {
+                pending.try_put_signal(sig)?
+            } else {
+                pending.put_signal(sig)
+            };
+            // Set under the lock, so that the flag is never seen clear while
+            // the signal is queued.
+            if queued {
+                self.possibly_has_signal.store(true, Ordering::Release);
+            }
+            queued.then(|| pending.count(signo))
+        };
+        let queued = depth.is_some();
+        let mut signals = SignalSet::default();
+        signals.add(signo);
+        let result = self.select_target(&signals);
+        #[cfg(feature = "journal")]
+        self.journal
+            .lock()
+            .record(JournalEvent::Send, result.unwrap_or(0), Some(signo), code);
+        #[cfg(feature = "tracepoint")]
+        trace_generate(
+            &traced,
+            result,
+            true,
+            if queued {
+                GenerateResult::Delivered
+            } else {
+                GenerateResult::AlreadyPending
+            },
+        );
+        if let (Some(listener), Some(sig), true) = (listener, notified, queued) {
+            listener.on_send(result, &sig);
         }
-        let mut result = None;
-        self.children.lock().retain(|(tid, thread)| {
-            if let Some(thread) = thread.upgrade() {
-                if result.is_none() && !thread.signal_blocked(signo) {
-                    result = Some(*tid);
-                }
-                true
+        if let (Some(pressure), Some(sig), Some(depth)) = (pressure, pressured, depth) {
+            self.check_pressure(pressure, None, &sig, depth);
+        }
+        self.notify_ready(None, signo);
+        Ok(Self::add_resumed(
+            self.wake(result, self.affects_all_threads(signo)),
+            resumed,
+        ))
+    }
+
+    /// Sends several signals to the process at once.
+    ///
+    /// The signals are queued in order under a single acquisition of the
+    /// pending queue lock, and one wake decision is made for all of them:
+    /// a thread not blocking at least one of the signals, or all threads if
+    /// any of them kills or stops the process. Ignored signals and hardware
+    /// faults are discarded as with [`send_signal`](Self::send_signal).
+    #[must_use]
+    pub fn send_signals(&self, sigs: &[SignalInfo]) -> SignalWake {
+        if self.is_exiting() {
+            return SignalWake::None;
+        }
+        #[cfg(feature = "linux-strict")]
+        let resumed: Vec<_> = sigs
+            .iter()
+            .flat_map(|sig| self.prepare_signal(sig.signo()))
+            .collect();
+        #[cfg(not(feature = "linux-strict"))]
+        let resumed = Vec::new();
+        let listener = self.listener();
+        let mut all = false;
+        let ignored: Vec<bool> = sigs
+            .iter()
+            .map(|sig| {
+                let ignored = Self::misrouted_fault(sig) || self.signal_ignored(sig.signo());
+                all |= !ignored && self.affects_all_threads(sig.signo());
+                ignored
+            })
+            .collect();
+
+        let coalesced: Vec<bool> = sigs
+            .iter()
+            .zip(&ignored)
+            .map(|(sig, &ignored)| !ignored && self.coalesce_timer(sig))
+            .collect();
+
+        let mut signals = SignalSet::default();
+        let depths = {
+            let mut pending = self.pending.lock();
+            let depths: Vec<Option<usize>> = sigs
+                .iter()
+                .zip(&ignored)
+                .zip(&coalesced)
+                .map(|((sig, &ignored), &coalesced)| {
+                    if ignored {
+                        return None;
+                    }
+                    signals.add(sig.signo());
+                    (!coalesced && pending.put_signal(sig.clone()))
+                        .then(|| pending.count(sig.signo()))
+                })
+                .collect();
+            if depths.iter().any(Option::is_some) {
+                self.possibly_has_signal.store(true, Ordering::Release);
+            }
+            depths
+        };
+
+        let result = self.select_target(&signals);
+        let pressure = self.pressure_hook();
+        for ((sig, ignored), depth) in sigs.iter().zip(ignored).zip(depths) {
+            if ignored {
+                self.on_ignored(listener.as_deref(), sig);
+            } else {
+                self.on_sent(listener.as_deref(), sig, result, depth.is_some());
+                self.notify_ready(None, sig.signo());
+            }
+            if let (Some(pressure), Some(depth)) = (&pressure, depth) {
+                self.check_pressure(pressure.clone(), None, sig, depth);
+            }
+        }
+        Self::add_resumed(self.wake(result, all), resumed)
+    }
+
+    /// Applies the interaction of stop signals and `SIGCONT` when one is
+    /// sent, as Linux does whether it is blocked or ignored: a stop signal
+    /// discards the pending `SIGCONT`, and `SIGCONT` continues the process
+    /// (see [`cont_threads`](Self::cont_threads)), discarding the pending
+    /// stop signals.
+    ///
+    /// Returns the threads leaving group-stop, which the OS should resume.
+    #[cfg(feature = "linux-strict")]
+    pub(crate) fn prepare_signal(&self, signo: Signo) -> Vec<u32> {
+        if signo == Signo::SIGCONT {
+            return self.cont_inner().unwrap_or_default();
+        } else if signo.is_stop() {
+            self.remove_signal(Signo::SIGCONT);
+        }
+        Vec::new()
+    }
+
+    /// Adds the threads resumed by `SIGCONT` to a wake decision, kicking
+    /// those not in it through the [`ReschedHook`](super::ReschedHook).
+    fn add_resumed(wake: SignalWake, resumed: Vec<u32>) -> SignalWake {
+        if resumed.is_empty() {
+            return wake;
+        }
+        let mut tids = wake.tids().to_vec();
+        let kicked = tids.len();
+        for tid in resumed {
+            if !tids.contains(&tid) {
+                tids.push(tid);
+            }
+        }
+        kick(&tids[kicked..]);
+        SignalWake::All(tids)
+    }
+
+    /// Checks if a signal is a hardware fault, which only the faulting thread
+    /// may take, sent to the process by mistake.
+    fn misrouted_fault(sig: &SignalInfo) -> bool {
+        let fault = sig.is_fault();
+        if fault {
+            warn!(
+                "fault signal {:?} sent to a process is dropped, it must be sent to the faulting \
+                 thread",
+                sig.signo()
+            );
+        }
+        fault
+    }
+
+    fn on_ignored(&self, listener: Option<&dyn SignalEventListener>, sig: &SignalInfo) {
+        #[cfg(feature = "journal")]
+        self.record_event(JournalEvent::Ignore, 0, Some(sig));
+        #[cfg(feature = "tracepoint")]
+        trace_generate(sig, None, true, GenerateResult::Ignored);
+        if let Some(listener) = listener {
+            listener.on_ignore(None, sig);
+        }
+    }
+
+    fn on_sent(
+        &self,
+        listener: Option<&dyn SignalEventListener>,
+        sig: &SignalInfo,
+        tid: Option<u32>,
+        queued: bool,
+    ) {
+        #[cfg(feature = "journal")]
+        self.journal.lock().record(
+            JournalEvent::Send,
+            tid.unwrap_or(0),
+            Some(sig.signo()),
+            sig.code(),
+        );
+        #[cfg(feature = "tracepoint")]
+        trace_generate(
+            sig,
+            tid,
+            true,
+            if queued {
+                GenerateResult::Delivered
             } else {
-                false
+                GenerateResult::AlreadyPending
+            },
+        );
+        if let (Some(listener), true) = (listener, queued) {
+            listener.on_send(tid, sig);
+        }
+    }
+
+    /// Builds the wake decision for a signal taken by `target`, and kicks the
+    /// threads through the [`ReschedHook`](super::ReschedHook).
+    fn wake(&self, target: Option<u32>, all: bool) -> SignalWake {
+        let wake = match target {
+            None => SignalWake::None,
+            Some(tid) if all => {
+                let mut tids = self.thread_ids();
+                tids.retain(|&other| other != tid);
+                tids.insert(0, tid);
+                SignalWake::All(tids)
+            }
+            Some(tid) => SignalWake::One(tid),
+        };
+        kick(wake.tids());
+        wake
+    }
+
+    /// Checks if a signal kills or stops the whole process when delivered.
+    fn affects_all_threads(&self, signo: Signo) -> bool {
+        if signo.is_unblockable() {
+            return true;
+        }
+        matches!(
+            self.with_actions(|actions| actions[signo].disposition.clone()),
+            SignalDisposition::Default
+        ) && (signo.is_fatal_by_default() || signo.is_stop())
+    }
+
+    /// Discards the pending instances of a signal, both in the shared queue
+    /// and in the queue of every thread, e.g. when the signal becomes
+    /// ignored.
+    ///
+    /// Returns `true` if any instance was discarded.
+    pub fn remove_signal(&self, signo: Signo) -> bool {
+        let mut mask = SignalSet::default();
+        mask.add(signo);
+        self.remove_signals(&mask)
+    }
+
+    /// Discards the pending stop signals, both in the shared queue and in
+    /// the queue of every thread, so that they don't stop the process again
+    /// after `SIGCONT`.
+    ///
+    /// Returns `true` if any signal was discarded.
+    pub fn flush_stop_signals(&self) -> bool {
+        self.remove_signals(&SignalSet::stop())
+    }
+
+    fn remove_signals(&self, mask: &SignalSet) -> bool {
+        let mut removed = self.pending.lock().remove_signals(mask);
+        self.for_each_thread(|thread| removed |= thread.pending.lock().remove_signals(mask));
+        removed
+    }
+
+    /// Tears down the signal state when the process exits.
+    ///
+    /// Pending signals of the process and of its threads are discarded,
+    /// their event flags are cleared, any stop and its unreported event are
+    /// forgotten, and the entries of exited threads are dropped. Signals sent afterwards are discarded too:
+    /// [`send_signal`](Self::send_signal) returns [`SignalWake::None`] and
+    /// [`ThreadSignalManager::send_signal`] returns `false`.
+    pub fn clear_all(&self) {
+        self.exiting.store(true, Ordering::Release);
+        {
+            let mut pending = self.pending.lock();
+            pending.remove_signals(&!SignalSet::default());
+            self.possibly_has_signal.store(false, Ordering::Release);
+        }
+        *self.stop.lock() = StopState::default();
+        self.children
+            .lock()
+            .retain(|(_, thread)| thread.strong_count() > 0);
+        self.for_each_thread(ThreadSignalManager::clear);
+    }
+
+    /// Checks if [`clear_all`](Self::clear_all) has been called.
+    pub fn is_exiting(&self) -> bool {
+        self.exiting.load(Ordering::Acquire)
+    }
+
+    /// Calls `f` on every live thread of the process.
+    pub(crate) fn for_each_thread(&self, mut f: impl FnMut(&ThreadSignalManager)) {
+        for (_, thread) in self.children.lock().iter() {
+            if let Some(thread) = thread.upgrade() {
+                f(&thread);
+            }
+        }
+    }
+
+    /// Gets a live thread of the process.
+    pub(crate) fn thread(&self, tid: u32) -> Option<Arc<ThreadSignalManager>> {
+        self.children
+            .lock()
+            .iter()
+            .find(|(id, _)| *id == tid)
+            .and_then(|(_, thread)| thread.upgrade())
+    }
+
+    /// Gets the IDs of the live threads of the process.
+    fn thread_ids(&self) -> Vec<u32> {
+        self.children
+            .lock()
+            .iter()
+            .filter(|(_, thread)| thread.strong_count() > 0)
+            .map(|(tid, _)| *tid)
+            .collect()
+    }
+
+    /// Picks the thread to wake for process-directed signals: the first one
+    /// not exiting and not blocking any of them, starting after the one
+    /// picked last time.
+    fn select_target(&self, signals: &SignalSet) -> Option<u32> {
+        let mut children = self.children.lock();
+        children.retain(|(_, thread)| thread.strong_count() > 0);
+        let len = children.len();
+        let start = self.next_target.load(Ordering::Relaxed);
+        (0..len).map(|i| (start + i) % len).find_map(|i| {
+            let (tid, thread) = &children[i];
+            let thread = thread.upgrade()?;
+            if thread.is_exiting() || (*signals & !thread.blocked()).is_empty() {
+                return None;
             }
+            self.next_target.store(i + 1, Ordering::Relaxed);
+            Some(*tid)
+        })
+    }
+
+    /// Checks the CPU time used by the process against `RLIMIT_CPU`.
+    ///
+    /// `soft` and `hard` are the limits in seconds, with `u64::MAX` meaning
+    /// unlimited. Returns the signal the caller should send to the process:
+    /// `SIGKILL` once the hard limit is reached, otherwise `SIGXCPU` when the
+    /// soft limit is reached and then once every further second of CPU time.
+    pub fn on_cputime_tick(&self, used: Duration, soft: u64, hard: u64) -> Option<Signo> {
+        let used = used.as_secs();
+        if used >= hard {
+            return Some(Signo::SIGKILL);
+        }
+        if used < soft {
+            return None;
+        }
+        let next = self.next_xcpu.load(Ordering::Relaxed).max(soft);
+        if used < next {
+            return None;
+        }
+        self.next_xcpu.store(used + 1, Ordering::Relaxed);
+        Some(Signo::SIGXCPU)
+    }
+
+    /// Sets POSIX timer `timer_id` on a CPU-time clock, as in
+    /// `timer_settime`, returning the previous setting.
+    ///
+    /// The timer is expired by [`CpuTimeTick::on_task_tick`].
+    ///
+    /// [`CpuTimeTick::on_task_tick`]: super::CpuTimeTick::on_task_tick
+    pub fn set_cpu_timer(&self, timer_id: i32, timer: CpuTimer) -> Option<CpuTimer> {
+        let mut timers = self.cpu_timers.lock();
+        match timers.iter_mut().find(|(id, _)| *id == timer_id) {
+            Some((_, old)) => Some(mem::replace(old, timer)),
+            None => {
+                timers.push((timer_id, timer));
+                None
+            }
+        }
+    }
+
+    /// Gets the setting of POSIX timer `timer_id` on a CPU-time clock.
+    pub fn cpu_timer(&self, timer_id: i32) -> Option<CpuTimer> {
+        self.cpu_timers
+            .lock()
+            .iter()
+            .find(|(id, _)| *id == timer_id)
+            .map(|(_, timer)| timer.clone())
+    }
+
+    /// Deletes POSIX timer `timer_id` on a CPU-time clock, as in
+    /// `timer_delete`.
+    pub fn delete_cpu_timer(&self, timer_id: i32) -> Option<CpuTimer> {
+        self.forget_timer(timer_id);
+        let mut timers = self.cpu_timers.lock();
+        let index = timers.iter().position(|(id, _)| *id == timer_id)?;
+        Some(timers.swap_remove(index).1)
+    }
+
+    /// Sets an interval timer driven by CPU time, as in `setitimer`,
+    /// returning the previous setting.
+    ///
+    /// The timer counts the time charged with [`CpuTimeAccounting::charge`].
+    ///
+    /// [`CpuTimeAccounting::charge`]: super::CpuTimeAccounting::charge
+    pub fn set_cpu_itimer(&self, which: CpuItimer, itimer: Itimer) -> Itimer {
+        mem::replace(&mut self.cpu_itimers.lock()[which as usize], itimer)
+    }
+
+    /// Gets an interval timer driven by CPU time, as in `getitimer`.
+    pub fn cpu_itimer(&self, which: CpuItimer) -> Itimer {
+        self.cpu_itimers.lock()[which as usize]
+    }
+
+    /// Sets `ITIMER_REAL`, as in `setitimer`, returning the previous
+    /// setting. `now` is the reading of the wall clock the OS runs the timer
+    /// on.
+    ///
+    /// The OS arms its timer for [`Self::real_itimer_deadline`] and calls
+    /// [`Self::expire_real_itimer`] when it fires.
+    pub fn set_real_itimer(&self, itimer: Itimer, now: Duration) -> Itimer {
+        let mut real = self.real_itimer.lock();
+        let old = real.get(now);
+        *real = RealItimer::new(itimer, now);
+        old
+    }
+
+    /// Gets `ITIMER_REAL`, as in `getitimer`.
+    pub fn real_itimer(&self, now: Duration) -> Itimer {
+        self.real_itimer.lock().get(now)
+    }
+
+    /// Gets the wall clock reading at which `ITIMER_REAL` expires next, if
+    /// it is armed.
+    pub fn real_itimer_deadline(&self) -> Option<Duration> {
+        self.real_itimer.lock().deadline()
+    }
+
+    /// Expires `ITIMER_REAL` if it is due at `now`, sending `SIGALRM`.
+    ///
+    /// If the previous `SIGALRM` is still pending, the expiry is folded into
+    /// it rather than queued. A periodic timer is rearmed on its original
+    /// schedule, skipping the periods already past; the OS should arm its
+    /// timer again for [`Self::real_itimer_deadline`]. Nothing happens if the
+    /// timer is not due, e.g. because it was set again meanwhile.
+    ///
+    /// Returns the threads to wake up.
+    #[must_use]
+    pub fn expire_real_itimer(&self, now: Duration) -> SignalWake {
+        if !self.real_itimer.lock().expire(now) {
+            return SignalWake::None;
+        }
+        self.send_signal(SignalInfo::new_kernel(Signo::SIGALRM))
+    }
+
+    /// Posts a kernel-private notification to every thread of the process.
+    ///
+    /// See [`ThreadSignalManager::notify`].
+    pub fn notify_all(&self, id: u32) -> SignalResult<()> {
+        if id >= u64::BITS {
+            return Err(SignalError::InvalidArgument);
+        }
+        let mut tids = Vec::new();
+        self.for_each_thread(|thread| {
+            thread.post_notification(1 << id);
+            tids.push(thread.tid());
         });
-        result
+        self.arrival.notify(usize::MAX);
+        kick(&tids);
+        Ok(())
+    }
+
+    /// Marks the process as stopped by a signal, when the OS stops it after
+    /// [`SignalOSAction::Stop`].
+    ///
+    /// Returns `false` if the process was already stopped, in which case no
+    /// new event is recorded. Each thread then records itself in group-stop
+    /// with [`ThreadSignalManager::enter_group_stop`] as it stops.
+    ///
+    /// [`SignalOSAction::Stop`]: crate::SignalOSAction::Stop
+    pub fn stop(&self, signo: Signo) -> bool {
+        let mut state = self.stop.lock();
+        if state.stopped.is_some() {
+            return false;
+        }
+        state.stopped = Some(signo);
+        state.event = Some(StopEvent::Stopped(signo));
+        true
+    }
+
+    /// Marks the process as continued, when `SIGCONT` is sent to it.
+    ///
+    /// Pending stop signals are discarded even if the process is not
+    /// stopped. Returns `false` if the process was not stopped, in which
+    /// case no event is recorded. With the `linux-strict` feature, sending
+    /// `SIGCONT` calls it.
+    ///
+    /// Threads in group-stop leave it; see [`cont_threads`](Self::cont_threads)
+    /// to know which.
+    pub fn cont(&self) -> bool {
+        self.cont_inner().is_some()
+    }
+
+    /// Like [`cont`](Self::cont), but returns the threads leaving group-stop,
+    /// which the OS should resume, or `None` if the process was not stopped.
+    ///
+    /// Threads in `ptrace` stop stay stopped until the tracer resumes them
+    /// with [`ThreadSignalManager::trace_cont`].
+    pub fn cont_threads(&self) -> Option<Vec<u32>> {
+        self.cont_inner()
+    }
+
+    fn cont_inner(&self) -> Option<Vec<u32>> {
+        let continued = {
+            let mut state = self.stop.lock();
+            state.stopped.take().map(|_| {
+                state.event = Some(StopEvent::Continued);
+                let mut resumed = Vec::new();
+                state.threads.retain(|&(tid, kind)| {
+                    let group = kind == StopKind::Group;
+                    if group {
+                        resumed.push(tid);
+                    }
+                    !group
+                });
+                resumed
+            })
+        };
+        self.flush_stop_signals();
+        continued
+    }
+
+    /// Gets the threads in a stop, and the kind of it.
+    pub fn stopped_threads(&self) -> Vec<(u32, StopKind)> {
+        self.stop.lock().threads.clone()
+    }
+
+    /// Records that a thread entered a stop of the given kind, or left its
+    /// stop if `kind` is `None`.
+    pub(crate) fn set_thread_stop(&self, tid: u32, kind: Option<StopKind>) {
+        let threads = &mut self.stop.lock().threads;
+        threads.retain(|&(t, _)| t != tid);
+        if let Some(kind) = kind {
+            threads.push((tid, kind));
+        }
+    }
+
+    /// Gets the kind of stop a thread is in, if any.
+    pub(crate) fn thread_stop(&self, tid: u32) -> Option<StopKind> {
+        let state = self.stop.lock();
+        state
+            .threads
+            .iter()
+            .find(|&&(t, _)| t == tid)
+            .map(|&(_, kind)| kind)
+    }
+
+    /// Checks if the process is stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stop.lock().stopped.is_some()
+    }
+
+    /// Gets the signal the process is stopped by, if stopped.
+    pub fn stop_signal(&self) -> Option<Signo> {
+        self.stop.lock().stopped
+    }
+
+    /// Takes the stop or continue not yet reported to `wait`, if `options`
+    /// asks for its kind.
+    ///
+    /// The event is consumed unless [`WaitOptions::NOWAIT`] is set. Checking
+    /// and consuming happen under one lock, so two waiters never both
+    /// consume, or both miss, the same event.
+    pub fn take_event(&self, options: WaitOptions) -> Option<StopEvent> {
+        let mut state = self.stop.lock();
+        let event = state.event?;
+        let wanted = match event {
+            StopEvent::Stopped(_) => options.contains(WaitOptions::STOPPED),
+            StopEvent::Continued => options.contains(WaitOptions::CONTINUED),
+        };
+        if !wanted {
+            return None;
+        }
+        if !options.contains(WaitOptions::NOWAIT) {
+            state.event = None;
+        }
+        Some(event)
+    }
+
+    /// Freezes the process.
+    ///
+    /// Each thread stops at its next delivery point, in
+    /// [`ThreadSignalManager::freeze_point`], until [`thaw`](Self::thaw) is
+    /// called. Unlike `SIGSTOP`, freezing is invisible to user space: no
+    /// signal is queued and no parent is notified. The threads are kicked
+    /// through the [`ReschedHook`](super::ReschedHook) and their waits for
+    /// signals are interrupted, so that they reach a delivery point.
+    pub fn freeze(&self) {
+        self.frozen.store(true, Ordering::Release);
+        self.arrival.notify(usize::MAX);
+        kick(&self.thread_ids());
+    }
+
+    /// Thaws the process, resuming the threads stopped by
+    /// [`freeze`](Self::freeze).
+    pub fn thaw(&self) {
+        self.frozen.store(false, Ordering::Release);
+        self.thaw_event.notify(usize::MAX);
+    }
+
+    /// Checks if the process is frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Acquire)
+    }
+
+    /// Returns a listener notified when the process is thawed, or `None` if
+    /// the process is not frozen.
+    pub(crate) fn thaw_listener(&self) -> Option<EventListener> {
+        if !self.is_frozen() {
+            return None;
+        }
+        let listener = self.thaw_event.listen();
+        // Check again in case `thaw` ran before the listener was registered.
+        self.is_frozen().then_some(listener)
+    }
+
+    /// Notifies the process of a state change of one of its children by
+    /// sending `SIGCHLD`.
+    ///
+    /// Stop, continue and trap notifications are suppressed if `SIGCHLD` has
+    /// [`SignalActionFlags::NOCLDSTOP`] set, and exit notifications are
+    /// suppressed if [`should_autoreap_children`](Self::should_autoreap_children).
+    /// Returns the threads to wake up.
+    #[must_use]
+    pub fn notify_child(&self, code: ChildCode, pid: u32, uid: u32, status: i32) -> SignalWake {
+        let action = self.with_actions(|actions| actions[Signo::SIGCHLD].clone());
+        let suppressed = match code {
+            ChildCode::Stopped | ChildCode::Continued | ChildCode::Trapped => {
+                action.flags.contains(SignalActionFlags::NOCLDSTOP)
+            }
+            ChildCode::Exited | ChildCode::Killed | ChildCode::Dumped => Self::autoreaps(&action),
+        };
+        if suppressed {
+            return SignalWake::None;
+        }
+        self.send_signal(SignalInfo::new_child(code, pid, uid, status))
+    }
+
+    fn autoreaps(action: &SignalAction) -> bool {
+        matches!(action.disposition, SignalDisposition::Ignore)
+            || action.flags.contains(SignalActionFlags::NOCLDWAIT)
+    }
+
+    /// Checks if terminated children should be reaped immediately instead of
+    /// becoming zombies, i.e. if `SIGCHLD` is explicitly ignored or has
+    /// [`SignalActionFlags::NOCLDWAIT`] set.
+    pub fn should_autoreap_children(&self) -> bool {
+        self.with_actions(|actions| Self::autoreaps(&actions[Signo::SIGCHLD]))
     }
 
     /// Gets currently pending signals.
     pub fn pending(&self) -> SignalSet {
         self.pending.lock().set
     }
+
+    /// Gets the action table of the process, which may be shared with
+    /// other processes.
+    pub fn actions(&self) -> Arc<SpinNoIrq<SignalActions>> {
+        self.actions.lock().clone()
+    }
+
+    /// Runs `f` with the action table locked, counting the acquisition in
+    /// the metrics of the process with the `lock-stats` feature.
+    pub(crate) fn with_actions<R>(&self, f: impl FnOnce(&mut SignalActions) -> R) -> R {
+        let table = self.actions.lock();
+        #[cfg(feature = "lock-stats")]
+        let mut actions = self.actions_stats.lock(&table);
+        #[cfg(not(feature = "lock-stats"))]
+        let mut actions = table.lock();
+        f(&mut actions)
+    }
+
+    /// Gets the metrics of the locks of the process.
+    ///
+    /// The action table may be shared with other processes; only its
+    /// acquisitions by this one are counted.
+    #[cfg(feature = "lock-stats")]
+    pub fn lock_stats(&self) -> ProcessLockStats {
+        ProcessLockStats {
+            pending: self.pending.counters.stats(),
+            actions: self.actions_stats.stats(),
+        }
+    }
+
+    /// Resets the metrics of the locks of the process.
+    #[cfg(feature = "lock-stats")]
+    pub fn reset_lock_stats(&self) {
+        self.pending.counters.reset();
+        self.actions_stats.reset();
+    }
+
+    /// Sets the event listener of the process, replacing the previous one.
+    pub fn set_listener(&self, listener: Option<Arc<dyn SignalEventListener>>) {
+        let mut slot = self.listener.lock();
+        self.has_listener
+            .store(listener.is_some(), Ordering::Release);
+        *slot = listener;
+    }
+
+    pub(crate) fn listener(&self) -> Option<Arc<dyn SignalEventListener>> {
+        if !self.has_listener.load(Ordering::Acquire) {
+            return None;
+        }
+        self.listener.lock().clone()
+    }
+
+    /// Sets the security filter of the process, replacing the previous one.
+    pub fn set_filter(&self, filter: Option<Arc<dyn SignalFilter>>) {
+        *self.filter.lock() = filter;
+    }
+
+    pub(crate) fn filter(&self) -> Option<Arc<dyn SignalFilter>> {
+        self.filter.lock().clone()
+    }
+
+    /// Sets the pressure hook of the process and the thresholds it is called
+    /// at, replacing the previous ones.
+    pub fn set_pressure_hook(
+        &self,
+        hook: Option<Arc<dyn SignalPressureHook>>,
+        thresholds: PressureThresholds,
+    ) {
+        let mut slot = self.pressure.lock();
+        self.has_pressure.store(hook.is_some(), Ordering::Release);
+        *slot = hook.map(|hook| (hook, thresholds));
+        self.pressure_high.store(false, Ordering::Relaxed);
+    }
+
+    pub(crate) fn pressure_hook(
+        &self,
+    ) -> Option<(Arc<dyn SignalPressureHook>, PressureThresholds)> {
+        if !self.has_pressure.load(Ordering::Acquire) {
+            return None;
+        }
+        self.pressure.lock().clone()
+    }
+
+    /// Calls the pressure hook for a signal just queued to thread `tid`, or
+    /// to the process if `None`, leaving `depth` instances of it in its
+    /// queue.
+    pub(crate) fn check_pressure(
+        &self,
+        (hook, thresholds): (Arc<dyn SignalPressureHook>, PressureThresholds),
+        tid: Option<u32>,
+        sig: &SignalInfo,
+        depth: usize,
+    ) {
+        // Measured under the queue lock, so exactly one send reaches it.
+        if sig.signo().is_realtime() && depth == thresholds.rt_depth {
+            hook.on_pressure(tid, sig, SignalPressure::RtDepth(depth));
+        }
+        if thresholds.total == usize::MAX {
+            return;
+        }
+        let mut total = self.pending.lock().len();
+        self.for_each_thread(|thread| total += thread.pending.lock().len());
+        if total < thresholds.total {
+            self.pressure_high.store(false, Ordering::Relaxed);
+        } else if !self.pressure_high.swap(true, Ordering::Relaxed) {
+            hook.on_pressure(tid, sig, SignalPressure::Total(total));
+        }
+    }
+
+    /// Sets the user stack hook of the process, replacing the previous one.
+    pub fn set_stack_hook(&self, hook: Option<Arc<dyn UserStackHook>>) {
+        *self.stack_hook.lock() = hook;
+    }
+
+    /// Sets the timer hook, or removes it with `None`.
+    pub fn set_timer_hook(&self, hook: Option<Arc<dyn TimerSignalHook>>) {
+        *self.timer_hook.lock() = hook;
+    }
+
+    pub(crate) fn stack_hook(&self) -> Option<Arc<dyn UserStackHook>> {
+        self.stack_hook.lock().clone()
+    }
+
+    /// Registers a consumer to be notified when signals it waits for arrive
+    /// at the process or any of its threads.
+    pub fn register_readiness(&self, consumer: &Arc<dyn SignalReadiness>) {
+        let mut readiness = self.readiness.lock();
+        readiness.push(Arc::downgrade(consumer));
+        self.has_readiness.store(true, Ordering::Release);
+    }
+
+    /// Unregisters a consumer registered with
+    /// [`register_readiness`](Self::register_readiness).
+    pub fn unregister_readiness(&self, consumer: &Arc<dyn SignalReadiness>) {
+        let consumer = Arc::downgrade(consumer);
+        let mut readiness = self.readiness.lock();
+        readiness.retain(|other| !Weak::ptr_eq(other, &consumer));
+        self.has_readiness
+            .store(!readiness.is_empty(), Ordering::Release);
+    }
+
+    /// Notifies the threads waiting for signals and the readiness consumers
+    /// waiting for `signo`.
+    pub(crate) fn notify_ready(&self, tid: Option<u32>, signo: Signo) {
+        self.arrival.notify(usize::MAX);
+        if !self.has_readiness.load(Ordering::Acquire) {
+            return;
+        }
+        let consumers: Vec<_> = {
+            let mut readiness = self.readiness.lock();
+            readiness.retain(|consumer| consumer.strong_count() > 0);
+            self.has_readiness
+                .store(!readiness.is_empty(), Ordering::Release);
+            readiness.iter().filter_map(Weak::upgrade).collect()
+        };
+        for consumer in consumers {
+            if consumer.interest().has(signo) {
+                consumer.notify_ready(tid, signo);
+            }
+        }
+    }
+
+    #[cfg(feature = "journal")]
+    pub(crate) fn record_event(&self, event: JournalEvent, tid: u32, sig: Option<&SignalInfo>) {
+        let (signo, code) = sig.map_or((None, 0), |sig| (Some(sig.signo()), sig.code()));
+        self.journal.lock().record(event, tid, signo, code);
+    }
+
+    /// Gets the journaled signal events of the process and its threads,
+    /// oldest first.
+    #[cfg(feature = "journal")]
+    pub fn journal(&self) -> Vec<JournalEntry> {
+        self.journal.lock().entries()
+    }
+
+    /// Reports the deliverable signals pending on the process or its
+    /// threads for longer than `threshold`, in the unit of the
+    /// [watchdog time source](crate::watchdog::set_time_source).
+    ///
+    /// A signal pending on a thread is deliverable if the thread does not
+    /// block it, and one pending on the process if any thread does not.
+    #[cfg(feature = "watchdog")]
+    pub fn check_stale_signals(&self, threshold: u64) {
+        let now = crate::watchdog::now();
+        let mut stale = Vec::new();
+        let mut deliverable = SignalSet::default();
+        self.for_each_thread(|thread| {
+            let unblocked = !thread.blocked();
+            deliverable |= unblocked;
+            stale.extend(
+                thread
+                    .pending
+                    .lock()
+                    .ages(unblocked, now)
+                    .filter(|(_, age)| *age > threshold)
+                    .map(|(signo, age)| (Some(thread.tid()), signo, age)),
+            );
+        });
+        stale.extend(
+            self.pending
+                .lock()
+                .ages(deliverable, now)
+                .filter(|(_, age)| *age > threshold)
+                .map(|(signo, age)| (None, signo, age)),
+        );
+        for (tid, signo, age) in stale {
+            crate::watchdog::report(tid, signo, age);
+        }
+    }
+
+    /// Exports the process-level signal state.
+    pub fn export_state(&self) -> ProcessSignalState {
+        let (stopped, stop_event) = {
+            let stop = self.stop.lock();
+            (stop.stopped, stop.event)
+        };
+        ProcessSignalState {
+            actions: self.with_actions(|actions| actions.deep_clone()),
+            pending: self.pending.lock().iter().cloned().collect(),
+            default_restorer: self.default_restorer(),
+            stopped,
+            stop_event,
+        }
+    }
+
+    /// Replaces the process-level signal state with an exported one.
+    ///
+    /// Pending signals are restored regardless of the limit of queued
+    /// real-time signals. Fails as [`PendingSignals::restore_signal`],
+    /// changing nothing, if one of them can't be restored.
+    pub fn import_state(&self, state: ProcessSignalState) -> SignalResult {
+        let mut pending = self.new_pending();
+        for sig in state.pending {
+            pending.restore_signal(sig)?;
+        }
+
+        self.with_actions(|actions| actions.replace(state.actions));
+        self.default_restorer
+            .store(state.default_restorer, Ordering::Relaxed);
+        {
+            let mut stop = self.stop.lock();
+            stop.stopped = state.stopped;
+            stop.event = state.stop_event;
+        }
+        let has_signal = !pending.set.is_empty();
+        let mut guard = self.pending.lock();
+        *guard = pending;
+        self.possibly_has_signal
+            .store(has_signal, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// Notifies the foreground process group of a terminal that the window size
+/// changed, by sending `SIGWINCH` to every process in `group`.
+///
+/// Processes ignoring `SIGWINCH`, explicitly or by default, don't get it
+/// queued, as with [`ProcessSignalManager::send_signal`]. Returns the threads
+/// woken up.
+pub fn notify_winch<'a>(group: impl IntoIterator<Item = &'a ProcessSignalManager>) -> Vec<u32> {
+    group
+        .into_iter()
+        .flat_map(|proc| {
+            proc.send_signal(SignalInfo::new_kernel(Signo::SIGWINCH))
+                .tids()
+                .to_vec()
+        })
+        .collect()
 }
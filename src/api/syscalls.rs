@@ -0,0 +1,191 @@
+//! User-memory side of the signal syscalls.
+//!
+//! Each helper reads the user structures through [`starry_vm`], validates
+//! them, applies them to the signal managers and writes back the old value.
+//! Null pointers are skipped, as in Linux. Errors convert to `LinuxError`
+//! with the `axerrno` feature.
+
+use core::{future::Future, time::Duration};
+
+use linux_raw_sys::general::{
+    __kernel_timespec, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK, SS_ONSTACK, kernel_sigaction,
+    kernel_sigset_t, siginfo_t, stack_t,
+};
+use starry_vm::{VmMutPtr, VmPtr};
+
+use crate::{
+    SIGSET_SIZE, SignalAction, SignalError, SignalResult, SignalSet, SignalStack, Signo,
+    api::ThreadSignalManager,
+};
+
+/// Reads a plain C structure from user memory.
+fn read<T>(ptr: *const T) -> SignalResult<T> {
+    let value = ptr.vm_read_uninit()?;
+    // SAFETY: The structures read here only contain integers and nullable
+    // function pointers, for which every bit pattern is valid.
+    Ok(unsafe { value.assume_init() })
+}
+
+fn check_sigsetsize(sigsetsize: usize) -> SignalResult {
+    if sigsetsize != SIGSET_SIZE {
+        return Err(SignalError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Implements `rt_sigaction` for the process of `thread`.
+///
+/// The actions of `SIGKILL` and `SIGSTOP` can't be changed. Setting an
+/// action that ignores the signal discards its pending instances.
+pub fn rt_sigaction(
+    thread: &ThreadSignalManager,
+    signo: u32,
+    act: *const kernel_sigaction,
+    oldact: *mut kernel_sigaction,
+    sigsetsize: usize,
+) -> SignalResult {
+    check_sigsetsize(sigsetsize)?;
+    let signo = Signo::try_from(signo)?;
+    let act = if act.is_null() {
+        None
+    } else {
+        if signo.is_unblockable() {
+            return Err(SignalError::InvalidArgument);
+        }
+        Some(SignalAction::from(read(act)?))
+    };
+
+    let proc = thread.process();
+    let old = proc.with_actions(|actions| {
+        let old = actions[signo].clone();
+        if let Some(act) = act {
+            actions[signo] = act;
+        }
+        old
+    });
+    if proc.signal_ignored(signo) {
+        proc.remove_signal(signo);
+    }
+
+    if !oldact.is_null() {
+        oldact.vm_write(old.into())?;
+    }
+    Ok(())
+}
+
+/// Implements `rt_sigprocmask` for `thread`.
+///
+/// `how` is one of `SIG_BLOCK`, `SIG_UNBLOCK` and `SIG_SETMASK`. `SIGKILL`
+/// and `SIGSTOP` are never blocked.
+pub fn rt_sigprocmask(
+    thread: &ThreadSignalManager,
+    how: u32,
+    set: *const kernel_sigset_t,
+    oldset: *mut kernel_sigset_t,
+    sigsetsize: usize,
+) -> SignalResult {
+    check_sigsetsize(sigsetsize)?;
+    let old = thread.blocked();
+    if !set.is_null() {
+        let set = SignalSet::from(read(set)?);
+        let new = match how {
+            SIG_BLOCK => old | set,
+            SIG_UNBLOCK => old & !set,
+            SIG_SETMASK => set,
+            _ => return Err(SignalError::InvalidArgument),
+        };
+        thread.set_blocked(new);
+    }
+
+    if !oldset.is_null() {
+        oldset.vm_write(old.into())?;
+    }
+    Ok(())
+}
+
+/// Implements `rt_sigpending` for `thread`.
+///
+/// As in POSIX, only the pending signals that are blocked are reported, from
+/// both the thread and the process. The others are about to be delivered.
+pub fn rt_sigpending(
+    thread: &ThreadSignalManager,
+    set: *mut kernel_sigset_t,
+    sigsetsize: usize,
+) -> SignalResult {
+    check_sigsetsize(sigsetsize)?;
+    set.vm_write((thread.pending() & thread.blocked()).into())?;
+    Ok(())
+}
+
+/// Implements `sigaltstack` for `thread`, whose user stack pointer is `sp`.
+///
+/// The signal stack can't be changed while running on it. The old stack
+/// reports `SS_ONSTACK` in that case.
+pub fn sigaltstack(
+    thread: &ThreadSignalManager,
+    ss: *const stack_t,
+    old_ss: *mut stack_t,
+    sp: usize,
+) -> SignalResult {
+    let mut old = thread.stack();
+    let on_stack = old.contains(sp);
+    if !ss.is_null() {
+        let stack = SignalStack::try_from(read(ss)?)?;
+        if on_stack {
+            return Err(SignalError::Permission);
+        }
+        thread.set_stack(stack);
+    }
+
+    if !old_ss.is_null() {
+        if on_stack {
+            old.flags = SS_ONSTACK;
+        }
+        old_ss.vm_write(old.into())?;
+    }
+    Ok(())
+}
+
+/// Implements `rt_sigtimedwait` for `thread`, returning the signal taken.
+///
+/// Without `timeout`, it waits indefinitely. Otherwise `sleep` is called to
+/// create the timer the wait is raced with, unless the timeout is zero, in
+/// which case it only polls. Fails with [`SignalError::WouldBlock`] on
+/// timeout and [`SignalError::Interrupted`] if another signal arrives. See
+/// [`ThreadSignalManager::wait_signal`].
+pub async fn rt_sigtimedwait<F: Future<Output = ()>>(
+    thread: &ThreadSignalManager,
+    set: *const kernel_sigset_t,
+    info: *mut siginfo_t,
+    timeout: *const __kernel_timespec,
+    sigsetsize: usize,
+    sleep: impl FnOnce(Duration) -> F,
+) -> SignalResult<Signo> {
+    check_sigsetsize(sigsetsize)?;
+    let mut set = SignalSet::from(read(set)?);
+    let timeout = if timeout.is_null() {
+        None
+    } else {
+        let ts = read(timeout)?;
+        if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+            return Err(SignalError::InvalidArgument);
+        }
+        Some(Duration::new(ts.tv_sec as _, ts.tv_nsec as _))
+    };
+
+    let sig = match timeout {
+        Some(timeout) if timeout.is_zero() => {
+            set.remove(Signo::SIGKILL);
+            set.remove(Signo::SIGSTOP);
+            thread.dequeue_signal(&set).ok_or(SignalError::WouldBlock)?
+        }
+        Some(timeout) => thread.wait_signal_or(set, sleep(timeout)).await?,
+        None => thread.wait_signal(set).await?,
+    };
+
+    let signo = sig.signo();
+    if !info.is_null() {
+        info.vm_write(sig.0)?;
+    }
+    Ok(signo)
+}
@@ -1,63 +1,574 @@
-use alloc::sync::Arc;
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 use core::{
-    alloc::Layout,
+    fmt,
+    future::{Future, poll_fn},
     mem::offset_of,
-    sync::atomic::{AtomicBool, Ordering},
+    pin::pin,
+    task::Poll,
+    time::Duration,
 };
 
 use axcpu::uspace::UserContext;
-use kspin::SpinNoIrq;
-use starry_vm::VmMutPtr;
+use linux_raw_sys::general::MINSIGSTKSZ;
+use starry_vm::{VmMutPtr, VmPtr};
+use strum::IntoEnumIterator;
 
-use super::ProcessSignalManager;
+#[cfg(feature = "tracepoint")]
+use super::process::trace_generate;
+use super::{
+    FilterDecision, MonotonicClock, ProcessSignalManager, SignalActions, SignalTracer, StopKind,
+    TraceDecision, kick, process::actions_summary,
+};
+#[cfg(feature = "journal")]
+use crate::journal::JournalEvent;
+#[cfg(feature = "lock-stats")]
+use crate::lockstat::LockStats;
+#[cfg(feature = "tracepoint")]
+use crate::tracepoint::{GenerateResult, SignalDeliver, Sigreturn};
 use crate::{
     DefaultSignalAction, PendingSignals, SignalAction, SignalActionFlags, SignalDisposition,
-    SignalInfo, SignalOSAction, SignalSet, SignalStack, Signo, arch::UContext,
+    SignalError, SignalInfo, SignalOSAction, SignalResult, SignalSet, SignalStack, Signo,
+    SyscallRestart,
+    arch::{ADDR_TAG_MASK, UContext},
+    coredump::CoreDumpContext,
+    sync::{
+        SpinNoIrq, StatLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
 };
 
+/// The signal frame set up on the user stack for a handler. Its layout is
+/// exported to C by the `header` tool.
+#[repr(C)]
 struct SignalFrame {
     ucontext: UContext,
     siginfo: SignalInfo,
-    uctx: UserContext,
+    magic: u64,
+}
+
+/// Magic value identifying a signal frame, mixed with the
+/// [frame cookie](ProcessSignalManager::set_frame_cookie) of the process.
+pub const SIGNAL_FRAME_MAGIC: u64 = 0x5354_5259_5349_4746;
+
+/// Size of the signal frame set up on the user stack for a handler.
+pub const SIGNAL_FRAME_SIZE: usize = size_of::<SignalFrame>();
+/// Alignment of the signal frame.
+pub const SIGNAL_FRAME_ALIGN: usize = align_of::<SignalFrame>();
+/// Offset of the ucontext, passed as the third argument of the handler, in
+/// the signal frame.
+pub const SIGNAL_FRAME_UCONTEXT_OFFSET: usize = offset_of!(SignalFrame, ucontext);
+/// Offset of the siginfo, passed as the second argument of the handler, in
+/// the signal frame.
+pub const SIGNAL_FRAME_SIGINFO_OFFSET: usize = offset_of!(SignalFrame, siginfo);
+/// Number of outstanding signal frames tracked per thread. Beyond it, the
+/// oldest are forgotten, as handlers that leave with `longjmp` never restore
+/// their frames.
+pub const MAX_TRACKED_FRAMES: usize = 32;
+
+/// Offset of the magic, see [`SIGNAL_FRAME_MAGIC`], in the signal frame.
+pub const SIGNAL_FRAME_MAGIC_OFFSET: usize = offset_of!(SignalFrame, magic);
+/// Worst-case user stack consumed by one signal delivery: the frame, its
+/// alignment padding and, on x86_64, the return address.
+pub const SIGNAL_FRAME_MAX_USAGE: usize = SIGNAL_FRAME_SIZE + SIGNAL_FRAME_ALIGN - 1
+    + if cfg!(target_arch = "x86_64") {
+        size_of::<usize>()
+    } else {
+        0
+    };
+
+const _: () = assert!(SIGNAL_FRAME_MAX_USAGE <= MINSIGSTKSZ as usize);
+
+/// Signal attributes of `posix_spawn`, applied with
+/// [`ThreadSignalManager::apply_spawn_attrs`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpawnSignalAttrs {
+    /// Signals reset to the default disposition (`POSIX_SPAWN_SETSIGDEF`).
+    pub sigdefault: Option<SignalSet>,
+    /// The blocked mask of the new thread (`POSIX_SPAWN_SETSIGMASK`).
+    pub sigmask: Option<SignalSet>,
+}
+
+/// Thread-level signal state exported for checkpointing.
+///
+/// See [`ThreadSignalManager::export_state`].
+#[derive(Clone)]
+pub struct ThreadSignalState {
+    /// The thread-directed pending signals, in delivery order.
+    pub pending: Vec<SignalInfo>,
+    /// The blocked signals.
+    pub blocked: SignalSet,
+    /// The signal stack.
+    pub stack: SignalStack,
+    /// The kind of stop the thread is in, if any.
+    pub stop: Option<StopKind>,
+}
+
+/// Signal-related fields of `/proc/<pid>/status`.
+///
+/// The [`Display`](fmt::Display) implementation renders the `SigPnd`,
+/// `ShdPnd`, `SigBlk`, `SigIgn` and `SigCgt` lines in the Linux format.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalStatus {
+    /// Signals pending for the thread.
+    pub thread_pending: SignalSet,
+    /// Signals pending for the whole process.
+    pub shared_pending: SignalSet,
+    /// Signals blocked by the thread.
+    pub blocked: SignalSet,
+    /// Signals ignored by the process.
+    pub ignored: SignalSet,
+    /// Signals caught by the process.
+    pub caught: SignalSet,
+}
+
+impl fmt::Display for SignalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SigPnd:\t{:016x}", self.thread_pending.bits())?;
+        writeln!(f, "ShdPnd:\t{:016x}", self.shared_pending.bits())?;
+        writeln!(f, "SigBlk:\t{:016x}", self.blocked.bits())?;
+        writeln!(f, "SigIgn:\t{:016x}", self.ignored.bits())?;
+        writeln!(f, "SigCgt:\t{:016x}", self.caught.bits())
+    }
+}
+
+/// Consistent view of the signal state of a thread, taken with
+/// [`ThreadSignalManager::snapshot`] for procfs, core dumps and debugging.
+#[derive(Clone)]
+pub struct SignalSnapshot {
+    /// Signals pending for the thread.
+    pub thread_pending: SignalSet,
+    /// Signals pending for the whole process.
+    pub shared_pending: SignalSet,
+    /// Signals blocked by the thread.
+    pub blocked: SignalSet,
+    /// The action table of the process. Cloning it is cheap, see
+    /// [`SignalActions`].
+    pub actions: SignalActions,
+}
+
+impl SignalSnapshot {
+    /// Gets the signals pending for the thread or the whole process.
+    pub fn pending(&self) -> SignalSet {
+        self.thread_pending | self.shared_pending
+    }
+
+    /// Gets the signals explicitly ignored, and those caught, by the
+    /// process.
+    pub fn dispositions(&self) -> (SignalSet, SignalSet) {
+        actions_summary(&self.actions)
+    }
+
+    /// Gets the signal-related fields of `/proc/<pid>/status`.
+    pub fn status(&self) -> SignalStatus {
+        let (ignored, caught) = self.dispositions();
+        SignalStatus {
+            thread_pending: self.thread_pending,
+            shared_pending: self.shared_pending,
+            blocked: self.blocked,
+            ignored,
+            caught,
+        }
+    }
+}
+
+/// What a write that exceeds `RLIMIT_FSIZE` should do.
+///
+/// See [`ThreadSignalManager::send_xfsz`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XfszAction {
+    /// `SIGXFSZ` will be delivered on return to user space. The write fails
+    /// with `EFBIG` if the process survives it.
+    Deliver,
+    /// `SIGXFSZ` is ignored or blocked. The write fails with `EFBIG`.
+    ReturnEfbig,
+}
+
+/// What a write to a broken pipe or a shut-down socket should do.
+///
+/// See [`ThreadSignalManager::send_sigpipe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeAction {
+    /// `SIGPIPE` has the default action and is not blocked, so the process
+    /// will be killed on return to user space. The write may be abandoned.
+    Fatal,
+    /// `SIGPIPE` will be delivered to a handler on return to user space. The
+    /// write fails with `EPIPE`.
+    Deliver,
+    /// `SIGPIPE` is suppressed, ignored or blocked. The write fails with
+    /// `EPIPE`.
+    ReturnEpipe,
 }
 
+/// Signal stack usage of a thread.
+///
+/// See [`ThreadSignalManager::stack_usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SignalStackUsage {
+    /// The most stack used by one delivery, from the interrupted stack
+    /// pointer (or the top of the alternate signal stack) down to the
+    /// bottom of the frame.
+    pub max_frame: usize,
+    /// The deepest use of the alternate signal stack, from its top down to
+    /// the bottom of the lowest frame set up on it, including nested ones.
+    pub altstack_high_water: usize,
+}
+
+/// Guard that defers signal delivery to a thread while it's alive.
+///
+/// See [`ThreadSignalManager::delivery_gate`].
+#[must_use]
+pub struct SignalDeliveryGate<'a> {
+    thread: &'a ThreadSignalManager,
+}
+
+impl Drop for SignalDeliveryGate<'_> {
+    fn drop(&mut self) {
+        self.thread.gates.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Returns the siginfo as seen by a handler with the given flags.
+fn user_siginfo(sig: &SignalInfo, flags: SignalActionFlags) -> SignalInfo {
+    let mut sig = sig.clone();
+    if ADDR_TAG_MASK != 0
+        && !flags.contains(SignalActionFlags::EXPOSE_TAGBITS)
+        && sig.signo().is_synchronous_fault()
+    {
+        sig.set_addr(sig.addr() & !ADDR_TAG_MASK);
+    }
+    sig
+}
+
+/// Outcome of consulting the tracer about a dequeued signal.
+enum Traced {
+    /// Deliver the signal.
+    Deliver(SignalInfo),
+    /// Enter signal-delivery-stop with the signal.
+    Stop(SignalInfo),
+    /// Nothing to deliver.
+    Skip,
+}
+
+/// The function `restart_syscall` calls to resume an interrupted syscall
+/// returning `ERESTART_RESTARTBLOCK`.
+pub type RestartBlock = Box<dyn FnOnce() -> isize + Send>;
+
 /// Thread-level signal manager.
 pub struct ThreadSignalManager {
+    /// The thread ID
+    tid: u32,
     /// The process-level signal manager
     proc: Arc<ProcessSignalManager>,
 
     /// The pending signals
-    pending: SpinNoIrq<PendingSignals>,
+    pub(crate) pending: StatLock<PendingSignals>,
     /// The set of signals currently blocked from delivery.
-    blocked: SpinNoIrq<SignalSet>,
+    blocked: AtomicU64,
     /// The stack used by signal handlers
     stack: SpinNoIrq<SignalStack>,
 
     possibly_has_signal: AtomicBool,
+    /// Whether the thread is exiting and takes no more shared signals
+    exiting: AtomicBool,
+
+    /// The number of live delivery gates
+    gates: AtomicUsize,
+
+    /// The pending kernel-private notifications, one bit each
+    notifications: AtomicU64,
+
+    /// The tracer consulted before delivering signals
+    tracer: SpinNoIrq<Option<Arc<dyn SignalTracer>>>,
+    /// The signal the thread stopped with in signal-delivery-stop
+    last_siginfo: SpinNoIrq<Option<SignalInfo>>,
+    /// The signal injected by the tracer, delivered at the next check
+    injected: SpinNoIrq<Option<SignalInfo>>,
+    /// The syscall interrupted by a signal
+    restart: SpinNoIrq<Option<SyscallRestart>>,
+    /// The function `restart_syscall` calls
+    restart_block: SpinNoIrq<Option<RestartBlock>>,
+    /// The context captured by the last core-dumping signal
+    coredump: SpinNoIrq<Option<CoreDumpContext>>,
+    /// Addresses of the signal frames set up and not yet restored, oldest
+    /// first
+    frames: SpinNoIrq<Vec<usize>>,
+    /// The most stack used by one signal delivery
+    max_frame_usage: AtomicUsize,
+    /// The deepest use of the alternate signal stack
+    altstack_high_water: AtomicUsize,
+    /// The architecture fault code of the last fault, or zero
+    fault_code: AtomicU64,
 }
 
 impl ThreadSignalManager {
     pub fn new(tid: u32, proc: Arc<ProcessSignalManager>) -> Arc<Self> {
         let this = Arc::new(Self {
+            tid,
             proc: proc.clone(),
 
-            pending: SpinNoIrq::new(PendingSignals::default()),
-            blocked: SpinNoIrq::new(SignalSet::default()),
+            pending: StatLock::new(proc.new_pending()),
+            blocked: AtomicU64::new(0),
             stack: SpinNoIrq::new(SignalStack::default()),
 
             possibly_has_signal: AtomicBool::new(false),
+            exiting: AtomicBool::new(false),
+
+            gates: AtomicUsize::new(0),
+
+            notifications: AtomicU64::new(0),
+
+            tracer: SpinNoIrq::new(None),
+            last_siginfo: SpinNoIrq::new(None),
+            injected: SpinNoIrq::new(None),
+            restart: SpinNoIrq::new(None),
+            restart_block: SpinNoIrq::new(None),
+            coredump: SpinNoIrq::new(None),
+            frames: SpinNoIrq::new(Vec::new()),
+            max_frame_usage: AtomicUsize::new(0),
+            altstack_high_water: AtomicUsize::new(0),
+            fault_code: AtomicU64::new(0),
         });
-        proc.children.lock().push((tid, Arc::downgrade(&this)));
+        {
+            let mut children = proc.children.lock();
+            debug_assert!(
+                !children
+                    .iter()
+                    .any(|(other, thread)| *other == tid && thread.strong_count() > 0),
+                "thread {tid} already has a signal manager"
+            );
+            children.push((tid, Arc::downgrade(&this)));
+        }
         this
     }
 
     /// Dequeues a signal from the thread's pending signals.
     #[must_use]
     pub fn dequeue_signal(&self, mask: &SignalSet) -> Option<SignalInfo> {
-        self.pending
-            .lock()
-            .dequeue_signal(mask)
-            .or_else(|| self.proc.dequeue_signal(mask))
+        let sig = self.pending.lock().dequeue_signal(mask);
+        // The queue is unlocked before `on_dequeue` calls the timer hook.
+        let sig = sig.or_else(|| self.proc.dequeue_signal(mask))?;
+        self.proc.on_dequeue(&sig);
+        Some(sig)
+    }
+
+    /// Dequeues a pending signal of `mask` without waiting and without
+    /// touching the trap frame, for completion-based IO loops that surface
+    /// signals as completion events instead of running handlers.
+    ///
+    /// Signals are taken whether blocked or not, except `SIGKILL` and
+    /// `SIGSTOP`, which are left to [`check_signals`](Self::check_signals).
+    /// No lock is taken when nothing was sent since the last check.
+    #[must_use]
+    pub fn poll_signal(&self, mask: &SignalSet) -> Option<SignalInfo> {
+        if !self.possibly_has_signal.load(Ordering::Acquire)
+            && !self.proc.possibly_has_signal.load(Ordering::Acquire)
+        {
+            return None;
+        }
+        let mut mask = *mask;
+        mask.remove(Signo::SIGKILL);
+        mask.remove(Signo::SIGSTOP);
+        self.dequeue_signal(&mask)
+    }
+
+    /// Waits for a signal of `set` to be pending and dequeues it, as in
+    /// `sigtimedwait`.
+    ///
+    /// The signals of `set` are taken whether blocked or not, except
+    /// `SIGKILL` and `SIGSTOP`. Fails with [`SignalError::Interrupted`] if
+    /// another signal that is not blocked, or a kernel-private notification,
+    /// is pending, or if the process is frozen. See
+    /// [`dequeue_signal_until`](Self::dequeue_signal_until) to wait with a
+    /// deadline.
+    pub async fn wait_signal(&self, mut set: SignalSet) -> SignalResult<SignalInfo> {
+        set.remove(Signo::SIGKILL);
+        set.remove(Signo::SIGSTOP);
+        loop {
+            if let Some(sig) = self.try_wait_signal(&set)? {
+                return Ok(sig);
+            }
+            let listener = self.proc.arrival.listen();
+            // Check again in case a signal arrived before the listener was
+            // registered.
+            if let Some(sig) = self.try_wait_signal(&set)? {
+                return Ok(sig);
+            }
+            listener.await;
+        }
+    }
+
+    /// Like [`wait_signal`](Self::wait_signal), but fails with
+    /// [`SignalError::WouldBlock`] once `clock` reaches `deadline`, as
+    /// `sigtimedwait` does with `EAGAIN`. If the deadline has already passed,
+    /// it only polls for a signal of `set`.
+    pub async fn dequeue_signal_until(
+        &self,
+        mut set: SignalSet,
+        deadline: Duration,
+        clock: &impl MonotonicClock,
+    ) -> SignalResult<SignalInfo> {
+        if clock.now() >= deadline {
+            set.remove(Signo::SIGKILL);
+            set.remove(Signo::SIGSTOP);
+            return self.dequeue_signal(&set).ok_or(SignalError::WouldBlock);
+        }
+        self.wait_signal_or(set, clock.sleep_until(deadline)).await
+    }
+
+    /// Waits as [`wait_signal`](Self::wait_signal) until `timeout` completes,
+    /// then fails with [`SignalError::WouldBlock`].
+    pub(crate) async fn wait_signal_or(
+        &self,
+        set: SignalSet,
+        timeout: impl Future<Output = ()>,
+    ) -> SignalResult<SignalInfo> {
+        let mut wait = pin!(self.wait_signal(set));
+        let mut timeout = pin!(timeout);
+        poll_fn(|cx| {
+            if let Poll::Ready(result) = wait.as_mut().poll(cx) {
+                return Poll::Ready(result);
+            }
+            timeout
+                .as_mut()
+                .poll(cx)
+                .map(|()| Err(SignalError::WouldBlock))
+        })
+        .await
+    }
+
+    fn try_wait_signal(&self, set: &SignalSet) -> SignalResult<Option<SignalInfo>> {
+        if let Some(sig) = self.dequeue_signal(set) {
+            return Ok(Some(sig));
+        }
+        if !(self.pending() & !self.blocked() & !*set).is_empty()
+            || self.notifications() != 0
+            || self.proc.is_frozen()
+        {
+            return Err(SignalError::Interrupted);
+        }
+        Ok(None)
+    }
+
+    /// Discards the pending instances of a signal directed to the thread.
+    ///
+    /// Returns `true` if any instance was discarded. See
+    /// [`ProcessSignalManager::remove_signal`] to also discard the shared
+    /// ones and those of the other threads.
+    pub fn remove_signal(&self, signo: Signo) -> bool {
+        let mut mask = SignalSet::default();
+        mask.add(signo);
+        self.pending.lock().remove_signals(&mask)
+    }
+
+    /// Discards the pending signals and notifications of the thread. See
+    /// [`ProcessSignalManager::clear_all`].
+    pub(crate) fn clear(&self) {
+        self.frames.lock().clear();
+        *self.injected.lock() = None;
+        self.notifications.store(0, Ordering::Release);
+        let mut pending = self.pending.lock();
+        pending.remove_signals(&!SignalSet::default());
+        self.possibly_has_signal.store(false, Ordering::Release);
+    }
+
+    /// Sets the tracer of the thread, replacing the previous one.
+    pub fn set_tracer(&self, tracer: Option<Arc<dyn SignalTracer>>) {
+        *self.tracer.lock() = tracer;
+    }
+
+    /// Gets the signal the thread is stopped with in signal-delivery-stop,
+    /// i.e. the one reported with [`SignalOSAction::TraceStop`]. Used by
+    /// `PTRACE_GETSIGINFO`.
+    pub fn last_siginfo(&self) -> Option<SignalInfo> {
+        self.last_siginfo.lock().clone()
+    }
+
+    /// Replaces the signal the thread is stopped with. Used by
+    /// `PTRACE_SETSIGINFO`.
+    ///
+    /// Returns `false` if the thread is not in signal-delivery-stop.
+    pub fn set_last_siginfo(&self, sig: SignalInfo) -> bool {
+        let mut guard = self.last_siginfo.lock();
+        if guard.is_none() {
+            return false;
+        }
+        *guard = Some(sig);
+        true
+    }
+
+    /// Takes the signal the thread is stopped with, leaving
+    /// signal-delivery-stop. Called when the tracer resumes the thread.
+    pub fn take_last_siginfo(&self) -> Option<SignalInfo> {
+        self.last_siginfo.lock().take()
+    }
+
+    /// Records that the thread stopped with the rest of the process, after
+    /// [`ProcessSignalManager::stop`]. It stays in group-stop until
+    /// [`ProcessSignalManager::cont_threads`] resumes it.
+    ///
+    /// A thread in `ptrace` stop stays in it.
+    pub fn enter_group_stop(&self) {
+        if self.stop_kind() != Some(StopKind::Trace) {
+            self.proc.set_thread_stop(self.tid, Some(StopKind::Group));
+        }
+    }
+
+    /// Gets the kind of stop the thread is in, if any, for `wait` and the
+    /// tracer to report the right status.
+    pub fn stop_kind(&self) -> Option<StopKind> {
+        self.proc.thread_stop(self.tid)
+    }
+
+    /// Resumes the thread from `ptrace` stop, as with `PTRACE_CONT`, leaving
+    /// signal-delivery-stop as well.
+    ///
+    /// Returns `false` if the thread is not in `ptrace` stop, e.g. if it is
+    /// in group-stop, which only `SIGCONT` ends.
+    pub fn trace_cont(&self) -> bool {
+        if self.stop_kind() != Some(StopKind::Trace) {
+            return false;
+        }
+        self.proc.set_thread_stop(self.tid, None);
+        self.last_siginfo.lock().take();
+        true
+    }
+
+    /// Takes the context captured when a signal with
+    /// [`SignalOSAction::CoreDump`] was delivered to the thread.
+    pub fn take_coredump(&self) -> Option<CoreDumpContext> {
+        self.coredump.lock().take()
+    }
+
+    /// Records the architecture fault code of a synchronous fault, before
+    /// sending the fault signal: the exception syndrome (`ESR_EL1`) on
+    /// aarch64, which runtimes read from the `esr_context` record of the
+    /// signal frame. It is consumed by the next fault signal delivered.
+    pub fn set_fault_code(&self, code: u64) {
+        self.fault_code.store(code, Ordering::Relaxed);
+    }
+
+    /// Gets the thread ID.
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Marks the thread as exiting. Called by the OS when the thread starts
+    /// to exit, as Linux's `exit_signals`.
+    ///
+    /// Process-directed signals are no longer given to the thread, and those
+    /// pending that it may have been picked for are handed over to other
+    /// threads. A stop the thread was in is forgotten.
+    pub fn exit_signals(&self) {
+        if self.exiting.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.proc.set_thread_stop(self.tid, None);
+        self.retarget_shared_pending(self.blocked(), !SignalSet::default());
+    }
+
+    /// Checks if [`exit_signals`](Self::exit_signals) has been called.
+    pub fn is_exiting(&self) -> bool {
+        self.exiting.load(Ordering::Acquire)
     }
 
     pub fn process(&self) -> &Arc<ProcessSignalManager> {
@@ -73,6 +584,55 @@ impl ThreadSignalManager {
     ) -> Option<SignalOSAction> {
         let signo = sig.signo();
         debug!("Handle signal: {signo:?}");
+        let saved = *uctx;
+        let result = self.handle_signal_inner(uctx, restore_blocked, sig, action);
+        if result == Some(SignalOSAction::CoreDump) {
+            *self.coredump.lock() = Some(CoreDumpContext::new(sig.clone(), saved, restore_blocked));
+        }
+        #[cfg(feature = "journal")]
+        {
+            let event = if result.is_some() {
+                JournalEvent::Deliver
+            } else {
+                JournalEvent::Ignore
+            };
+            self.proc.record_event(event, self.tid, Some(sig));
+        }
+        #[cfg(feature = "tracepoint")]
+        if result.is_some() {
+            crate::tracepoint::emit(|tp| {
+                tp.signal_deliver(&SignalDeliver {
+                    tid: self.tid,
+                    signo,
+                    errno: sig.errno(),
+                    code: sig.code(),
+                    handler: match action.disposition {
+                        SignalDisposition::Default => 0,
+                        SignalDisposition::Ignore => 1,
+                        ref disposition => disposition.handler().unwrap(),
+                    },
+                    flags: action.flags,
+                })
+            });
+        }
+        if let Some(listener) = self.proc.listener() {
+            match result {
+                Some(SignalOSAction::CoreDump) => listener.on_coredump(self.tid, sig),
+                Some(action) => listener.on_deliver(self.tid, sig, action),
+                None => listener.on_ignore(Some(self.tid), sig),
+            }
+        }
+        result
+    }
+
+    fn handle_signal_inner(
+        &self,
+        uctx: &mut UserContext,
+        restore_blocked: SignalSet,
+        sig: &SignalInfo,
+        action: &SignalAction,
+    ) -> Option<SignalOSAction> {
+        let signo = sig.signo();
         match action.disposition {
             SignalDisposition::Default => match signo.default_action() {
                 DefaultSignalAction::Terminate => Some(SignalOSAction::Terminate),
@@ -82,39 +642,94 @@ impl ThreadSignalManager {
                 DefaultSignalAction::Continue => Some(SignalOSAction::Continue),
             },
             SignalDisposition::Ignore => None,
-            SignalDisposition::Handler(handler) => {
-                let layout = Layout::new::<SignalFrame>();
-                let stack = self.stack.lock();
-                let sp = if stack.disabled() || !action.flags.contains(SignalActionFlags::ONSTACK) {
+            SignalDisposition::Handler(_) | SignalDisposition::SigactionHandler(_) => {
+                let handler = action.disposition.handler().unwrap();
+                let stack = self.stack();
+                let sp = if !action.flags.contains(SignalActionFlags::ONSTACK)
+                    || stack.disabled()
+                    || stack.contains(uctx.sp())
+                {
                     uctx.sp()
                 } else {
-                    stack.sp + stack.size
+                    stack.top()
                 };
-                drop(stack);
-
-                let aligned_sp = (sp - layout.size()) & !(layout.align() - 1);
-
-                let frame_ptr = aligned_sp as *mut SignalFrame;
-                if frame_ptr
-                    .vm_write(SignalFrame {
-                        ucontext: UContext::new(uctx, restore_blocked),
-                        siginfo: sig.clone(),
-                        uctx: *uctx,
-                    })
-                    .is_err()
+
+                let Some(aligned_sp) = sp
+                    .checked_sub(SIGNAL_FRAME_SIZE)
+                    .map(|sp| sp & !(SIGNAL_FRAME_ALIGN - 1))
+                else {
+                    return Some(SignalOSAction::CoreDump);
+                };
+                if !self.frame_fits(aligned_sp, sp) {
+                    warn!(
+                        "thread {}: signal frame at {aligned_sp:#x} can't be written to the stack",
+                        self.tid
+                    );
+                    return Some(SignalOSAction::CoreDump);
+                }
+
+                let mut ucontext = UContext::new(uctx, restore_blocked);
+                ucontext.stack = stack.clone();
+                // The stack is saved above and restored by `restore`.
+                if stack.autodisarm() {
+                    self.set_stack(SignalStack::default());
+                }
+                if sig.is_fault() {
+                    ucontext.mcontext.set_fault_address(sig.addr());
+                    let code = self.fault_code.swap(0, Ordering::Relaxed);
+                    if code != 0 {
+                        ucontext.mcontext.set_fault_code(code);
+                    }
+                }
+                // Chain to the context of the handler being interrupted, if any.
+                // The frames below the stack pointer on its stack were left by
+                // handlers that never returned, e.g. with `siglongjmp`.
+                ucontext.link = {
+                    let mut frames = self.frames.lock();
+                    let on_stack = stack.contains(uctx.sp());
+                    frames.retain(|&frame| frame >= uctx.sp() || stack.contains(frame) != on_stack);
+                    frames
+                        .last()
+                        .map_or(0, |frame| frame + SIGNAL_FRAME_UCONTEXT_OFFSET)
+                };
+
+                let siginfo_ptr = aligned_sp + SIGNAL_FRAME_SIGINFO_OFFSET;
+                let ucontext_ptr = aligned_sp + SIGNAL_FRAME_UCONTEXT_OFFSET;
+                let magic_ptr = aligned_sp + SIGNAL_FRAME_MAGIC_OFFSET;
+                // Only a handler taking the siginfo gets one.
+                let siginfo_written = match action.disposition {
+                    SignalDisposition::SigactionHandler(_) => (siginfo_ptr as *mut SignalInfo)
+                        .vm_write(user_siginfo(sig, action.flags))
+                        .is_ok(),
+                    _ => true,
+                };
+                if !siginfo_written
+                    || (ucontext_ptr as *mut UContext).vm_write(ucontext).is_err()
+                    || (magic_ptr as *mut u64)
+                        .vm_write(self.frame_magic())
+                        .is_err()
                 {
                     return Some(SignalOSAction::CoreDump);
                 }
 
-                uctx.set_ip(handler as usize);
+                self.record_stack_usage(&stack, sp, aligned_sp);
+                {
+                    let mut frames = self.frames.lock();
+                    if frames.len() == MAX_TRACKED_FRAMES {
+                        frames.remove(0);
+                    }
+                    frames.push(aligned_sp);
+                }
+
+                uctx.set_ip(handler);
                 uctx.set_sp(aligned_sp);
                 uctx.set_arg0(signo as _);
-                uctx.set_arg1(aligned_sp + offset_of!(SignalFrame, siginfo));
-                uctx.set_arg2(aligned_sp + offset_of!(SignalFrame, ucontext));
+                uctx.set_arg1(siginfo_ptr);
+                uctx.set_arg2(ucontext_ptr);
 
                 let restorer = action
                     .restorer
-                    .map_or(self.proc.default_restorer, |f| f as _);
+                    .map_or_else(|| self.proc.default_restorer(), |f| f as _);
                 #[cfg(target_arch = "x86_64")]
                 {
                     let new_sp = uctx.sp() - 8;
@@ -130,43 +745,166 @@ impl ThreadSignalManager {
                 if !action.flags.contains(SignalActionFlags::NODEFER) {
                     add_blocked.add(signo);
                 }
+                add_blocked.remove(Signo::SIGKILL);
+                add_blocked.remove(Signo::SIGSTOP);
 
                 if action.flags.contains(SignalActionFlags::RESETHAND) {
-                    self.proc.actions.lock()[signo] = SignalAction::default();
+                    self.proc
+                        .with_actions(|actions| actions[signo] = SignalAction::default());
                 }
-                *self.blocked.lock() |= add_blocked;
+                let old = self.blocked.fetch_or(add_blocked.bits(), Ordering::AcqRel);
+                self.retarget_shared_pending(SignalSet::from_bits(old), add_blocked);
                 Some(SignalOSAction::Handler)
             }
         }
     }
 
+    /// Consults the security filter of the process about a dequeued signal,
+    /// returning the signal to go on with.
+    fn filter_signal(&self, sig: SignalInfo, mask: &SignalSet) -> Option<SignalInfo> {
+        if sig.signo() == Signo::SIGKILL {
+            return Some(sig);
+        }
+        let Some(filter) = self.proc.filter() else {
+            return Some(sig);
+        };
+        match filter.filter(self.tid, &sig) {
+            FilterDecision::Allow => Some(sig),
+            FilterDecision::Drop => None,
+            FilterDecision::Rewrite(new_sig) => {
+                if mask.has(new_sig.signo()) {
+                    Some(new_sig)
+                } else {
+                    let _ = self.queue_signal(new_sig, false);
+                    None
+                }
+            }
+        }
+    }
+
+    /// Consults the tracer about a dequeued signal.
+    fn trace_signal(&self, sig: SignalInfo, mask: &SignalSet) -> Traced {
+        if sig.signo() == Signo::SIGKILL {
+            return Traced::Deliver(sig);
+        }
+        let Some(tracer) = self.tracer.lock().clone() else {
+            return Traced::Deliver(sig);
+        };
+        match tracer.on_signal(self.tid, &sig) {
+            TraceDecision::Deliver => Traced::Deliver(sig),
+            TraceDecision::Stop => Traced::Stop(sig),
+            TraceDecision::Suppress => Traced::Skip,
+            TraceDecision::Replace(new_sig) => {
+                if mask.has(new_sig.signo()) {
+                    Traced::Deliver(new_sig)
+                } else {
+                    let _ = self.queue_signal(new_sig, false);
+                    Traced::Skip
+                }
+            }
+        }
+    }
+
     #[cold]
     fn check_signals_slow(
         &self,
         uctx: &mut UserContext,
         restore_blocked: Option<SignalSet>,
+        filter: Option<&SignalSet>,
+    ) -> Option<(SignalInfo, SignalOSAction)> {
+        let mut restart = self.restart.lock().take();
+        let result = self.check_signals_inner(uctx, restore_blocked, filter, &mut restart);
+        // No handler has run, so the syscall is restarted transparently.
+        if let Some(restart) = restart {
+            restart.apply(uctx, None);
+        }
+        result
+    }
+
+    /// Handles a signal, deciding on the interrupted syscall first if a
+    /// handler is about to run.
+    fn deliver_signal(
+        &self,
+        uctx: &mut UserContext,
+        restore_blocked: SignalSet,
+        sig: &SignalInfo,
+        restart: &mut Option<SyscallRestart>,
+    ) -> Option<SignalOSAction> {
+        let action = self
+            .proc
+            .with_actions(|actions| actions[sig.signo()].clone());
+        if action.disposition.is_handler()
+            && let Some(restart) = restart.take()
+        {
+            restart.apply(
+                uctx,
+                Some(action.flags.contains(SignalActionFlags::RESTART)),
+            );
+        }
+        self.handle_signal(uctx, restore_blocked, sig, &action)
+    }
+
+    fn check_signals_inner(
+        &self,
+        uctx: &mut UserContext,
+        restore_blocked: Option<SignalSet>,
+        filter: Option<&SignalSet>,
+        restart: &mut Option<SyscallRestart>,
     ) -> Option<(SignalInfo, SignalOSAction)> {
-        let blocked = self.blocked.lock();
-        let mask = !*blocked;
-        let restore_blocked = restore_blocked.unwrap_or_else(|| *blocked);
-        drop(blocked);
+        let blocked = self.blocked();
+        let mut mask = !blocked;
+        let restore_blocked = restore_blocked.unwrap_or(blocked);
+        if let Some(filter) = filter {
+            mask &= *filter;
+        }
+
+        let injected = self
+            .injected
+            .lock()
+            .take_if(|sig| filter.is_none_or(|filter| filter.has(sig.signo())));
+        if let Some(sig) = injected
+            && let Some(os_action) = self.deliver_signal(uctx, restore_blocked, &sig, restart)
+        {
+            return Some((sig, os_action));
+        }
 
         loop {
             let sig = match self.pending.lock().dequeue_signal(&mask) {
                 Some(sig) => Some(sig),
                 None => {
-                    self.possibly_has_signal.store(false, Ordering::Release);
+                    // Signals outside the filter may still be pending, and
+                    // signals newly unblocked by another thread may be.
+                    if filter.is_none() && self.blocked() == blocked {
+                        self.possibly_has_signal.store(false, Ordering::Release);
+                    }
                     self.proc.dequeue_signal(&mask)
                 }
             }?;
-            let action = self.proc.actions.lock()[sig.signo()].clone();
-
-            if let Some(os_action) = self.handle_signal(uctx, restore_blocked, &sig, &action) {
+            self.proc.on_dequeue(&sig);
+            let Some(sig) = self.filter_signal(sig, &mask) else {
+                continue;
+            };
+            let sig = match self.trace_signal(sig, &mask) {
+                Traced::Deliver(sig) => sig,
+                Traced::Skip => continue,
+                Traced::Stop(sig) => {
+                    *self.last_siginfo.lock() = Some(sig.clone());
+                    self.proc.set_thread_stop(self.tid, Some(StopKind::Trace));
+                    break Some((sig, SignalOSAction::TraceStop));
+                }
+            };
+            if let Some(os_action) = self.deliver_signal(uctx, restore_blocked, &sig, restart) {
                 break Some((sig, os_action));
             }
         }
     }
 
+    fn should_check_signals(&self) -> bool {
+        self.gates.load(Ordering::Acquire) == 0
+            && (self.possibly_has_signal.load(Ordering::Acquire)
+                || self.proc.possibly_has_signal.load(Ordering::Acquire))
+    }
+
     /// Checks pending signals and handle them.
     ///
     /// Returns the signal number and the action the OS should take, if any.
@@ -175,66 +913,611 @@ impl ThreadSignalManager {
         uctx: &mut UserContext,
         restore_blocked: Option<SignalSet>,
     ) -> Option<(SignalInfo, SignalOSAction)> {
+        #[cfg(debug_assertions)]
+        self.assert_invariants();
         // Fast path
-        if !self.possibly_has_signal.load(Ordering::Acquire)
-            && !self.proc.possibly_has_signal.load(Ordering::Acquire)
-        {
+        if !self.should_check_signals() {
             return None;
         }
-        self.check_signals_slow(uctx, restore_blocked)
+        self.check_signals_slow(uctx, restore_blocked, None)
     }
 
-    /// Restores the signal frame. Called by `sigreturn`.
-    pub fn restore(&self, uctx: &mut UserContext) {
-        let frame_ptr = uctx.sp() as *const SignalFrame;
-        // FIXME: remove this `unsafe`
-        let frame = unsafe { &*frame_ptr };
+    /// Like [`check_signals`](Self::check_signals), but handles up to `max`
+    /// signals in one go.
+    ///
+    /// Handler frames are stacked: each frame is set up on top of the
+    /// previous one and with the mask left by the previous action, so the
+    /// handlers run last-delivered first as if the thread had returned to
+    /// user space between them. Stops at the first action that is not
+    /// [`SignalOSAction::Handler`].
+    ///
+    /// Returns the handled signals and actions in delivery order.
+    pub fn check_signals_batch(
+        &self,
+        uctx: &mut UserContext,
+        mut restore_blocked: Option<SignalSet>,
+        max: usize,
+    ) -> Vec<(SignalInfo, SignalOSAction)> {
+        let mut result = Vec::new();
+        while result.len() < max && self.should_check_signals() {
+            let Some((sig, action)) = self.check_signals_slow(uctx, restore_blocked.take(), None)
+            else {
+                break;
+            };
+            result.push((sig, action));
+            if action != SignalOSAction::Handler {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Like [`check_signals`](Self::check_signals), but only handles signals
+    /// in `filter`, leaving the others pending.
+    ///
+    /// [`SignalSet::synchronous`] can be used to only handle synchronous
+    /// fault signals.
+    pub fn check_signals_filtered(
+        &self,
+        uctx: &mut UserContext,
+        restore_blocked: Option<SignalSet>,
+        filter: &SignalSet,
+    ) -> Option<(SignalInfo, SignalOSAction)> {
+        if !self.should_check_signals() {
+            return None;
+        }
+        self.check_signals_slow(uctx, restore_blocked, Some(filter))
+    }
 
-        *uctx = frame.uctx;
-        frame.ucontext.mcontext.restore(uctx);
+    /// Defers signal delivery to the thread until the returned guard is
+    /// dropped.
+    ///
+    /// Signals can still be sent and queued, but
+    /// [`check_signals`](Self::check_signals) returns `None` while any gate is
+    /// held. Pending signals are delivered by the first call after the last
+    /// gate is dropped. Gates can be nested.
+    pub fn delivery_gate(&self) -> SignalDeliveryGate<'_> {
+        self.gates.fetch_add(1, Ordering::Acquire);
+        SignalDeliveryGate { thread: self }
+    }
 
-        *self.blocked.lock() = frame.ucontext.sigmask;
+    /// Records a syscall interrupted by a signal, i.e. returning one of the
+    /// `ERESTART*` errnos.
+    ///
+    /// The next call to [`check_signals`](Self::check_signals) decides on it:
+    /// if a handler runs, the syscall fails with `EINTR` or is restarted after
+    /// the handler returns, as required by the [`RestartKind`] and
+    /// `SA_RESTART`. Otherwise it's restarted transparently.
+    ///
+    /// [`RestartKind`]: crate::RestartKind
+    pub fn set_syscall_restart(&self, restart: SyscallRestart) {
+        *self.restart.lock() = Some(restart);
         self.possibly_has_signal.store(true, Ordering::Release);
     }
 
+    /// Sets the function `restart_syscall` calls.
+    pub fn set_restart_block(&self, block: RestartBlock) {
+        *self.restart_block.lock() = Some(block);
+    }
+
+    /// Takes the function `restart_syscall` calls, if any.
+    pub fn take_restart_block(&self) -> Option<RestartBlock> {
+        self.restart_block.lock().take()
+    }
+
+    /// Waits while the process is frozen.
+    ///
+    /// The OS should await this at every delivery point, along with
+    /// [`check_signals`](Self::check_signals). It completes immediately if
+    /// the process is not frozen.
+    pub async fn freeze_point(&self) {
+        while let Some(listener) = self.proc.thaw_listener() {
+            listener.await;
+        }
+    }
+
+    /// Prepares the memory of a frame from `aligned_sp` up to `sp`, along
+    /// with the return address pushed below it on x86_64, and checks that it
+    /// lies in the stack, with the user stack hook if any.
+    fn frame_fits(&self, aligned_sp: usize, sp: usize) -> bool {
+        let Some(hook) = self.proc.stack_hook() else {
+            return true;
+        };
+        let return_addr = if cfg!(target_arch = "x86_64") {
+            size_of::<usize>()
+        } else {
+            0
+        };
+        let Some(bottom) = aligned_sp.checked_sub(return_addr) else {
+            return false;
+        };
+        hook.prepare_frame(self.tid, bottom..sp)
+            && hook
+                .stack_bounds(self.tid, sp - 1)
+                .is_some_and(|bounds| bounds.start <= bottom && sp <= bounds.end)
+    }
+
+    fn record_stack_usage(&self, stack: &SignalStack, sp: usize, aligned_sp: usize) {
+        let bottom = if cfg!(target_arch = "x86_64") {
+            aligned_sp - size_of::<usize>()
+        } else {
+            aligned_sp
+        };
+        self.max_frame_usage
+            .fetch_max(sp - bottom, Ordering::Relaxed);
+        if stack.enabled() && bottom >= stack.sp && sp <= stack.top() {
+            self.altstack_high_water
+                .fetch_max(stack.top() - bottom, Ordering::Relaxed);
+        }
+    }
+
+    /// Gets the signal stack usage of the thread, to tune the size of the
+    /// alternate signal stack or detect near-overflows.
+    pub fn stack_usage(&self) -> SignalStackUsage {
+        SignalStackUsage {
+            max_frame: self.max_frame_usage.load(Ordering::Relaxed),
+            altstack_high_water: self.altstack_high_water.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Resets the signal stack usage of the thread.
+    pub fn reset_stack_usage(&self) {
+        self.max_frame_usage.store(0, Ordering::Relaxed);
+        self.altstack_high_water.store(0, Ordering::Relaxed);
+    }
+
+    /// Checks the internal invariants of the thread, panicking if any is
+    /// broken.
+    ///
+    /// Only called by the thread itself, in
+    /// [`check_signals`](Self::check_signals).
+    #[cfg(debug_assertions)]
+    fn assert_invariants(&self) {
+        let blocked = self.blocked();
+        assert!(
+            !blocked.has(Signo::SIGKILL) && !blocked.has(Signo::SIGSTOP),
+            "thread {}: SIGKILL or SIGSTOP blocked: {blocked:?}",
+            self.tid
+        );
+        let pending = self.pending.lock();
+        assert!(
+            (pending.set & !blocked).is_empty() || self.possibly_has_signal.load(Ordering::Acquire),
+            "thread {}: deliverable signals {:?} pending but not flagged",
+            self.tid,
+            pending.set & !blocked
+        );
+        drop(pending);
+        self.proc.assert_invariants();
+    }
+
+    /// Gets the addresses of the signal frames set up by
+    /// [`handle_signal`](Self::handle_signal) and not yet restored, oldest
+    /// first. At most [`MAX_TRACKED_FRAMES`] are kept.
+    pub fn outstanding_frames(&self) -> Vec<usize> {
+        self.frames.lock().clone()
+    }
+
+    fn frame_magic(&self) -> u64 {
+        SIGNAL_FRAME_MAGIC ^ self.proc.frame_cookie()
+    }
+
+    /// Restores the signal frame. Called by `sigreturn`.
+    ///
+    /// Fails with [`SignalError::BadAddress`], leaving `uctx` and the thread
+    /// untouched, if the stack pointer does not point at a frame set up by
+    /// [`handle_signal`](Self::handle_signal), as told by its magic, and as
+    /// [`UContext::read_from_user`] if the ucontext in it is malformed. The
+    /// OS should then kill the thread with `SIGSEGV`, as Linux does.
+    pub fn restore(&self, uctx: &mut UserContext) -> SignalResult {
+        #[cfg(feature = "tracepoint")]
+        crate::tracepoint::emit(|tp| {
+            tp.sigreturn(&Sigreturn {
+                tid: self.tid,
+                sp: uctx.sp(),
+            })
+        });
+        let magic = ((uctx.sp() + SIGNAL_FRAME_MAGIC_OFFSET) as *const u64).vm_read()?;
+        if magic != self.frame_magic() {
+            return Err(SignalError::BadAddress);
+        }
+        let ucontext =
+            UContext::read_from_user((uctx.sp() + offset_of!(SignalFrame, ucontext)) as *const _)?;
+        {
+            let mut frames = self.frames.lock();
+            match frames.iter().rposition(|&frame| frame == uctx.sp()) {
+                // Frames set up after it belong to handlers that were left
+                // without returning.
+                Some(index) => frames.truncate(index),
+                None => warn!(
+                    "thread {}: sigreturn with sp {:#x} not at an outstanding signal frame",
+                    self.tid,
+                    uctx.sp()
+                ),
+            }
+        }
+        // The saved context is user memory as well, so only the machine
+        // context is restored, on top of the live one, with privileged state
+        // left untouched.
+        ucontext.restore(uctx);
+        if ucontext.stack.autodisarm() {
+            self.set_stack(ucontext.stack.clone());
+        }
+        let old = self.blocked.swap(ucontext.sigmask.bits(), Ordering::AcqRel);
+        self.possibly_has_signal.store(true, Ordering::Release);
+        self.retarget_shared_pending(SignalSet::from_bits(old), ucontext.sigmask);
+        #[cfg(feature = "journal")]
+        self.proc
+            .record_event(JournalEvent::Sigreturn, self.tid, None);
+        if let Some(listener) = self.proc.listener() {
+            listener.on_sigreturn(self.tid);
+        }
+        Ok(())
+    }
+
+    /// Sends `SIGPROF` to the thread, as on a tick of a profiling timer.
+    ///
+    /// This is a fast path of [`send_signal`](Self::send_signal) for sampling
+    /// profilers sending thousands of `SIGPROF` a second. The pending queue
+    /// of the thread is locked once, both to merge a tick into a `SIGPROF`
+    /// still pending and to queue a new one, copied from a static signal
+    /// info and queued without allocating once the thread has taken a
+    /// standard signal before. The listener, readiness consumers and
+    /// rescheduling hook are only looked up if installed, and the pressure
+    /// hook is not called, as a tick adds at most one signal to the queue.
+    /// An ignored `SIGPROF` takes the slow path.
+    ///
+    /// Only sending is sped up: the tick is delivered by
+    /// [`handle_signal`](Self::handle_signal) like any other signal, with a
+    /// signal frame built for it.
+    ///
+    /// Returns `true` if the thread needs to be woken up.
+    #[must_use]
+    pub fn send_profiling_signal(&self) -> bool {
+        static PROF_INFO: SignalInfo = SignalInfo::new_kernel(Signo::SIGPROF);
+        let signo = Signo::SIGPROF;
+        if self.proc.is_exiting() {
+            return false;
+        }
+        {
+            let mut pending = self.pending.lock();
+            if pending.is_pending(signo) {
+                return false;
+            }
+            if self.proc.signal_ignored(signo) {
+                drop(pending);
+                return self.send_signal(PROF_INFO.clone());
+            }
+            pending.put_signal(PROF_INFO.clone());
+            self.possibly_has_signal.store(true, Ordering::Release);
+        }
+        #[cfg(feature = "journal")]
+        self.proc
+            .record_event(JournalEvent::Send, self.tid, Some(&PROF_INFO));
+        #[cfg(feature = "tracepoint")]
+        trace_generate(&PROF_INFO, Some(self.tid), false, GenerateResult::Delivered);
+        if let Some(listener) = self.proc.listener() {
+            listener.on_send(Some(self.tid), &PROF_INFO);
+        }
+        self.proc.notify_ready(Some(self.tid), signo);
+        let wake = !self.signal_blocked(signo);
+        if wake {
+            kick(&[self.tid]);
+        }
+        wake
+    }
+
     /// Sends a signal to the thread.
     ///
     /// Returns `true` if the task was woken up by the signal (i.e. the signal
-    /// was not blocked and not ignored).
+    /// was not blocked and not ignored). With `linux-strict`, the threads a
+    /// `SIGCONT` resumes from group-stop are kicked through the
+    /// [`ReschedHook`](super::ReschedHook); send it with
+    /// [`ProcessSignalManager::send_signal`] to get them back.
     ///
     /// See [`ProcessSignalManager::send_signal`] for the process-level version.
     #[must_use]
     pub fn send_signal(&self, sig: SignalInfo) -> bool {
+        // Never fails when the signal may be dropped.
+        self.send_signal_inner(sig, false).unwrap_or(false)
+    }
+
+    /// Like [`send_signal`](Self::send_signal), but fails with
+    /// [`SignalError::WouldBlock`] instead of dropping a real-time signal
+    /// whose queue is full, as `rt_tgsigqueueinfo` does with `EAGAIN`.
+    pub fn try_send_signal(&self, sig: SignalInfo) -> SignalResult<bool> {
+        self.send_signal_inner(sig, true)
+    }
+
+    /// Sends a signal to the thread, failing if `fail_if_full` and its queue
+    /// is full, checked under the same lock as the signal is queued.
+    fn send_signal_inner(&self, sig: SignalInfo, fail_if_full: bool) -> SignalResult<bool> {
+        if self.proc.is_exiting() {
+            return Ok(false);
+        }
         let signo = sig.signo();
+        // The threads resumed by `SIGCONT` can't be returned, so they are
+        // only kicked.
+        #[cfg(feature = "linux-strict")]
+        kick(&self.proc.prepare_signal(signo));
+        let listener = self.proc.listener();
         if self.proc.signal_ignored(signo) {
-            return false;
+            #[cfg(feature = "journal")]
+            self.proc
+                .record_event(JournalEvent::Ignore, self.tid, Some(&sig));
+            #[cfg(feature = "tracepoint")]
+            trace_generate(&sig, Some(self.tid), false, GenerateResult::Ignored);
+            if let Some(listener) = listener {
+                listener.on_ignore(Some(self.tid), &sig);
+            }
+            return Ok(false);
+        }
+
+        #[cfg(feature = "journal")]
+        self.proc
+            .record_event(JournalEvent::Send, self.tid, Some(&sig));
+        #[cfg(feature = "tracepoint")]
+        let traced = sig.clone();
+        let notified = listener.as_ref().map(|_| sig.clone());
+        let pressure = self.proc.pressure_hook();
+        let pressured = pressure.as_ref().map(|_| sig.clone());
+        let depth = if self.proc.coalesce_timer(&sig) {
+            None
+        } else {
+            self.queue_signal(sig, fail_if_full)?
+        };
+        let queued = depth.is_some();
+        #[cfg(feature = "tracepoint")]
+        trace_generate(
+            &traced,
+            Some(self.tid),
+            false,
+            if queued {
+                GenerateResult::Delivered
+            } else {
+                GenerateResult::AlreadyPending
+            },
+        );
+        if let (Some(listener), Some(sig), true) = (listener, notified, queued) {
+            listener.on_send(Some(self.tid), &sig);
+        }
+        if let (Some(pressure), Some(sig), Some(depth)) = (pressure, pressured, depth) {
+            self.proc
+                .check_pressure(pressure, Some(self.tid), &sig, depth);
+        }
+        self.proc.notify_ready(Some(self.tid), signo);
+        let wake = !self.signal_blocked(signo);
+        if wake {
+            kick(&[self.tid]);
+        }
+        Ok(wake)
+    }
+
+    /// Sends a hardware fault raised by the thread itself, such as `SIGSEGV`
+    /// on a bad access, as Linux's `force_sig_fault`.
+    ///
+    /// The signal goes to the queue of the thread, never the shared one, and
+    /// is delivered even if blocked or ignored: the disposition is reset to
+    /// the default and the signal unblocked then, so that a fault that can't
+    /// be handled kills the process. An instance of the signal already
+    /// pending on the thread, sent by someone else, is replaced, so that the
+    /// handler sees the info of the fault. Returns whether the thread needs
+    /// to be woken up.
+    ///
+    /// Fails with [`SignalError::InvalidArgument`] if `sig` is not a fault
+    /// (see [`SignalInfo::is_fault`]).
+    pub fn send_fault(&self, sig: SignalInfo) -> SignalResult<bool> {
+        if !sig.is_fault() {
+            return Err(SignalError::InvalidArgument);
+        }
+        let signo = sig.signo();
+        let blocked = self.signal_blocked(signo);
+        if blocked || self.proc.signal_ignored(signo) {
+            self.proc.with_actions(|actions| {
+                actions[signo].disposition = SignalDisposition::Default;
+            });
+        }
+        if blocked {
+            let mut set = self.blocked();
+            set.remove(signo);
+            self.set_blocked(set);
+        }
+        self.remove_signal(signo);
+        Ok(self.send_signal(sig))
+    }
+
+    /// Puts a signal into the pending queue of the thread. Returns the
+    /// number of its instances left queued, or `None` if it was not queued.
+    ///
+    /// Fails with [`SignalError::WouldBlock`] if `fail_if_full` and the
+    /// queue of a real-time signal is full.
+    fn queue_signal(&self, sig: SignalInfo, fail_if_full: bool) -> SignalResult<Option<usize>> {
+        let signo = sig.signo();
+        let mut pending = self.pending.lock();
+        let queued = if fail_if_full {
+            pending.try_put_signal(sig)?
+        } else {
+            pending.put_signal(sig)
+        };
+        // Set under the lock, so that the flag is never seen clear while the
+        // signal is queued.
+        if queued {
+            self.possibly_has_signal.store(true, Ordering::Release);
         }
+        Ok(queued.then(|| pending.count(signo)))
+    }
 
-        if self.pending.lock().put_signal(sig) {
+    /// Injects a signal on behalf of the tracer, as when resuming the thread
+    /// from a `ptrace` stop with a nonzero signal.
+    ///
+    /// Unlike [`send_signal`](Self::send_signal), the signal is not discarded
+    /// if it's ignored and the tracer is not consulted again; it is acted on
+    /// at the next call to [`check_signals`](Self::check_signals) with the
+    /// disposition in effect then. If the signal is blocked, it is queued as
+    /// a pending signal of the thread instead.
+    pub fn inject_signal(&self, sig: SignalInfo) {
+        // The flag is set under the lock, as on the other paths queueing a
+        // signal.
+        if self.signal_blocked(sig.signo()) {
+            let mut pending = self.pending.lock();
+            if pending.put_signal(sig) {
+                self.possibly_has_signal.store(true, Ordering::Release);
+            }
+        } else {
+            let mut injected = self.injected.lock();
+            *injected = Some(sig);
             self.possibly_has_signal.store(true, Ordering::Release);
         }
-        !self.signal_blocked(signo)
+    }
+
+    /// Generates `SIGXFSZ` for a write that exceeds the file size limit.
+    ///
+    /// As with any other signal, a blocked `SIGXFSZ` stays pending and an
+    /// ignored one is discarded.
+    pub fn send_xfsz(&self) -> XfszAction {
+        if self.send_signal(SignalInfo::new_kernel(Signo::SIGXFSZ)) {
+            XfszAction::Deliver
+        } else {
+            XfszAction::ReturnEfbig
+        }
+    }
+
+    /// Generates `SIGPIPE` for a write to a broken pipe or a shut-down
+    /// socket.
+    ///
+    /// Nothing is sent if `nosignal` is set, as with `MSG_NOSIGNAL`.
+    /// Otherwise, as with any other signal, a blocked `SIGPIPE` stays pending
+    /// and an ignored one is discarded.
+    pub fn send_sigpipe(&self, nosignal: bool) -> PipeAction {
+        if nosignal {
+            return PipeAction::ReturnEpipe;
+        }
+        let fatal = matches!(
+            self.proc
+                .with_actions(|actions| actions[Signo::SIGPIPE].disposition.clone()),
+            SignalDisposition::Default
+        );
+        if !self.send_signal(SignalInfo::new_kernel(Signo::SIGPIPE)) {
+            PipeAction::ReturnEpipe
+        } else if fatal {
+            PipeAction::Fatal
+        } else {
+            PipeAction::Deliver
+        }
+    }
+
+    /// Posts a kernel-private notification to the thread.
+    ///
+    /// Notifications are numbered from 0 to 63, with meanings defined by the
+    /// OS (e.g. termination requests or debugger attach). They are not
+    /// signals: masks, `sigpending` and `sigaction` don't see them, and they
+    /// can't be blocked. The OS collects them at delivery points with
+    /// [`take_notifications`](Self::take_notifications).
+    ///
+    /// The thread is kicked through the [`ReschedHook`](super::ReschedHook)
+    /// and its waits for signals are interrupted, so that it reaches a
+    /// delivery point. Returns `true` if the notification was not already
+    /// pending, or fails with [`SignalError::InvalidArgument`] if `id` is out
+    /// of range.
+    pub fn notify(&self, id: u32) -> SignalResult<bool> {
+        if id >= u64::BITS {
+            return Err(SignalError::InvalidArgument);
+        }
+        let new = self.post_notification(1 << id);
+        self.proc.arrival.notify(usize::MAX);
+        kick(&[self.tid]);
+        Ok(new)
+    }
+
+    /// Sets the bit of a notification, returning whether it was clear. The
+    /// caller wakes the thread up.
+    pub(crate) fn post_notification(&self, bit: u64) -> bool {
+        let new = self.notifications.fetch_or(bit, Ordering::AcqRel) & bit == 0;
+        self.possibly_has_signal.store(true, Ordering::Release);
+        new
+    }
+
+    /// Gets the pending kernel-private notifications as a bitmask.
+    pub fn notifications(&self) -> u64 {
+        self.notifications.load(Ordering::Acquire)
+    }
+
+    /// Takes the pending kernel-private notifications as a bitmask.
+    pub fn take_notifications(&self) -> u64 {
+        if self.notifications() == 0 {
+            return 0;
+        }
+        self.notifications.swap(0, Ordering::AcqRel)
+    }
+
+    /// Gets the metrics of the pending queue lock of the thread. The
+    /// blocked mask takes no lock.
+    #[cfg(feature = "lock-stats")]
+    pub fn lock_stats(&self) -> LockStats {
+        self.pending.counters.stats()
+    }
+
+    /// Resets the metrics of the pending queue lock of the thread.
+    #[cfg(feature = "lock-stats")]
+    pub fn reset_lock_stats(&self) {
+        self.pending.counters.reset();
     }
 
     /// Gets the blocked signals.
     pub fn blocked(&self) -> SignalSet {
-        *self.blocked.lock()
+        SignalSet::from_bits(self.blocked.load(Ordering::Acquire))
     }
 
     /// Sets the blocked signals. Return the old value.
+    ///
+    /// Shared pending signals newly blocked are handed over to other threads
+    /// not blocking them, which are kicked through the
+    /// [`ReschedHook`](super::ReschedHook). If pending signals are unblocked,
+    /// the thread is kicked and its waits are interrupted, so that one of
+    /// them is delivered before the thread returns to user space.
     pub fn set_blocked(&self, mut set: SignalSet) -> SignalSet {
         set.remove(Signo::SIGKILL);
         set.remove(Signo::SIGSTOP);
-        self.possibly_has_signal.store(true, Ordering::Release);
-        let mut guard = self.blocked.lock();
-        let old = *guard;
-        *guard = set;
+        // Changed under the pending lock, so that `check_signals` can't clear
+        // the flag with the old mask after it is set.
+        let old = {
+            let _pending = self.pending.lock();
+            let old = SignalSet::from_bits(self.blocked.swap(set.bits(), Ordering::AcqRel));
+            self.possibly_has_signal.store(true, Ordering::Release);
+            old
+        };
+        self.retarget_shared_pending(old, set);
+        if !(old & !set & self.pending()).is_empty() {
+            self.proc.arrival.notify(usize::MAX);
+            kick(&[self.tid]);
+        }
         old
     }
 
+    /// Kicks other threads to take the shared pending signals that the
+    /// thread has just blocked, as they may have been left to it. This is
+    /// Linux's `retarget_shared_pending`.
+    fn retarget_shared_pending(&self, old: SignalSet, new: SignalSet) {
+        let mut retarget = new & !old & self.proc.pending();
+        if retarget.is_empty() {
+            return;
+        }
+        let mut woken = Vec::new();
+        self.proc.for_each_thread(|thread| {
+            if retarget.is_empty() || thread.tid == self.tid || thread.is_exiting() {
+                return;
+            }
+            let unblocked = retarget & !thread.blocked();
+            if !unblocked.is_empty() {
+                retarget &= !unblocked;
+                woken.push(thread.tid);
+            }
+        });
+        kick(&woken);
+    }
+
     /// Checks if a signal is blocked.
     pub fn signal_blocked(&self, signo: Signo) -> bool {
-        self.blocked.lock().has(signo)
+        self.blocked().has(signo)
     }
 
     /// Gets the signal stack.
@@ -251,4 +1534,102 @@ impl ThreadSignalManager {
     pub fn pending(&self) -> SignalSet {
         self.pending.lock().set | self.proc.pending()
     }
+
+    /// Gets the signal-related fields of `/proc/<pid>/status`, from a
+    /// [`snapshot`](Self::snapshot).
+    pub fn status(&self) -> SignalStatus {
+        self.snapshot().status()
+    }
+
+    /// Takes a consistent view of the blocked mask, the pending sets and the
+    /// action table.
+    ///
+    /// The pending queues of the thread and of the process and the action
+    /// table are locked together while the view is taken, so it never mixes
+    /// states from before and after a send, a dequeue or a `sigaction`.
+    pub fn snapshot(&self) -> SignalSnapshot {
+        // Taken in the lock order (see `crate::sync`).
+        let thread_pending = self.pending.lock();
+        let shared_pending = self.proc.pending.lock();
+        let blocked = self.blocked();
+        let actions = self.proc.with_actions(|actions| actions.clone());
+        SignalSnapshot {
+            thread_pending: thread_pending.set,
+            shared_pending: shared_pending.set,
+            blocked,
+            actions,
+        }
+    }
+
+    /// Exports the thread-level signal state.
+    ///
+    /// The process-level part is exported separately with
+    /// [`ProcessSignalManager::export_state`].
+    pub fn export_state(&self) -> ThreadSignalState {
+        ThreadSignalState {
+            pending: self.pending.lock().iter().cloned().collect(),
+            blocked: self.blocked(),
+            stack: self.stack(),
+            stop: self.stop_kind(),
+        }
+    }
+
+    /// Replaces the thread-level signal state with an exported one.
+    ///
+    /// Fails as [`ProcessSignalManager::import_state`], changing nothing.
+    pub fn import_state(&self, state: ThreadSignalState) -> SignalResult {
+        let mut pending = self.proc.new_pending();
+        for sig in state.pending {
+            pending.restore_signal(sig)?;
+        }
+        {
+            let mut guard = self.pending.lock();
+            *guard = pending;
+            self.possibly_has_signal.store(true, Ordering::Release);
+        }
+        self.set_blocked(state.blocked);
+        self.set_stack(state.stack);
+        self.proc.set_thread_stop(self.tid, state.stop);
+        Ok(())
+    }
+
+    /// Applies the signal attributes of `posix_spawn` to the freshly forked
+    /// thread, before it runs: the listed signals are reset to the default
+    /// disposition first, then the blocked mask is set.
+    ///
+    /// The action table is unshared first (see
+    /// [`ProcessSignalManager::unshare_actions`]), so that resetting it never
+    /// changes the parent. Pending signals ignored by default once reset are
+    /// discarded.
+    ///
+    /// Fails with [`SignalError::InvalidArgument`], changing nothing, if
+    /// `sigdefault` contains `SIGKILL` or `SIGSTOP`, as `sigaction` does.
+    pub fn apply_spawn_attrs(&self, attrs: &SpawnSignalAttrs) -> SignalResult {
+        if let Some(sigdefault) = attrs.sigdefault {
+            if sigdefault.has(Signo::SIGKILL) || sigdefault.has(Signo::SIGSTOP) {
+                return Err(SignalError::InvalidArgument);
+            }
+            self.proc.unshare_actions();
+            let mut actions = self.proc.with_actions(|actions| actions.clone());
+            for signo in Signo::iter().filter(|&signo| sigdefault.has(signo)) {
+                actions[signo] = SignalAction::default();
+            }
+            self.proc.replace_actions(actions);
+        }
+        if let Some(sigmask) = attrs.sigmask {
+            self.set_blocked(sigmask);
+        }
+        Ok(())
+    }
+
+    /// Applies the signal semantics of `execve` to the calling thread.
+    ///
+    /// Besides [`ProcessSignalManager::on_exec`], this disables the alternate
+    /// signal stack and forgets the outstanding signal frames. The blocked
+    /// mask and pending signals are preserved.
+    pub fn on_exec(&self, default_restorer: usize) {
+        self.proc.on_exec(default_restorer);
+        *self.stack.lock() = SignalStack::default();
+        self.frames.lock().clear();
+    }
 }
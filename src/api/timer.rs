@@ -0,0 +1,283 @@
+use alloc::vec::Vec;
+use core::{future::Future, time::Duration};
+
+use crate::{
+    SignalInfo, Signo,
+    api::{ProcessSignalManager, ThreadSignalManager},
+};
+
+/// Monotonic clock of the OS, against which deadlines of signal waits are
+/// given.
+pub trait MonotonicClock {
+    /// Gets the current reading of the clock.
+    fn now(&self) -> Duration;
+
+    /// Sleeps until the clock reads `deadline` or later.
+    fn sleep_until(&self, deadline: Duration) -> impl Future<Output = ()>;
+}
+
+/// CPU-time clock measured by a timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuClock {
+    /// CPU time of the whole process (`CLOCK_PROCESS_CPUTIME_ID`).
+    Process,
+    /// CPU time of a thread (`CLOCK_THREAD_CPUTIME_ID`).
+    Thread(u32),
+}
+
+/// A POSIX timer on a CPU-time clock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuTimer {
+    /// The clock measured.
+    pub clock: CpuClock,
+    /// The signal sent on expiry.
+    pub signo: Signo,
+    /// The `sigevent` value attached to the signal.
+    pub value: usize,
+    /// The thread the signal is directed to (`SIGEV_THREAD_ID`), or `None`
+    /// for the process.
+    pub target: Option<u32>,
+    /// The clock reading at which the timer expires next, or zero if it is
+    /// disarmed.
+    pub expires: Duration,
+    /// The period of the timer, or zero if it expires once.
+    pub interval: Duration,
+}
+
+impl CpuTimer {
+    /// Checks if the timer is armed.
+    pub fn armed(&self) -> bool {
+        !self.expires.is_zero()
+    }
+
+    /// Expires the timer if it is due at clock reading `now`.
+    ///
+    /// Returns the signal to send, with the periods missed since the
+    /// expiry counted in its overrun, and rearms a periodic timer for the
+    /// next period after `now`.
+    pub(crate) fn expire(&mut self, id: i32, now: Duration) -> Option<SignalInfo> {
+        if !self.armed() || now < self.expires {
+            return None;
+        }
+        let mut sig = SignalInfo::new_timer(self.signo, id, self.value);
+        if self.interval.is_zero() {
+            self.expires = Duration::ZERO;
+        } else {
+            let missed = (now - self.expires).as_nanos() / self.interval.as_nanos();
+            sig.set_overrun(missed.try_into().unwrap_or(i32::MAX));
+            self.expires += self
+                .interval
+                .saturating_mul((missed + 1).try_into().unwrap_or(u32::MAX));
+        }
+        Some(sig)
+    }
+}
+
+/// Receiver of CPU time updates from the scheduler, driving the timers on
+/// CPU-time clocks.
+pub trait CpuTimeTick {
+    /// Called by the scheduler on a tick of a running thread with the CPU
+    /// time used so far by the thread and by its whole process.
+    ///
+    /// Expires the timers due and sends their signals. Returns the threads to
+    /// wake up.
+    fn on_task_tick(&self, thread_cputime: Duration, process_cputime: Duration) -> Vec<u32>;
+}
+
+impl CpuTimeTick for ThreadSignalManager {
+    fn on_task_tick(&self, thread_cputime: Duration, process_cputime: Duration) -> Vec<u32> {
+        let proc = self.process();
+        let expired: Vec<_> = proc
+            .cpu_timers
+            .lock()
+            .iter_mut()
+            .filter_map(|(id, timer)| {
+                let now = match timer.clock {
+                    CpuClock::Process => process_cputime,
+                    CpuClock::Thread(tid) if tid == self.tid() => thread_cputime,
+                    CpuClock::Thread(_) => return None,
+                };
+                Some((timer.target, timer.expire(*id, now)?))
+            })
+            .collect();
+
+        let mut wake = Vec::new();
+        for (target, sig) in expired {
+            match target.and_then(|tid| proc.thread(tid)) {
+                Some(thread) => {
+                    if thread.send_signal(sig) {
+                        wake.push(thread.tid());
+                    }
+                }
+                None => wake.extend(proc.send_signal(sig).tids()),
+            }
+        }
+        wake.sort_unstable();
+        wake.dedup();
+        wake
+    }
+}
+
+/// An interval timer driven by CPU time, set with `setitimer`.
+///
+/// `ITIMER_REAL` runs on the wall clock and is not one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuItimer {
+    /// `ITIMER_VIRTUAL`, counting the user time of the process and sending
+    /// `SIGVTALRM`.
+    Virtual,
+    /// `ITIMER_PROF`, counting the user and system time of the process and
+    /// sending `SIGPROF`.
+    Prof,
+}
+
+impl CpuItimer {
+    /// Gets the signal sent on expiry.
+    pub fn signo(self) -> Signo {
+        match self {
+            Self::Virtual => Signo::SIGVTALRM,
+            Self::Prof => Signo::SIGPROF,
+        }
+    }
+}
+
+/// Setting of an interval timer, as in `struct itimerval`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Itimer {
+    /// The time left until the next expiry, or zero if the timer is
+    /// disarmed.
+    pub value: Duration,
+    /// The period the timer is reloaded with on expiry, or zero if it
+    /// expires once.
+    pub interval: Duration,
+}
+
+impl Itimer {
+    /// Charges `time` to the timer.
+    ///
+    /// Returns `true` if the timer expires. A periodic timer is reloaded
+    /// with the time charged past the expiry deducted, so that it does not
+    /// drift.
+    pub(crate) fn charge(&mut self, time: Duration) -> bool {
+        if self.value.is_zero() {
+            return false;
+        }
+        if let Some(value) = self.value.checked_sub(time)
+            && !value.is_zero()
+        {
+            self.value = value;
+            return false;
+        }
+        let excess = time - self.value;
+        self.value = if self.interval.is_zero() {
+            Duration::ZERO
+        } else {
+            // Expiries within the excess are folded into this one.
+            let excess =
+                Duration::from_nanos((excess.as_nanos() % self.interval.as_nanos()) as u64);
+            self.interval - excess
+        };
+        true
+    }
+}
+
+/// Receiver of the CPU time charged by the scheduler to a process, driving
+/// `ITIMER_VIRTUAL` and `ITIMER_PROF`.
+pub trait CpuTimeAccounting {
+    /// Charges `user` and `system` time used since the last call.
+    ///
+    /// Sends `SIGVTALRM` and `SIGPROF` when the timers expire. Returns the
+    /// threads to wake up.
+    fn charge(&self, user: Duration, system: Duration) -> Vec<u32>;
+}
+
+impl CpuTimeAccounting for ProcessSignalManager {
+    fn charge(&self, user: Duration, system: Duration) -> Vec<u32> {
+        let expired = {
+            let mut itimers = self.cpu_itimers.lock();
+            [
+                itimers[CpuItimer::Virtual as usize]
+                    .charge(user)
+                    .then_some(CpuItimer::Virtual),
+                itimers[CpuItimer::Prof as usize]
+                    .charge(user + system)
+                    .then_some(CpuItimer::Prof),
+            ]
+        };
+
+        let mut wake = Vec::new();
+        for itimer in expired.into_iter().flatten() {
+            wake.extend(
+                self.send_signal(SignalInfo::new_kernel(itimer.signo()))
+                    .tids(),
+            );
+        }
+        wake.sort_unstable();
+        wake.dedup();
+        wake
+    }
+}
+
+/// `ITIMER_REAL`, running on the wall clock of the OS.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RealItimer {
+    /// The clock reading at which the timer expires next, or zero if it is
+    /// disarmed.
+    deadline: Duration,
+    interval: Duration,
+}
+
+impl RealItimer {
+    pub(crate) fn new(itimer: Itimer, now: Duration) -> Self {
+        Self {
+            deadline: if itimer.value.is_zero() {
+                Duration::ZERO
+            } else {
+                now + itimer.value
+            },
+            interval: itimer.interval,
+        }
+    }
+
+    pub(crate) fn get(&self, now: Duration) -> Itimer {
+        let value = if self.deadline.is_zero() {
+            Duration::ZERO
+        } else {
+            // A timer due but not expired yet reports the least nonzero time
+            // left, since zero means disarmed.
+            self.deadline
+                .saturating_sub(now)
+                .max(Duration::from_nanos(1))
+        };
+        Itimer {
+            value,
+            interval: self.interval,
+        }
+    }
+
+    pub(crate) fn deadline(&self) -> Option<Duration> {
+        (!self.deadline.is_zero()).then_some(self.deadline)
+    }
+
+    /// Expires the timer if it is due at `now`.
+    ///
+    /// Returns `true` if it expires. A periodic timer is rearmed for the
+    /// first period of its original schedule after `now`: periods missed
+    /// while the OS was late are skipped rather than made up for, and the
+    /// schedule does not drift by the latency.
+    pub(crate) fn expire(&mut self, now: Duration) -> bool {
+        if self.deadline.is_zero() || now < self.deadline {
+            return false;
+        }
+        self.deadline = if self.interval.is_zero() {
+            Duration::ZERO
+        } else {
+            let missed = (now - self.deadline).as_nanos() / self.interval.as_nanos();
+            self.deadline
+                + self
+                    .interval
+                    .saturating_mul((missed + 1).try_into().unwrap_or(u32::MAX))
+        };
+        true
+    }
+}
@@ -15,6 +15,27 @@ signal_trampoline:
 "
 );
 
+/// Length of the `svc` instruction.
+pub const SYSCALL_INSN_LEN: usize = 4;
+
+/// Syscall number of `restart_syscall`.
+pub const SYSNO_RESTART_SYSCALL: usize = 128;
+
+/// Bits of a user address used as tag, hidden from `si_addr` unless
+/// `SA_EXPOSE_TAGBITS` is set.
+pub const ADDR_TAG_MASK: usize = 0xff << 56;
+
+/// Condition flags of `pstate`, the only bits userspace may change on
+/// `rt_sigreturn`.
+const PSTATE_NZCV: u64 = 0xf << 28;
+
+/// Magic of the `esr_context` record in the reserved area of the machine
+/// context.
+pub const ESR_MAGIC: u32 = 0x4553_5201;
+
+/// Size of the `esr_context` record.
+const ESR_CONTEXT_SIZE: u32 = 16;
+
 #[repr(C, align(16))]
 #[derive(Clone)]
 struct MContextPadding([u8; 4096]);
@@ -42,14 +63,110 @@ impl MContext {
         }
     }
 
+    /// Restores the machine context on top of the live context `uctx`.
+    ///
+    /// The machine context is user memory, so only the condition flags of
+    /// `pstate` are taken from it. The exception level, stack pointer
+    /// selection and interrupt masks are kept.
     pub fn restore(&self, uctx: &mut UserContext) {
         uctx.x = self.regs;
         uctx.sp = self.sp;
         uctx.elr = self.pc;
-        uctx.spsr = self.pstate;
+        uctx.spsr = (uctx.spsr & !PSTATE_NZCV) | (self.pstate & PSTATE_NZCV);
+    }
+
+    /// Gets the faulting address (`fault_address`).
+    pub fn fault_address(&self) -> usize {
+        self.fault_address as _
+    }
+
+    /// Sets the faulting address (`fault_address`), for a fault signal.
+    pub fn set_fault_address(&mut self, addr: usize) {
+        self.fault_address = addr as _;
+    }
+
+    /// Gets the exception syndrome of the `esr_context` record, if there is
+    /// one.
+    pub fn esr(&self) -> Option<u64> {
+        let reserved = &self.__reserved.0;
+        (reserved[..4] == ESR_MAGIC.to_ne_bytes())
+            .then(|| u64::from_ne_bytes(reserved[8..16].try_into().unwrap()))
+    }
+
+    /// Records the exception syndrome (`ESR_EL1`) of a synchronous fault in
+    /// an `esr_context` record, as the first record of the reserved area.
+    /// The zeros after it terminate the record list.
+    pub fn set_fault_code(&mut self, esr: u64) {
+        let reserved = &mut self.__reserved.0;
+        reserved[..4].copy_from_slice(&ESR_MAGIC.to_ne_bytes());
+        reserved[4..8].copy_from_slice(&ESR_CONTEXT_SIZE.to_ne_bytes());
+        reserved[8..16].copy_from_slice(&esr.to_ne_bytes());
+    }
+
+    /// Gets the program counter.
+    pub fn pc(&self) -> usize {
+        self.pc as _
+    }
+
+    /// Sets the program counter.
+    pub fn set_pc(&mut self, pc: usize) {
+        self.pc = pc as _;
+    }
+
+    /// Gets the stack pointer.
+    pub fn sp(&self) -> usize {
+        self.sp as _
+    }
+
+    /// Sets the stack pointer.
+    pub fn set_sp(&mut self, sp: usize) {
+        self.sp = sp as _;
+    }
+
+    /// Gets the return address register (`x30`).
+    pub fn ra(&self) -> usize {
+        self.regs[30] as _
+    }
+
+    /// Sets the return address register (`x30`).
+    pub fn set_ra(&mut self, ra: usize) {
+        self.regs[30] = ra as _;
+    }
+
+    /// Gets the `index`-th syscall argument register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 6.
+    pub fn arg(&self, index: usize) -> usize {
+        assert!(index < 6, "invalid argument index {index}");
+        self.regs[index] as _
+    }
+
+    /// Sets the `index`-th syscall argument register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 6.
+    pub fn set_arg(&mut self, index: usize, value: usize) {
+        assert!(index < 6, "invalid argument index {index}");
+        self.regs[index] = value as _;
+    }
+
+    /// Gets the syscall return value register (`x0`).
+    pub fn retval(&self) -> usize {
+        self.regs[0] as _
+    }
+
+    /// Sets the syscall return value register (`x0`).
+    pub fn set_retval(&mut self, value: usize) {
+        self.regs[0] = value as _;
     }
 }
 
+/// All defined `uc_flags` bits. There are none on this architecture.
+pub(crate) const UC_FLAGS: usize = 0;
+
 #[repr(C)]
 #[derive(Clone)]
 pub struct UContext {
@@ -57,10 +174,14 @@ pub struct UContext {
     pub link: usize,
     pub stack: SignalStack,
     pub sigmask: SignalSet,
-    __unused: [u8; 1024 / 8 - size_of::<SignalSet>()],
+    /// Pads the mask to the 1024 bits of libc's `sigset_t`. A literal, so
+    /// that the C header generator can read it.
+    __unused: [u8; 120],
     pub mcontext: MContext,
 }
 
+const _: () = assert!(size_of::<SignalSet>() + 120 == 1024 / 8);
+
 impl UContext {
     pub fn new(uctx: &UserContext, sigmask: SignalSet) -> Self {
         Self {
@@ -68,8 +189,14 @@ impl UContext {
             link: 0,
             stack: SignalStack::default(),
             sigmask,
-            __unused: [0; 1024 / 8 - size_of::<SignalSet>()],
+            __unused: [0; 120],
             mcontext: MContext::new(uctx),
         }
     }
+
+    /// Restores the machine context into `uctx`. `uc_flags` has no defined
+    /// bits on this architecture.
+    pub fn restore(&self, uctx: &mut UserContext) {
+        self.mcontext.restore(uctx);
+    }
 }
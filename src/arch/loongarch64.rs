@@ -15,6 +15,15 @@ signal_trampoline:
 "
 );
 
+/// Length of the `syscall` instruction.
+pub const SYSCALL_INSN_LEN: usize = 4;
+
+/// Syscall number of `restart_syscall`.
+pub const SYSNO_RESTART_SYSCALL: usize = 128;
+
+/// Bits of a user address used as tag. There is no address tagging.
+pub const ADDR_TAG_MASK: usize = 0;
+
 #[repr(C, align(16))]
 #[derive(Clone)]
 pub struct MContext {
@@ -32,12 +41,108 @@ impl MContext {
         }
     }
 
+    /// Restores the machine context on top of the live context `uctx`.
+    ///
+    /// Only the program counter and general registers are taken from the
+    /// frame; `prmd`, holding the privilege level and interrupt enable, is
+    /// kept.
     pub fn restore(&self, uctx: &mut UserContext) {
         uctx.era = self.sc_pc as _;
         uctx.regs = self.sc_regs;
     }
+
+    /// Gets the faulting address. The machine context has no slot for it on
+    /// this architecture, so it is always zero: handlers read `si_addr`.
+    pub fn fault_address(&self) -> usize {
+        0
+    }
+
+    /// Sets the faulting address. A no-op on this architecture.
+    pub fn set_fault_address(&mut self, _addr: usize) {}
+
+    /// Records the architecture fault code of a fault. A no-op on this
+    /// architecture.
+    pub fn set_fault_code(&mut self, _code: u64) {}
+
+    /// Gets the program counter.
+    pub fn pc(&self) -> usize {
+        self.sc_pc as _
+    }
+
+    /// Sets the program counter.
+    pub fn set_pc(&mut self, pc: usize) {
+        self.sc_pc = pc as _;
+    }
+
+    /// Gets the stack pointer.
+    pub fn sp(&self) -> usize {
+        self.sc_regs.sp
+    }
+
+    /// Sets the stack pointer.
+    pub fn set_sp(&mut self, sp: usize) {
+        self.sc_regs.sp = sp;
+    }
+
+    /// Gets the return address register.
+    pub fn ra(&self) -> usize {
+        self.sc_regs.ra
+    }
+
+    /// Sets the return address register.
+    pub fn set_ra(&mut self, ra: usize) {
+        self.sc_regs.ra = ra;
+    }
+
+    /// Gets the `index`-th syscall argument register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 6.
+    pub fn arg(&self, index: usize) -> usize {
+        match index {
+            0 => self.sc_regs.a0,
+            1 => self.sc_regs.a1,
+            2 => self.sc_regs.a2,
+            3 => self.sc_regs.a3,
+            4 => self.sc_regs.a4,
+            5 => self.sc_regs.a5,
+            _ => panic!("invalid argument index {index}"),
+        }
+    }
+
+    /// Sets the `index`-th syscall argument register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 6.
+    pub fn set_arg(&mut self, index: usize, value: usize) {
+        let reg = match index {
+            0 => &mut self.sc_regs.a0,
+            1 => &mut self.sc_regs.a1,
+            2 => &mut self.sc_regs.a2,
+            3 => &mut self.sc_regs.a3,
+            4 => &mut self.sc_regs.a4,
+            5 => &mut self.sc_regs.a5,
+            _ => panic!("invalid argument index {index}"),
+        };
+        *reg = value;
+    }
+
+    /// Gets the syscall return value register (`a0`).
+    pub fn retval(&self) -> usize {
+        self.sc_regs.a0
+    }
+
+    /// Sets the syscall return value register (`a0`).
+    pub fn set_retval(&mut self, value: usize) {
+        self.sc_regs.a0 = value;
+    }
 }
 
+/// All defined `uc_flags` bits. There are none on this architecture.
+pub(crate) const UC_FLAGS: usize = 0;
+
 #[repr(C)]
 #[derive(Clone)]
 pub struct UContext {
@@ -45,10 +150,14 @@ pub struct UContext {
     pub link: usize,
     pub stack: SignalStack,
     pub sigmask: SignalSet,
-    __unused: [u8; 1024 / 8 - size_of::<SignalSet>()],
+    /// Pads the mask to the 1024 bits of libc's `sigset_t`. A literal, so
+    /// that the C header generator can read it.
+    __unused: [u8; 120],
     pub mcontext: MContext,
 }
 
+const _: () = assert!(size_of::<SignalSet>() + 120 == 1024 / 8);
+
 impl UContext {
     pub fn new(uctx: &UserContext, sigmask: SignalSet) -> Self {
         Self {
@@ -56,8 +165,14 @@ impl UContext {
             link: 0,
             stack: SignalStack::default(),
             sigmask,
-            __unused: [0; 1024 / 8 - size_of::<SignalSet>()],
+            __unused: [0; 120],
             mcontext: MContext::new(uctx),
         }
     }
+
+    /// Restores the machine context into `uctx`. `uc_flags` has no defined
+    /// bits on this architecture.
+    pub fn restore(&self, uctx: &mut UserContext) {
+        self.mcontext.restore(uctx);
+    }
 }
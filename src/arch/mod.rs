@@ -1,3 +1,7 @@
+use starry_vm::VmPtr;
+
+use crate::{SignalError, SignalResult, Signo};
+
 cfg_if::cfg_if! {
     if #[cfg(target_arch = "x86_64")] {
         mod x86_64;
@@ -16,6 +20,31 @@ cfg_if::cfg_if! {
     }
 }
 
+impl UContext {
+    /// Reads a ucontext from user memory, such as the one in a signal frame
+    /// after the handler may have modified it.
+    ///
+    /// Fails with [`SignalError::BadAddress`] if it cannot be read and with
+    /// [`SignalError::InvalidArgument`] if `uc_flags` has undefined bits set.
+    /// `SIGKILL` and `SIGSTOP` are removed from the mask read, since they may
+    /// never be blocked.
+    ///
+    /// The padding after the mask is not checked: libc's `sigset_t` spans it,
+    /// and `sigfillset` fills it.
+    pub fn read_from_user(ptr: *const UContext) -> SignalResult<Self> {
+        let value = ptr.vm_read_uninit()?;
+        // SAFETY: A ucontext only contains integers, for which every bit
+        // pattern is valid.
+        let mut ucontext = unsafe { value.assume_init() };
+        if ucontext.flags & !UC_FLAGS != 0 {
+            return Err(SignalError::InvalidArgument);
+        }
+        ucontext.sigmask.remove(Signo::SIGKILL);
+        ucontext.sigmask.remove(Signo::SIGSTOP);
+        Ok(ucontext)
+    }
+}
+
 pub fn signal_trampoline_address() -> usize {
     unsafe extern "C" {
         safe static signal_trampoline: [u8; 0];
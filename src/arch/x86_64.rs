@@ -16,6 +16,30 @@ signal_trampoline:
 "
 );
 
+/// Length of the `syscall` instruction.
+pub const SYSCALL_INSN_LEN: usize = 2;
+
+/// Syscall number of `restart_syscall`.
+pub const SYSNO_RESTART_SYSCALL: usize = 219;
+
+/// Bits of a user address used as tag. There is no address tagging.
+pub const ADDR_TAG_MASK: usize = 0;
+
+/// `rflags` bits userspace may change on `rt_sigreturn`: CF, PF, AF, ZF, SF,
+/// TF, DF, OF, RF and AC. IOPL, IF and the other system flags are kept.
+const USER_RFLAGS: usize = 0x5_0dd5;
+
+/// `uc_flags` bit: `fpstate` points to an XSAVE area rather than a plain
+/// FXSAVE one.
+pub const UC_FP_XSTATE: usize = 0x1;
+/// `uc_flags` bit: the `ss` slot of the machine context is saved.
+pub const UC_SIGCONTEXT_SS: usize = 0x2;
+/// `uc_flags` bit: `ss` is restored as saved on `rt_sigreturn`.
+pub const UC_STRICT_RESTORE_SS: usize = 0x4;
+
+/// All defined `uc_flags` bits.
+pub(crate) const UC_FLAGS: usize = UC_FP_XSTATE | UC_SIGCONTEXT_SS | UC_STRICT_RESTORE_SS;
+
 #[repr(C, align(16))]
 #[derive(Clone)]
 pub struct MContext {
@@ -40,7 +64,7 @@ pub struct MContext {
     cs: u16,
     gs: u16,
     fs: u16,
-    _pad: u16,
+    ss: u16,
     err: usize,
     trapno: usize,
     oldmask: usize,
@@ -71,9 +95,10 @@ impl MContext {
             rip: uctx.rip as _,
             eflags: uctx.rflags as _,
             cs: uctx.cs as _,
+            // As on Linux, `fs` and `gs` are not saved in 64-bit mode.
             gs: 0,
             fs: 0,
-            _pad: 0,
+            ss: uctx.ss as _,
             err: uctx.error_code as _,
             trapno: uctx.vector as _,
             oldmask: 0,
@@ -83,6 +108,10 @@ impl MContext {
         }
     }
 
+    /// Restores the machine context on top of the live context `uctx`.
+    ///
+    /// The machine context is user memory, so only the user-modifiable bits
+    /// of `rflags` are taken from it, and `cs` is kept.
     pub fn restore(&self, uctx: &mut UserContext) {
         uctx.r8 = self.r8 as _;
         uctx.r9 = self.r9 as _;
@@ -101,11 +130,99 @@ impl MContext {
         uctx.rcx = self.rcx as _;
         uctx.rsp = self.rsp as _;
         uctx.rip = self.rip as _;
-        uctx.rflags = self.eflags as _;
-        uctx.cs = self.cs as _;
+        uctx.rflags = ((uctx.rflags as usize & !USER_RFLAGS) | (self.eflags & USER_RFLAGS)) as _;
         uctx.error_code = self.err as _;
         uctx.vector = self.trapno as _;
     }
+
+    /// Gets the error code of the exception that raised the signal (`err`).
+    pub fn err(&self) -> usize {
+        self.err
+    }
+
+    /// Gets the vector of the exception that raised the signal (`trapno`).
+    pub fn trapno(&self) -> usize {
+        self.trapno
+    }
+
+    /// Gets the faulting address (`cr2`).
+    pub fn fault_address(&self) -> usize {
+        self.cr2
+    }
+
+    /// Sets the faulting address (`cr2`), for a fault signal.
+    pub fn set_fault_address(&mut self, addr: usize) {
+        self.cr2 = addr;
+    }
+
+    /// Records the architecture fault code of a fault. A no-op on this
+    /// architecture, where the error code is saved from the trap frame.
+    pub fn set_fault_code(&mut self, _code: u64) {}
+
+    /// Gets the program counter (`rip`).
+    pub fn pc(&self) -> usize {
+        self.rip
+    }
+
+    /// Sets the program counter (`rip`).
+    pub fn set_pc(&mut self, pc: usize) {
+        self.rip = pc;
+    }
+
+    /// Gets the stack pointer (`rsp`).
+    pub fn sp(&self) -> usize {
+        self.rsp
+    }
+
+    /// Sets the stack pointer (`rsp`).
+    pub fn set_sp(&mut self, sp: usize) {
+        self.rsp = sp;
+    }
+
+    /// Gets the `index`-th syscall argument register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 6.
+    pub fn arg(&self, index: usize) -> usize {
+        match index {
+            0 => self.rdi,
+            1 => self.rsi,
+            2 => self.rdx,
+            3 => self.r10,
+            4 => self.r8,
+            5 => self.r9,
+            _ => panic!("invalid argument index {index}"),
+        }
+    }
+
+    /// Sets the `index`-th syscall argument register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than 6.
+    pub fn set_arg(&mut self, index: usize, value: usize) {
+        let reg = match index {
+            0 => &mut self.rdi,
+            1 => &mut self.rsi,
+            2 => &mut self.rdx,
+            3 => &mut self.r10,
+            4 => &mut self.r8,
+            5 => &mut self.r9,
+            _ => panic!("invalid argument index {index}"),
+        };
+        *reg = value;
+    }
+
+    /// Gets the syscall return value register (`rax`).
+    pub fn retval(&self) -> usize {
+        self.rax
+    }
+
+    /// Sets the syscall return value register (`rax`).
+    pub fn set_retval(&mut self, value: usize) {
+        self.rax = value;
+    }
 }
 
 #[repr(C)]
@@ -119,13 +236,24 @@ pub struct UContext {
 }
 
 impl UContext {
+    /// Creates a ucontext. No FPU state is saved, so [`UC_FP_XSTATE`] is never
+    /// set.
     pub fn new(uctx: &UserContext, sigmask: SignalSet) -> Self {
         Self {
-            flags: 0,
+            flags: UC_SIGCONTEXT_SS | UC_STRICT_RESTORE_SS,
             link: 0,
             stack: SignalStack::default(),
             mcontext: MContext::new(uctx),
             sigmask,
         }
     }
+
+    /// Restores the machine context into `uctx`, including `ss` if
+    /// [`UC_SIGCONTEXT_SS`] is set. `ss` is forced to privilege level 3.
+    pub fn restore(&self, uctx: &mut UserContext) {
+        self.mcontext.restore(uctx);
+        if self.flags & UC_SIGCONTEXT_SS != 0 {
+            uctx.ss = (self.mcontext.ss | 3) as _;
+        }
+    }
 }
@@ -0,0 +1,77 @@
+//! Core dump support.
+//!
+//! When a signal whose action is [`SignalOSAction::CoreDump`] is delivered,
+//! the thread signal manager captures a [`CoreDumpContext`], which the OS
+//! retrieves with [`ThreadSignalManager::take_coredump`]. The context can
+//! drive a [`CoreDumpWriter`] to emit the signal-related ELF notes.
+//!
+//! [`SignalOSAction::CoreDump`]: crate::SignalOSAction::CoreDump
+//! [`ThreadSignalManager::take_coredump`]: crate::api::ThreadSignalManager::take_coredump
+
+use core::{mem, slice};
+
+use axcpu::uspace::UserContext;
+
+use crate::{SignalInfo, SignalSet, arch::UContext};
+
+/// ELF note type of the process status (`struct elf_prstatus`).
+pub const NT_PRSTATUS: u32 = 1;
+/// ELF note type of the fatal signal (`siginfo_t`).
+pub const NT_SIGINFO: u32 = 0x5349_4749;
+
+/// Everything the crate knows about a thread killed with a core dump.
+#[derive(Clone)]
+pub struct CoreDumpContext {
+    /// The fatal signal.
+    pub siginfo: SignalInfo,
+    /// The user context at the time the signal was delivered.
+    pub uctx: UserContext,
+    /// The user context in `ucontext_t` layout.
+    pub ucontext: UContext,
+    /// The signals blocked when the signal was delivered.
+    pub blocked: SignalSet,
+}
+
+impl CoreDumpContext {
+    pub(crate) fn new(siginfo: SignalInfo, uctx: UserContext, blocked: SignalSet) -> Self {
+        Self {
+            ucontext: UContext::new(&uctx, blocked),
+            siginfo,
+            uctx,
+            blocked,
+        }
+    }
+
+    /// Returns the raw bytes of the `NT_SIGINFO` note descriptor.
+    pub fn siginfo_bytes(&self) -> &[u8] {
+        // SAFETY: `SignalInfo` is a plain C struct.
+        unsafe {
+            slice::from_raw_parts(
+                (&self.siginfo as *const SignalInfo).cast(),
+                mem::size_of::<SignalInfo>(),
+            )
+        }
+    }
+
+    /// Writes the `NT_PRSTATUS` and `NT_SIGINFO` notes of the thread.
+    pub fn write_notes<W: CoreDumpWriter + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        writer.write_prstatus(self)?;
+        writer.write_note("CORE", NT_SIGINFO, self.siginfo_bytes())
+    }
+}
+
+/// Sink of the ELF notes of a core dump.
+pub trait CoreDumpWriter {
+    /// The error returned when writing fails.
+    type Error;
+
+    /// Writes a note with the given name, type and descriptor.
+    fn write_note(&mut self, name: &str, ty: u32, desc: &[u8]) -> Result<(), Self::Error>;
+
+    /// Writes the `NT_PRSTATUS` note of the thread.
+    ///
+    /// `struct elf_prstatus` contains process information the crate doesn't
+    /// have, so the OS builds it from `ctx` and passes it to
+    /// [`write_note`](Self::write_note) with type [`NT_PRSTATUS`].
+    fn write_prstatus(&mut self, ctx: &CoreDumpContext) -> Result<(), Self::Error>;
+}
@@ -0,0 +1,70 @@
+use core::fmt;
+
+use starry_vm::VmError;
+
+/// Errors of signal operations.
+///
+/// With the `axerrno` feature, it converts into [`LinuxError`] for syscall
+/// implementations.
+///
+/// [`LinuxError`]: axerrno::LinuxError
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalError {
+    /// The signal number is out of range.
+    InvalidSignal,
+    /// An argument other than the signal number is invalid.
+    InvalidArgument,
+    /// The caller is not allowed to perform the operation.
+    Permission,
+    /// No target thread or process exists.
+    NoSuchProcess,
+    /// The alternate signal stack is smaller than `MINSIGSTKSZ`.
+    StackTooSmall,
+    /// A user memory access failed.
+    BadAddress,
+    /// The operation can't complete without blocking.
+    WouldBlock,
+    /// The operation was interrupted by a signal.
+    Interrupted,
+}
+
+/// A specialized [`Result`] type with [`SignalError`] as the error type.
+pub type SignalResult<T = ()> = Result<T, SignalError>;
+
+impl fmt::Display for SignalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InvalidSignal => "invalid signal",
+            Self::InvalidArgument => "invalid argument",
+            Self::Permission => "operation not permitted",
+            Self::NoSuchProcess => "no such process",
+            Self::StackTooSmall => "signal stack too small",
+            Self::BadAddress => "bad address",
+            Self::WouldBlock => "operation would block",
+            Self::Interrupted => "interrupted",
+        })
+    }
+}
+
+impl core::error::Error for SignalError {}
+
+impl From<VmError> for SignalError {
+    fn from(_: VmError) -> Self {
+        Self::BadAddress
+    }
+}
+
+#[cfg(feature = "axerrno")]
+impl From<SignalError> for axerrno::LinuxError {
+    fn from(err: SignalError) -> Self {
+        match err {
+            SignalError::InvalidSignal | SignalError::InvalidArgument => Self::EINVAL,
+            SignalError::Permission => Self::EPERM,
+            SignalError::NoSuchProcess => Self::ESRCH,
+            SignalError::StackTooSmall => Self::ENOMEM,
+            SignalError::BadAddress => Self::EFAULT,
+            SignalError::WouldBlock => Self::EAGAIN,
+            SignalError::Interrupted => Self::EINTR,
+        }
+    }
+}
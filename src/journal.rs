@@ -0,0 +1,92 @@
+//! Signal event journal for debugging.
+//!
+//! Each [`ProcessSignalManager`](crate::api::ProcessSignalManager) keeps the
+//! most recent [`JOURNAL_CAPACITY`] signal events in a ring buffer, which can
+//! be read with
+//! [`ProcessSignalManager::journal`](crate::api::ProcessSignalManager::journal).
+
+use alloc::vec::Vec;
+
+use kspin::SpinNoIrq;
+
+use crate::Signo;
+
+/// Number of events kept by each journal.
+pub const JOURNAL_CAPACITY: usize = 128;
+
+static TIME_SOURCE: SpinNoIrq<fn() -> u64> = SpinNoIrq::new(|| 0);
+
+/// Sets the function used to timestamp journal entries.
+///
+/// The unit is up to the OS. Timestamps are `0` until this is called.
+pub fn set_time_source(source: fn() -> u64) {
+    *TIME_SOURCE.lock() = source;
+}
+
+/// Kind of a journaled event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalEvent {
+    /// A signal was queued.
+    Send,
+    /// A signal was delivered, either to a handler or as an OS action.
+    Deliver,
+    /// A signal was discarded because it is ignored.
+    Ignore,
+    /// A signal frame was restored by `sigreturn`.
+    Sigreturn,
+}
+
+/// An entry of the journal.
+#[derive(Debug, Clone, Copy)]
+pub struct JournalEntry {
+    /// Sequence number of the entry within its journal.
+    pub seq: u64,
+    /// Timestamp from the function set by [`set_time_source`].
+    pub timestamp: u64,
+    /// Kind of the event.
+    pub event: JournalEvent,
+    /// The thread involved, or `0` for a process-directed signal which was
+    /// not routed to a thread.
+    pub tid: u32,
+    /// The signal number, if any.
+    pub signo: Option<Signo>,
+    /// The `si_code` of the signal, or `0` if there is no signal.
+    pub code: i32,
+}
+
+/// Fixed-size ring buffer of signal events.
+pub(crate) struct SignalJournal {
+    entries: [Option<JournalEntry>; JOURNAL_CAPACITY],
+    next_seq: u64,
+}
+
+impl Default for SignalJournal {
+    fn default() -> Self {
+        Self {
+            entries: [None; JOURNAL_CAPACITY],
+            next_seq: 0,
+        }
+    }
+}
+
+impl SignalJournal {
+    pub fn record(&mut self, event: JournalEvent, tid: u32, signo: Option<Signo>, code: i32) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.entries[seq as usize % JOURNAL_CAPACITY] = Some(JournalEntry {
+            seq,
+            timestamp: (TIME_SOURCE.lock())(),
+            event,
+            tid,
+            signo,
+            code,
+        });
+    }
+
+    /// Returns the recorded entries, oldest first.
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        let mut result: Vec<_> = self.entries.iter().flatten().copied().collect();
+        result.sort_unstable_by_key(|entry| entry.seq);
+        result
+    }
+}
@@ -1,17 +1,43 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
 #[macro_use]
 extern crate log;
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "alloc")]
 pub mod api;
 pub mod arch;
+#[cfg(feature = "alloc")]
+pub mod coredump;
+#[cfg(feature = "journal")]
+pub mod journal;
+#[cfg(feature = "lock-stats")]
+pub mod lockstat;
+pub mod strace;
+#[cfg(feature = "alloc")]
+pub mod sync;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "tracepoint")]
+pub mod tracepoint;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
 
 mod action;
 pub use action::*;
 
+mod error;
+pub use error::*;
+
 mod pending;
 pub use pending::*;
 
+pub mod registry;
+
+mod restart;
+pub use restart::*;
+
 mod types;
 pub use types::*;
@@ -0,0 +1,132 @@
+//! Contention metrics of the locks of the signal managers, for performance
+//! work.
+//!
+//! Each manager counts the acquisitions of its locks, how many of them found
+//! the lock held, and the longest time the lock was held. Read them with
+//! [`ProcessSignalManager::lock_stats`] and
+//! [`ThreadSignalManager::lock_stats`]. The blocked mask of a thread is
+//! atomic and takes no lock, so it has no metrics.
+//!
+//! Contention is only seen where the locks actually spin, that is with the
+//! `smp` feature of `kspin`.
+//!
+//! [`ProcessSignalManager::lock_stats`]: crate::api::ProcessSignalManager::lock_stats
+//! [`ThreadSignalManager::lock_stats`]: crate::api::ThreadSignalManager::lock_stats
+
+use core::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use kspin::SpinNoIrq;
+
+use crate::sync;
+
+static TIME_SOURCE: SpinNoIrq<fn() -> u64> = SpinNoIrq::new(|| 0);
+
+/// Sets the function used to time how long locks are held.
+///
+/// The unit is up to the OS. Hold durations are `0` until this is called.
+pub fn set_time_source(source: fn() -> u64) {
+    *TIME_SOURCE.lock() = source;
+}
+
+fn now() -> u64 {
+    (TIME_SOURCE.lock())()
+}
+
+/// Metrics of a lock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockStats {
+    /// Number of times the lock was acquired.
+    pub acquisitions: u64,
+    /// Number of acquisitions that had to wait for the lock.
+    pub contended: u64,
+    /// Longest time the lock was held, in the unit of the time source.
+    pub max_hold: u64,
+}
+
+/// Counters behind [`LockStats`], updated without locking.
+#[derive(Default)]
+pub(crate) struct LockCounters {
+    acquisitions: AtomicU64,
+    contended: AtomicU64,
+    max_hold: AtomicU64,
+}
+
+impl LockCounters {
+    pub fn stats(&self) -> LockStats {
+        LockStats {
+            acquisitions: self.acquisitions.load(Ordering::Relaxed),
+            contended: self.contended.load(Ordering::Relaxed),
+            max_hold: self.max_hold.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.acquisitions.store(0, Ordering::Relaxed);
+        self.contended.store(0, Ordering::Relaxed);
+        self.max_hold.store(0, Ordering::Relaxed);
+    }
+
+    /// Acquires `lock`, counting the acquisition against these counters.
+    pub fn lock<'a, T>(&'a self, lock: &'a sync::SpinNoIrq<T>) -> impl DerefMut<Target = T> + 'a {
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        let guard = lock.try_lock().unwrap_or_else(|| {
+            self.contended.fetch_add(1, Ordering::Relaxed);
+            lock.lock()
+        });
+        StatGuard {
+            guard,
+            counters: self,
+            start: now(),
+        }
+    }
+}
+
+/// Guard recording how long the lock was held when dropped.
+struct StatGuard<'a, G> {
+    guard: G,
+    counters: &'a LockCounters,
+    start: u64,
+}
+
+impl<G: Deref> Deref for StatGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for StatGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<G> Drop for StatGuard<'_, G> {
+    fn drop(&mut self) {
+        let held = now().saturating_sub(self.start);
+        self.counters.max_hold.fetch_max(held, Ordering::Relaxed);
+    }
+}
+
+/// A lock counting its acquisitions in its [`LockCounters`].
+pub(crate) struct StatLock<T> {
+    lock: sync::SpinNoIrq<T>,
+    pub(crate) counters: LockCounters,
+}
+
+impl<T> StatLock<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            lock: sync::SpinNoIrq::new(data),
+            counters: LockCounters::default(),
+        }
+    }
+
+    pub fn lock(&self) -> impl DerefMut<Target = T> + '_ {
+        self.counters.lock(&self.lock)
+    }
+}
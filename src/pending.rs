@@ -1,7 +1,158 @@
+#[cfg(feature = "alloc")]
 use alloc::{boxed::Box, collections::vec_deque::VecDeque};
-use core::array;
+use core::{
+    array,
+    borrow::{Borrow, BorrowMut},
+};
 
-use crate::{SignalInfo, SignalSet};
+#[cfg(feature = "alloc")]
+use linux_raw_sys::general::{SI_MESGQ, SI_QUEUE, SI_TKILL, SI_USER};
+
+#[cfg(feature = "watchdog")]
+use crate::NSIG;
+use crate::{NSIG_RT, SignalError, SignalInfo, SignalResult, SignalSet, Signo};
+
+/// Maximum number of queued instances of each real-time signal without the
+/// `alloc` feature.
+#[cfg(not(feature = "alloc"))]
+pub const RT_QUEUE_CAPACITY: usize = 8;
+
+/// Signal info of a standard signal, boxed with the `alloc` feature to keep
+/// [`PendingSignals`] small.
+#[cfg(feature = "alloc")]
+type StdInfo = Box<SignalInfo>;
+#[cfg(not(feature = "alloc"))]
+type StdInfo = SignalInfo;
+
+/// Queue of a real-time signal, growable with the `alloc` feature and of
+/// [`RT_QUEUE_CAPACITY`] otherwise.
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+struct RtQueue(VecDeque<SignalInfo>);
+
+#[cfg(feature = "alloc")]
+impl RtQueue {
+    fn push_back(&mut self, sig: SignalInfo) {
+        self.0.push_back(sig);
+    }
+
+    fn pop_front(&mut self) -> Option<SignalInfo> {
+        self.0.pop_front()
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &SignalInfo> {
+        self.0.iter()
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut SignalInfo> {
+        self.0.iter_mut()
+    }
+}
+
+#[cfg(not(feature = "alloc"))]
+#[derive(Default)]
+struct RtQueue {
+    buf: [Option<SignalInfo>; RT_QUEUE_CAPACITY],
+    head: usize,
+    len: usize,
+}
+
+#[cfg(not(feature = "alloc"))]
+impl RtQueue {
+    fn push_back(&mut self, sig: SignalInfo) {
+        // Callers check the capacity first; dropping the signal is better
+        // than overwriting the oldest one.
+        if self.len == RT_QUEUE_CAPACITY {
+            return;
+        }
+        self.buf[(self.head + self.len) % RT_QUEUE_CAPACITY] = Some(sig);
+        self.len += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<SignalInfo> {
+        if self.len == 0 {
+            return None;
+        }
+        let sig = self.buf[self.head].take();
+        self.head = (self.head + 1) % RT_QUEUE_CAPACITY;
+        self.len -= 1;
+        sig
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &SignalInfo> {
+        (0..self.len).filter_map(|i| self.buf[(self.head + i) % RT_QUEUE_CAPACITY].as_ref())
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut SignalInfo> {
+        let (head, len) = (self.head, self.len);
+        self.buf
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| (i + RT_QUEUE_CAPACITY - head) % RT_QUEUE_CAPACITY < len)
+            .filter_map(|(_, sig)| sig.as_mut())
+    }
+}
+
+/// Gets the user a signal is accounted to, if it was sent by one.
+#[cfg(feature = "alloc")]
+fn sender_uid(sig: &SignalInfo) -> Option<u32> {
+    [SI_USER as i32, SI_QUEUE, SI_TKILL, SI_MESGQ]
+        .contains(&sig.code())
+        .then(|| sig.uid())
+}
+
+/// Charges a signal about to be queued to its sender, with the
+/// [pending accounting](crate::api::PendingAccounting) if any.
+#[cfg(feature = "alloc")]
+fn charge(sig: &SignalInfo) -> bool {
+    let Some(uid) = sender_uid(sig) else {
+        return true;
+    };
+    crate::api::pending_accounting().is_none_or(|accounting| accounting.charge(uid))
+}
+
+/// Releases a signal leaving the queue from its sender.
+#[cfg(feature = "alloc")]
+fn release(sig: &SignalInfo) {
+    let Some(uid) = sender_uid(sig) else {
+        return;
+    };
+    if let Some(accounting) = crate::api::pending_accounting() {
+        accounting.release(uid);
+    }
+}
+
+#[cfg(not(feature = "alloc"))]
+fn charge(_sig: &SignalInfo) -> bool {
+    true
+}
+
+#[cfg(not(feature = "alloc"))]
+fn release(_sig: &SignalInfo) {}
 
 /// Structure to record pending signals.
 pub struct PendingSignals {
@@ -13,10 +164,27 @@ pub struct PendingSignals {
     /// signal that is delivered and not yet handled.
     pub set: SignalSet,
 
-    /// Signal info of standard signals (1-31).
-    info_std: [Option<Box<SignalInfo>>; 32],
+    /// Signal info of standard signals (1-31), indexed by signal number.
+    info_std: [Option<StdInfo>; Signo::SIGRTMIN as usize],
     /// Signal info queue for real-time signals.
-    info_rt: [VecDeque<SignalInfo>; 33],
+    info_rt: [RtQueue; NSIG_RT],
+    /// Maximum number of queued instances of each real-time signal.
+    rt_limit: usize,
+    /// Whether standard signals are taken in arrival order.
+    fifo: bool,
+    /// Arrival order of the pending standard signals, indexed by signal
+    /// number.
+    arrival: [u64; Signo::SIGRTMIN as usize],
+    /// Arrival order of the next standard signal.
+    next_arrival: u64,
+    /// Box of the last standard signal dequeued, reused by the next one so
+    /// that a frequent signal such as `SIGPROF` doesn't allocate each time.
+    #[cfg(feature = "alloc")]
+    spare: Option<StdInfo>,
+    /// When each pending signal became pending, indexed by signal number
+    /// minus one.
+    #[cfg(feature = "watchdog")]
+    queued_at: [Option<u64>; NSIG],
 }
 
 impl Default for PendingSignals {
@@ -24,7 +192,15 @@ impl Default for PendingSignals {
         Self {
             set: SignalSet::default(),
             info_std: Default::default(),
-            info_rt: array::from_fn(|_| VecDeque::new()),
+            info_rt: array::from_fn(|_| RtQueue::default()),
+            rt_limit: usize::MAX,
+            fifo: false,
+            arrival: [0; Signo::SIGRTMIN as usize],
+            next_arrival: 0,
+            #[cfg(feature = "alloc")]
+            spare: None,
+            #[cfg(feature = "watchdog")]
+            queued_at: [None; NSIG],
         }
     }
 }
@@ -33,36 +209,363 @@ impl PendingSignals {
     /// Puts a signal into the pending queue.
     ///
     /// Returns `true` if the signal was added, `false` if the signal is
-    /// standard and ignored (i.e. already pending).
+    /// standard and ignored (i.e. already pending), or real-time and its
+    /// queue is full (see [`Self::set_rt_limit`]).
+    ///
+    /// An expiry of a POSIX timer whose signal is still pending is not
+    /// queued, but counted in the overrun of the pending instance, and
+    /// `false` is returned.
     pub fn put_signal(&mut self, sig: SignalInfo) -> bool {
+        if self.coalesce_timer(&sig) {
+            return false;
+        }
+        let signo = sig.signo();
+        // Signals generated by the kernel are queued over the limit, as on
+        // Linux, but never over the capacity of a fixed-size queue.
+        let full = if cfg!(feature = "linux-strict") && sig.code() > 0 {
+            self.at_capacity(signo)
+        } else {
+            self.is_full(signo)
+        };
+        // At most one standard signal can be pending.
+        if full || (!signo.is_realtime() && self.set.has(signo)) || !charge(&sig) {
+            return false;
+        }
+        self.enqueue(sig);
+        true
+    }
+
+    /// Like [`Self::put_signal`], but fails with [`SignalError::WouldBlock`]
+    /// instead of dropping a real-time signal whose queue is full (see
+    /// [`Self::is_full`]).
+    pub fn try_put_signal(&mut self, sig: SignalInfo) -> SignalResult<bool> {
+        if self.is_full(sig.signo()) {
+            return Err(SignalError::WouldBlock);
+        }
+        Ok(self.put_signal(sig))
+    }
+
+    /// Puts a signal restored from a checkpoint into the pending queue,
+    /// over the limit of queued real-time signals (see
+    /// [`Self::set_rt_limit`]).
+    ///
+    /// Unlike [`Self::put_signal`], this fails instead of dropping the
+    /// signal. It fails with [`SignalError::InvalidArgument`] if the signal
+    /// is standard and already pending, and with [`SignalError::WouldBlock`]
+    /// if the fixed-size queue of a real-time signal is full. It also fails
+    /// with [`SignalError::WouldBlock`] if the
+    /// [pending accounting](crate::api::PendingAccounting) refuses the
+    /// sender: the signal is still charged, as it is released when dequeued.
+    pub fn restore_signal(&mut self, sig: SignalInfo) -> SignalResult {
         let signo = sig.signo();
-        let added = self.set.add(signo);
+        if !signo.is_realtime() && self.set.has(signo) {
+            return Err(SignalError::InvalidArgument);
+        }
+        if self.at_capacity(signo) || !charge(&sig) {
+            return Err(SignalError::WouldBlock);
+        }
+        self.enqueue(sig);
+        Ok(())
+    }
 
+    /// Adds a signal, checked to fit, to the queue.
+    fn enqueue(&mut self, sig: SignalInfo) {
+        let signo = sig.signo();
         if signo.is_realtime() {
-            self.info_rt[signo as usize - 32].push_back(sig);
+            self.info_rt[signo as usize - Signo::SIGRTMIN as usize].push_back(sig);
         } else {
-            if !added {
-                // At most one standard signal can be pending.
-                return false;
-            }
-            self.info_std[signo as usize] = Some(Box::new(sig));
+            self.set_std(sig);
+            self.arrival[signo as usize] = self.next_arrival;
+            self.next_arrival += 1;
+        }
+        self.set.add(signo);
+        #[cfg(feature = "watchdog")]
+        self.queued_at[signo as usize - 1].get_or_insert_with(crate::watchdog::now);
+    }
+
+    /// Counts an expiry of a POSIX timer in the overrun of the pending
+    /// signal of the timer, if any.
+    ///
+    /// Returns `true` if `sig` reports a timer expiry and a signal of the
+    /// same timer is pending.
+    pub fn coalesce_timer(&mut self, sig: &SignalInfo) -> bool {
+        if !sig.is_timer() {
+            return false;
         }
+        let Some(pending) = self.pending_timer(sig.signo(), sig.timer_id()) else {
+            return false;
+        };
+        // Linux caps the overrun at `DELAYTIMER_MAX`, i.e. `INT_MAX`.
+        let overrun = pending
+            .overrun()
+            .saturating_add(sig.overrun())
+            .saturating_add(1);
+        pending.set_overrun(overrun);
         true
     }
 
+    /// Discards the pending signals contained in `mask`, including all queued
+    /// instances of real-time signals.
+    ///
+    /// Returns `true` if any signal was discarded.
+    pub fn remove_signals(&mut self, mask: &SignalSet) -> bool {
+        let mut removed = false;
+        while let Some(signo) = self.set.dequeue(mask) {
+            #[cfg(feature = "watchdog")]
+            {
+                self.queued_at[signo as usize - 1] = None;
+            }
+            if signo.is_realtime() {
+                let queue = &mut self.info_rt[signo as usize - Signo::SIGRTMIN as usize];
+                queue.iter().for_each(release);
+                queue.clear();
+            } else if let Some(sig) = self.take_std(signo) {
+                release(&sig);
+            }
+            removed = true;
+        }
+        removed
+    }
+
+    /// Checks if a signal is pending.
+    pub fn is_pending(&self, signo: Signo) -> bool {
+        self.set.has(signo)
+    }
+
+    /// Gets the number of queued instances of a signal, at most one for a
+    /// standard signal.
+    pub fn count(&self, signo: Signo) -> usize {
+        if signo.is_realtime() {
+            self.info_rt[signo as usize - Signo::SIGRTMIN as usize].len()
+        } else {
+            self.info_std[signo as usize].is_some() as usize
+        }
+    }
+
+    /// Sets the maximum number of queued instances of each real-time signal,
+    /// beyond which further instances are dropped. `usize::MAX` means no
+    /// limit, which is the default.
+    ///
+    /// Instances already queued are kept. With the `linux-strict` feature,
+    /// signals generated by the kernel (`si_code > 0`) are queued over the
+    /// limit.
+    pub fn set_rt_limit(&mut self, limit: usize) {
+        self.rt_limit = limit;
+    }
+
+    /// Gets the maximum number of queued instances of each real-time signal.
+    pub fn rt_limit(&self) -> usize {
+        self.rt_limit
+    }
+
+    /// Sets whether standard signals are taken in the order they arrived
+    /// instead of lowest-numbered first, for deterministic ordering across
+    /// signals in tests and record/replay systems. Real-time signals are
+    /// still taken after the standard ones, lowest-numbered first.
+    pub fn set_fifo(&mut self, fifo: bool) {
+        self.fifo = fifo;
+    }
+
+    /// Checks if standard signals are taken in arrival order.
+    pub fn is_fifo(&self) -> bool {
+        self.fifo
+    }
+
+    /// Checks if no more instances of a signal can be queued. Always `false`
+    /// for a standard signal, a further instance of which is merged.
+    pub fn is_full(&self, signo: Signo) -> bool {
+        signo.is_realtime() && (self.at_capacity(signo) || self.count(signo) >= self.rt_limit)
+    }
+
+    /// Checks if the queue of a real-time signal can't hold another instance
+    /// whatever the limit, which only happens without `alloc`.
+    fn at_capacity(&self, signo: Signo) -> bool {
+        #[cfg(not(feature = "alloc"))]
+        return signo.is_realtime() && self.count(signo) == RT_QUEUE_CAPACITY;
+        #[cfg(feature = "alloc")]
+        {
+            let _ = signo;
+            false
+        }
+    }
+
+    /// Gets the total number of queued signal info structures.
+    pub fn len(&self) -> usize {
+        self.info_std.iter().filter(|info| info.is_some()).count()
+            + self.info_rt.iter().map(RtQueue::len).sum::<usize>()
+    }
+
+    /// Checks if no signal is pending.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Returns an iterator over the queued signal info, standard signals first
+    /// and then real-time signals in delivery order.
+    pub fn iter(&self) -> impl Iterator<Item = &SignalInfo> {
+        self.info_std
+            .iter()
+            .filter_map(|info| info.as_ref().map(Borrow::borrow))
+            .chain(self.info_rt.iter().flat_map(RtQueue::iter))
+    }
+
     /// Dequeues the next pending signal contained in `mask`, if any.
+    ///
+    /// Signals are taken lowest-numbered first, or standard signals in
+    /// arrival order (see [`Self::set_fifo`]). With the `linux-strict`
+    /// feature, pending synchronous signals (see [`SignalSet::synchronous`])
+    /// are taken before all others.
     pub fn dequeue_signal(&mut self, mask: &SignalSet) -> Option<SignalInfo> {
-        self.set.dequeue(mask).and_then(|signo| {
+        // Synchronous signals come first, as on Linux, so that a fault is
+        // handled before the signals that happened to be pending.
+        #[cfg(feature = "linux-strict")]
+        let mask = &{
+            let synchronous = *mask & SignalSet::synchronous();
+            if (self.set & synchronous).is_empty() {
+                *mask
+            } else {
+                synchronous
+            }
+        };
+        let oldest = self.fifo.then(|| self.oldest_std(mask)).flatten();
+        let signo = match oldest {
+            Some(signo) => {
+                self.set.remove(signo);
+                Some(signo)
+            }
+            None => self.set.dequeue(mask),
+        };
+        let sig = signo.and_then(|signo| {
+            #[cfg(feature = "watchdog")]
+            {
+                self.queued_at[signo as usize - 1] = None;
+            }
             if signo.is_realtime() {
-                let queue = &mut self.info_rt[signo as usize - 32];
+                let queue = &mut self.info_rt[signo as usize - Signo::SIGRTMIN as usize];
                 let result = queue.pop_front();
                 if !queue.is_empty() {
                     self.set.add(signo);
+                    // The age of the next instance is counted from now.
+                    #[cfg(feature = "watchdog")]
+                    {
+                        self.queued_at[signo as usize - 1] = Some(crate::watchdog::now());
+                    }
                 }
                 result
             } else {
-                self.info_std[signo as usize].take().map(|boxed| *boxed)
+                self.take_std(signo)
             }
+        });
+        if let Some(sig) = &sig {
+            release(sig);
+        }
+        sig
+    }
+
+    /// Gets the pending standard signal in `mask` that arrived first.
+    fn oldest_std(&self, mask: &SignalSet) -> Option<Signo> {
+        let mut set = self.set & *mask;
+        core::iter::from_fn(|| set.dequeue(&!SignalSet::default()))
+            .take_while(|signo| !signo.is_realtime())
+            .min_by_key(|&signo| self.arrival[signo as usize])
+    }
+
+    fn pending_timer(&mut self, signo: Signo, timer_id: i32) -> Option<&mut SignalInfo> {
+        if !self.set.has(signo) {
+            return None;
+        }
+        let pending = if signo.is_realtime() {
+            self.info_rt[signo as usize - Signo::SIGRTMIN as usize]
+                .iter_mut()
+                .find(|sig| sig.is_timer() && sig.timer_id() == timer_id)
+        } else {
+            self.info_std[signo as usize]
+                .as_mut()
+                .map(BorrowMut::borrow_mut)
+        };
+        pending.filter(|sig| sig.is_timer() && sig.timer_id() == timer_id)
+    }
+
+    /// Returns an iterator over the pending signals contained in `mask`
+    /// with how long they have been pending at `now`.
+    #[cfg(feature = "watchdog")]
+    pub(crate) fn ages(&self, mask: SignalSet, now: u64) -> impl Iterator<Item = (Signo, u64)> {
+        let mut set = self.set;
+        core::iter::from_fn(move || set.dequeue(&mask)).filter_map(move |signo| {
+            let queued_at = self.queued_at[signo as usize - 1]?;
+            Some((signo, now.saturating_sub(queued_at)))
         })
     }
+
+    #[cfg(feature = "alloc")]
+    fn set_std(&mut self, sig: SignalInfo) {
+        let signo = sig.signo();
+        let boxed = match self.spare.take() {
+            Some(mut boxed) => {
+                *boxed = sig;
+                boxed
+            }
+            None => Box::new(sig),
+        };
+        self.info_std[signo as usize] = Some(boxed);
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn set_std(&mut self, sig: SignalInfo) {
+        let signo = sig.signo();
+        self.info_std[signo as usize] = Some(sig);
+    }
+
+    #[cfg(feature = "alloc")]
+    fn take_std(&mut self, signo: Signo) -> Option<SignalInfo> {
+        let boxed = self.info_std[signo as usize].take()?;
+        let sig = SignalInfo::clone(&boxed);
+        self.spare = Some(boxed);
+        Some(sig)
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn take_std(&mut self, signo: Signo) -> Option<SignalInfo> {
+        self.info_std[signo as usize].take()
+    }
+}
+
+impl Drop for PendingSignals {
+    fn drop(&mut self) {
+        self.iter().for_each(release);
+    }
+}
+
+#[cfg(all(test, not(feature = "alloc")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rt_queue_wraps() {
+        let sig = |pid| SignalInfo::new_user(Signo::SIGRTMIN, 0, pid);
+        let mut queue = RtQueue::default();
+        // Move the head off zero so that the queue wraps around.
+        for pid in 0..3 {
+            queue.push_back(sig(pid));
+            assert_eq!(queue.pop_front().unwrap().pid(), pid);
+        }
+        // The signals over the capacity are dropped.
+        for pid in 0..RT_QUEUE_CAPACITY as u32 + 2 {
+            queue.push_back(sig(pid));
+        }
+        assert_eq!(queue.len(), RT_QUEUE_CAPACITY);
+        assert!(
+            queue
+                .iter()
+                .map(SignalInfo::pid)
+                .eq(0..RT_QUEUE_CAPACITY as u32)
+        );
+        queue.iter_mut().for_each(|sig| sig.set_uid(1000));
+        for pid in 0..RT_QUEUE_CAPACITY as u32 {
+            let sig = queue.pop_front().unwrap();
+            assert_eq!((sig.pid(), sig.uid()), (pid, 1000));
+        }
+        assert!(queue.is_empty());
+        assert!(queue.pop_front().is_none());
+    }
 }
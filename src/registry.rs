@@ -0,0 +1,158 @@
+//! Fixed-capacity registry of the threads of a process, which needs no
+//! allocator.
+//!
+//! The managers of [`api`](crate::api) are built around `Arc` and need the
+//! `alloc` feature. Without it, a [`ThreadRegistry`] keeps the signal state
+//! of up to `N` threads, the blocked mask and pending queue of each, and the
+//! pending queue shared by the process, in place. Threads are addressed by
+//! their slot index, stable until they are removed. The caller is
+//! responsible for locking.
+
+use core::array;
+
+use crate::{PendingSignals, SignalError, SignalInfo, SignalResult, SignalSet};
+
+/// Signal state of a thread in a [`ThreadRegistry`].
+pub struct ThreadEntry {
+    tid: u32,
+    /// The blocked signals.
+    pub blocked: SignalSet,
+    /// The thread-directed pending signals.
+    pub pending: PendingSignals,
+}
+
+impl ThreadEntry {
+    /// Gets the thread ID.
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+}
+
+/// Fixed-capacity registry of the threads of a process, addressed by slot
+/// index.
+pub struct ThreadRegistry<const N: usize> {
+    slots: [Option<ThreadEntry>; N],
+    /// The process-directed pending signals.
+    pub shared: PendingSignals,
+    /// Slot to consider first for the next process-directed signal.
+    next_target: usize,
+}
+
+impl<const N: usize> Default for ThreadRegistry<N> {
+    fn default() -> Self {
+        Self {
+            slots: array::from_fn(|_| None),
+            shared: PendingSignals::default(),
+            next_target: 0,
+        }
+    }
+}
+
+impl<const N: usize> ThreadRegistry<N> {
+    /// Adds a thread, with no signal blocked or pending, returning its slot
+    /// index.
+    ///
+    /// Fails with [`SignalError::InvalidArgument`] if the thread is already
+    /// registered, and with [`SignalError::WouldBlock`] if all `N` slots are
+    /// taken.
+    pub fn insert(&mut self, tid: u32) -> SignalResult<usize> {
+        if self.index_of(tid).is_some() {
+            return Err(SignalError::InvalidArgument);
+        }
+        let index = self
+            .slots
+            .iter()
+            .position(Option::is_none)
+            .ok_or(SignalError::WouldBlock)?;
+        self.slots[index] = Some(ThreadEntry {
+            tid,
+            blocked: SignalSet::default(),
+            pending: PendingSignals::default(),
+        });
+        Ok(index)
+    }
+
+    /// Removes the thread in a slot, returning its state. The slot may be
+    /// reused by the next [`insert`](Self::insert).
+    pub fn remove(&mut self, index: usize) -> Option<ThreadEntry> {
+        self.slots.get_mut(index)?.take()
+    }
+
+    /// Gets the slot index of a thread.
+    pub fn index_of(&self, tid: u32) -> Option<usize> {
+        self.iter()
+            .find(|(_, entry)| entry.tid == tid)
+            .map(|(index, _)| index)
+    }
+
+    /// Gets the thread in a slot.
+    pub fn get(&self, index: usize) -> Option<&ThreadEntry> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    /// Gets the thread in a slot mutably.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut ThreadEntry> {
+        self.slots.get_mut(index)?.as_mut()
+    }
+
+    /// Returns an iterator over the registered threads with their slot
+    /// indices.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &ThreadEntry)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((index, entry.as_ref()?)))
+    }
+
+    /// Gets the number of registered threads.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Checks if no thread is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends a signal to the thread in a slot.
+    ///
+    /// Returns `true` if the thread should be woken up: the signal was
+    /// queued and is not blocked. Ignored signals should be filtered out by
+    /// the caller, which owns the actions.
+    pub fn send_to_thread(&mut self, index: usize, sig: SignalInfo) -> bool {
+        let Some(entry) = self.get_mut(index) else {
+            return false;
+        };
+        let signo = sig.signo();
+        entry.pending.put_signal(sig) && !entry.blocked.has(signo)
+    }
+
+    /// Sends a signal to the process, queuing it on the shared queue.
+    ///
+    /// Returns the slot of the thread to wake up: one not blocking the
+    /// signal, picked in turn among those, or `None` if every thread blocks
+    /// it or the signal was not queued.
+    pub fn send_to_process(&mut self, sig: SignalInfo) -> Option<usize> {
+        let signo = sig.signo();
+        if !self.shared.put_signal(sig) {
+            return None;
+        }
+        let start = self.next_target;
+        let index = (0..N).map(|i| (start + i) % N).find(|&index| {
+            self.get(index)
+                .is_some_and(|entry| !entry.blocked.has(signo))
+        })?;
+        self.next_target = index + 1;
+        Some(index)
+    }
+
+    /// Dequeues a signal of `mask` for the thread in a slot, from its own
+    /// queue first and then from the shared one.
+    pub fn dequeue_signal(&mut self, index: usize, mask: &SignalSet) -> Option<SignalInfo> {
+        let entry = self.get_mut(index)?;
+        entry
+            .pending
+            .dequeue_signal(mask)
+            .or_else(|| self.shared.dequeue_signal(mask))
+    }
+}
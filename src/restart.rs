@@ -0,0 +1,161 @@
+use core::time::Duration;
+
+#[cfg(feature = "alloc")]
+use axcpu::uspace::UserContext;
+
+#[cfg(feature = "alloc")]
+use crate::arch::{SYSCALL_INSN_LEN, SYSNO_RESTART_SYSCALL};
+
+/// `EINTR`, returned by an interrupted syscall that is not restarted.
+pub const EINTR: i32 = 4;
+/// Kernel-internal errno of [`RestartKind::Sys`].
+pub const ERESTARTSYS: i32 = 512;
+/// Kernel-internal errno of [`RestartKind::NoIntr`].
+pub const ERESTARTNOINTR: i32 = 513;
+/// Kernel-internal errno of [`RestartKind::NoHand`].
+pub const ERESTARTNOHAND: i32 = 514;
+/// Kernel-internal errno of [`RestartKind::RestartBlock`].
+pub const ERESTART_RESTARTBLOCK: i32 = 516;
+
+/// How a syscall interrupted by a signal should be restarted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartKind {
+    /// Restart unless a handler without `SA_RESTART` runs (`ERESTARTSYS`).
+    Sys,
+    /// Always restart (`ERESTARTNOINTR`).
+    NoIntr,
+    /// Restart unless a handler runs (`ERESTARTNOHAND`).
+    NoHand,
+    /// Restart through `restart_syscall` unless a handler runs
+    /// (`ERESTART_RESTARTBLOCK`).
+    RestartBlock,
+}
+
+impl RestartKind {
+    /// Gets the kind from a kernel-internal errno, if it's one of the
+    /// `ERESTART*` family.
+    pub fn from_errno(errno: i32) -> Option<Self> {
+        match errno {
+            ERESTARTSYS => Some(Self::Sys),
+            ERESTARTNOINTR => Some(Self::NoIntr),
+            ERESTARTNOHAND => Some(Self::NoHand),
+            ERESTART_RESTARTBLOCK => Some(Self::RestartBlock),
+            _ => None,
+        }
+    }
+
+    /// Checks if the syscall should be restarted. `handler_restart` is
+    /// `Some` with whether the action has `SA_RESTART` if a handler runs.
+    pub fn should_restart(&self, handler_restart: Option<bool>) -> bool {
+        match (self, handler_restart) {
+            (_, None) | (Self::NoIntr, _) => true,
+            (Self::Sys, Some(restart)) => restart,
+            (Self::NoHand | Self::RestartBlock, Some(_)) => false,
+        }
+    }
+
+    /// Gets the kernel-internal errno of the kind.
+    pub fn errno(&self) -> i32 {
+        match self {
+            Self::Sys => ERESTARTSYS,
+            Self::NoIntr => ERESTARTNOINTR,
+            Self::NoHand => ERESTARTNOHAND,
+            Self::RestartBlock => ERESTART_RESTARTBLOCK,
+        }
+    }
+}
+
+/// Whether a syscall can be restarted after a signal interrupts it.
+///
+/// This is a property of the syscall, passed in by the syscall layer. The
+/// `SA_RESTART` flag only matters for [`RestartPolicy::Restartable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Restarted unless a handler without `SA_RESTART` runs, e.g. `read` or
+    /// `wait4`.
+    Restartable,
+    /// Never restarted once a handler runs, e.g. `poll`, `select` or
+    /// `nanosleep`.
+    NotAfterHandler,
+    /// Always restarted, even after a handler.
+    Always,
+}
+
+impl From<RestartPolicy> for RestartKind {
+    fn from(policy: RestartPolicy) -> Self {
+        match policy {
+            RestartPolicy::Restartable => Self::Sys,
+            RestartPolicy::NotAfterHandler => Self::NoHand,
+            RestartPolicy::Always => Self::NoIntr,
+        }
+    }
+}
+
+/// How an interruptible sleep, such as `nanosleep` or a futex wait, ends
+/// when a signal interrupts it.
+///
+/// See [`ProcessSignalManager::sleep_restart`].
+///
+/// [`ProcessSignalManager::sleep_restart`]: crate::api::ProcessSignalManager::sleep_restart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepRestart {
+    /// Fail with `EINTR`.
+    Interrupted,
+    /// Restart the syscall as is, with its original timeout, which is absolute
+    /// if any.
+    Restart,
+    /// Restart through `restart_syscall`, sleeping for the time left. The
+    /// [restart block] should resume the sleep with it.
+    ///
+    /// [restart block]: crate::api::ThreadSignalManager::set_restart_block
+    RestartBlock(Duration),
+}
+
+impl SleepRestart {
+    /// Gets the kernel-internal errno for the sleep to fail with, to be
+    /// passed on to [`SyscallRestart`].
+    pub fn errno(&self, policy: RestartPolicy) -> i32 {
+        match self {
+            Self::Interrupted => EINTR,
+            Self::Restart => RestartKind::from(policy).errno(),
+            Self::RestartBlock(_) => ERESTART_RESTARTBLOCK,
+        }
+    }
+}
+
+/// A syscall interrupted by a signal, waiting for the restart decision.
+///
+/// See [`ThreadSignalManager::set_syscall_restart`].
+///
+/// [`ThreadSignalManager::set_syscall_restart`]: crate::api::ThreadSignalManager::set_syscall_restart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallRestart {
+    /// How to restart the syscall.
+    pub kind: RestartKind,
+    /// The syscall number.
+    pub sysno: usize,
+    /// The first argument, which the return value may have overwritten.
+    pub arg0: usize,
+}
+
+impl SyscallRestart {
+    /// Updates the user context according to the restart decision: either
+    /// rewinds to the syscall instruction, or makes the syscall fail with
+    /// `EINTR`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn apply(&self, uctx: &mut UserContext, handler_restart: Option<bool>) {
+        if !self.kind.should_restart(handler_restart) {
+            uctx.set_retval(-EINTR as usize);
+            return;
+        }
+        let sysno = if self.kind == RestartKind::RestartBlock {
+            SYSNO_RESTART_SYSCALL
+        } else {
+            self.sysno
+        };
+        uctx.set_sysno(sysno);
+        uctx.set_arg0(self.arg0);
+        let ip = uctx.ip() - SYSCALL_INSN_LEN;
+        uctx.set_ip(ip);
+    }
+}
@@ -0,0 +1,249 @@
+//! Formatting of signal structures in `strace` syntax.
+//!
+//! ```text
+//! --- SIGSEGV {si_signo=SIGSEGV, si_code=SEGV_MAPERR, si_addr=0x1000} ---
+//! {sa_handler=0x401136, sa_mask=[INT TERM], sa_flags=SA_RESTART|SA_RESTORER, sa_restorer=0x401000}
+//! ```
+
+use core::fmt;
+
+use linux_raw_sys::general::{
+    SI_ASYNCIO, SI_DETHREAD, SI_KERNEL, SI_MESGQ, SI_QUEUE, SI_SIGIO, SI_TIMER, SI_TKILL, SI_USER,
+};
+use strum::IntoEnumIterator;
+
+use crate::{SignalAction, SignalDisposition, SignalInfo, SignalSet, Signo};
+
+/// Formats a [`SignalInfo`] like
+/// `{si_signo=SIGTERM, si_code=SI_USER, si_pid=1, si_uid=0}`.
+pub fn format_siginfo(info: &SignalInfo) -> impl fmt::Display + '_ {
+    SigInfoFmt(info)
+}
+
+/// Formats a [`SignalAction`] like
+/// `{sa_handler=SIG_IGN, sa_mask=[], sa_flags=0}`.
+pub fn format_action(action: &SignalAction) -> impl fmt::Display + '_ {
+    ActionFmt(action)
+}
+
+/// Formats the delivery of a signal like
+/// `--- SIGTERM {si_signo=SIGTERM, si_code=SI_KERNEL} ---`.
+pub fn format_delivery(info: &SignalInfo) -> impl fmt::Display + '_ {
+    DeliveryFmt(info)
+}
+
+/// Gets the symbolic name of the `si_code` of a signal, if known.
+pub fn code_name(signo: Signo, code: i32) -> Option<&'static str> {
+    const GENERIC: &[(i32, &str)] = &[
+        (SI_USER as i32, "SI_USER"),
+        (SI_KERNEL as i32, "SI_KERNEL"),
+        (SI_QUEUE, "SI_QUEUE"),
+        (SI_TIMER, "SI_TIMER"),
+        (SI_MESGQ, "SI_MESGQ"),
+        (SI_ASYNCIO, "SI_ASYNCIO"),
+        (SI_SIGIO, "SI_SIGIO"),
+        (SI_TKILL, "SI_TKILL"),
+        (SI_DETHREAD, "SI_DETHREAD"),
+    ];
+    if let Some((_, name)) = GENERIC.iter().find(|(c, _)| *c == code) {
+        return Some(name);
+    }
+
+    let names: &[&str] = match signo {
+        Signo::SIGILL => &[
+            "ILL_ILLOPC",
+            "ILL_ILLOPN",
+            "ILL_ILLADR",
+            "ILL_ILLTRP",
+            "ILL_PRVOPC",
+            "ILL_PRVREG",
+            "ILL_COPROC",
+            "ILL_BADSTK",
+            "ILL_BADIADDR",
+        ],
+        Signo::SIGFPE => &[
+            "FPE_INTDIV",
+            "FPE_INTOVF",
+            "FPE_FLTDIV",
+            "FPE_FLTOVF",
+            "FPE_FLTUND",
+            "FPE_FLTRES",
+            "FPE_FLTINV",
+            "FPE_FLTSUB",
+        ],
+        Signo::SIGSEGV => &[
+            "SEGV_MAPERR",
+            "SEGV_ACCERR",
+            "SEGV_BNDERR",
+            "SEGV_PKUERR",
+            "SEGV_ACCADI",
+            "SEGV_ADIDERR",
+            "SEGV_ADIPERR",
+            "SEGV_MTEAERR",
+            "SEGV_MTESERR",
+            "SEGV_CPERR",
+        ],
+        Signo::SIGBUS => &[
+            "BUS_ADRALN",
+            "BUS_ADRERR",
+            "BUS_OBJERR",
+            "BUS_MCEERR_AR",
+            "BUS_MCEERR_AO",
+        ],
+        Signo::SIGTRAP => &[
+            "TRAP_BRKPT",
+            "TRAP_TRACE",
+            "TRAP_BRANCH",
+            "TRAP_HWBKPT",
+            "TRAP_UNK",
+            "TRAP_PERF",
+        ],
+        Signo::SIGCHLD => &[
+            "CLD_EXITED",
+            "CLD_KILLED",
+            "CLD_DUMPED",
+            "CLD_TRAPPED",
+            "CLD_STOPPED",
+            "CLD_CONTINUED",
+        ],
+        Signo::SIGIO => &[
+            "POLL_IN", "POLL_OUT", "POLL_MSG", "POLL_ERR", "POLL_PRI", "POLL_HUP",
+        ],
+        _ => &[],
+    };
+    usize::try_from(code)
+        .ok()
+        .and_then(|code| code.checked_sub(1))
+        .and_then(|index| names.get(index).copied())
+}
+
+struct SigInfoFmt<'a>(&'a SignalInfo);
+
+impl fmt::Display for SigInfoFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = self.0;
+        let signo = info.signo();
+        let code = info.code();
+
+        write!(f, "{{si_signo={signo:?}, si_code=")?;
+        match code_name(signo, code) {
+            Some(name) => f.write_str(name)?,
+            None => write!(f, "{code}")?,
+        }
+        if info.errno() != 0 {
+            write!(f, ", si_errno={}", info.errno())?;
+        }
+
+        let value = |f: &mut fmt::Formatter<'_>| {
+            write!(
+                f,
+                ", si_value={{int={}, ptr={:#x}}}",
+                info.value_int(),
+                info.value_ptr()
+            )
+        };
+        match code {
+            SI_TIMER => {
+                write!(
+                    f,
+                    ", si_timerid={:#x}, si_overrun={}",
+                    info.timer_id(),
+                    info.overrun()
+                )?;
+                value(f)?;
+            }
+            SI_QUEUE | SI_MESGQ => {
+                write!(f, ", si_pid={}, si_uid={}", info.pid(), info.uid())?;
+                value(f)?;
+            }
+            _ if code <= 0 => {
+                write!(f, ", si_pid={}, si_uid={}", info.pid(), info.uid())?;
+            }
+            _ if code == SI_KERNEL as i32 => {}
+            _ => match signo {
+                Signo::SIGILL | Signo::SIGFPE | Signo::SIGSEGV | Signo::SIGBUS | Signo::SIGTRAP => {
+                    write!(f, ", si_addr={:#x}", info.addr())?;
+                }
+                Signo::SIGCHLD => {
+                    write!(
+                        f,
+                        ", si_pid={}, si_uid={}, si_status={}",
+                        info.pid(),
+                        info.uid(),
+                        info.status()
+                    )?;
+                }
+                Signo::SIGIO => {
+                    write!(f, ", si_band={}, si_fd={}", info.band(), info.fd())?;
+                }
+                _ => {}
+            },
+        }
+        f.write_str("}")
+    }
+}
+
+struct DeliveryFmt<'a>(&'a SignalInfo);
+
+impl fmt::Display for DeliveryFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "--- {:?} {} ---", self.0.signo(), SigInfoFmt(self.0))
+    }
+}
+
+struct ActionFmt<'a>(&'a SignalAction);
+
+impl fmt::Display for ActionFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = self.0;
+        f.write_str("{sa_handler=")?;
+        match action.disposition {
+            SignalDisposition::Default => f.write_str("SIG_DFL")?,
+            SignalDisposition::Ignore => f.write_str("SIG_IGN")?,
+            SignalDisposition::Handler(_) | SignalDisposition::SigactionHandler(_) => {
+                write!(f, "{:#x}", action.disposition.handler().unwrap())?
+            }
+        }
+        write!(f, ", sa_mask={}", SetFmt(&action.mask))?;
+
+        f.write_str(", sa_flags=")?;
+        if action.flags.is_empty() {
+            f.write_str("0")?;
+        }
+        for (i, (name, _)) in action.flags.iter_names().enumerate() {
+            if i > 0 {
+                f.write_str("|")?;
+            }
+            write!(f, "SA_{name}")?;
+        }
+
+        if let Some(restorer) = action.restorer {
+            write!(f, ", sa_restorer={:#x}", restorer as usize)?;
+        }
+        f.write_str("}")
+    }
+}
+
+struct SetFmt<'a>(&'a SignalSet);
+
+impl fmt::Display for SetFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        let mut first = true;
+        for signo in Signo::iter().filter(|signo| self.0.has(*signo)) {
+            if !first {
+                f.write_str(" ")?;
+            }
+            first = false;
+            if signo == Signo::SIGRTMIN {
+                f.write_str("RTMIN")?;
+            } else if signo.is_realtime() {
+                write!(f, "RT_{}", signo as u8 - Signo::SIGRTMIN as u8)?;
+            } else {
+                let name: &'static str = signo.into();
+                f.write_str(&name[3..])?;
+            }
+        }
+        f.write_str("]")
+    }
+}
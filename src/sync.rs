@@ -0,0 +1,59 @@
+//! Synchronization primitives used by the signal managers.
+//!
+//! When built with `--cfg loom`, they are replaced with [loom]'s so that the
+//! concurrency core can be model-checked. See `tests/loom.rs`.
+//!
+//! # Lock order
+//!
+//! Where the locks of the signal managers nest, they are taken in this
+//! order, a lock only being acquired while holding earlier ones:
+//!
+//! 1. the thread list of a process;
+//! 2. the pending queue of a thread;
+//! 3. the pending queue of a process;
+//! 4. the action table of a process, and then the table itself.
+//!
+//! All other locks, such as the timer list, the stop state, the signal
+//! stack and the registered hooks, are leaves: no other lock is taken while
+//! holding one, and hooks are called with none held. The exception is the
+//! `PendingAccounting` callbacks, which run with a pending queue locked;
+//! their lock is only taken when an accounting is set. The blocked mask of
+//! a thread is atomic and takes no lock. The order is not checked at
+//! runtime.
+//!
+//! [loom]: https://docs.rs/loom
+
+#[cfg(not(loom))]
+pub(crate) use core::sync::atomic;
+
+#[cfg(not(loom))]
+pub use kspin::SpinNoIrq;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic;
+
+/// A lock with the interface of [`kspin::SpinNoIrq`], backed by a loom mutex.
+#[cfg(loom)]
+pub struct SpinNoIrq<T>(loom::sync::Mutex<T>);
+
+#[cfg(loom)]
+impl<T> SpinNoIrq<T> {
+    pub fn new(data: T) -> Self {
+        Self(loom::sync::Mutex::new(data))
+    }
+
+    pub fn lock(&self) -> loom::sync::MutexGuard<'_, T> {
+        self.0.lock().unwrap()
+    }
+
+    pub fn try_lock(&self) -> Option<loom::sync::MutexGuard<'_, T>> {
+        self.0.try_lock().ok()
+    }
+}
+
+#[cfg(feature = "lock-stats")]
+pub(crate) use crate::lockstat::StatLock;
+
+/// A lock of the signal managers with contention metrics, under the
+/// `lock-stats` feature (see [`lockstat`](crate::lockstat)).
+#[cfg(not(feature = "lock-stats"))]
+pub(crate) type StatLock<T> = SpinNoIrq<T>;
@@ -0,0 +1,194 @@
+//! Harness for testing signal paths on the host.
+//!
+//! Enabled by the `test-util` feature. It provides:
+//!
+//! - a memory pool standing in for user memory, with the [`VmIo`]
+//!   implementation backed by it. Since there can only be one [`VmIo`]
+//!   implementation, a kernel enabling this feature must not provide its own
+//!   in the same binary;
+//! - [`TestEnv`], a process with one thread, built with [`TestEnvBuilder`];
+//! - a manually advanced [`clock`].
+//!
+//! [`VmIo`]: starry_vm::VmIo
+
+extern crate std;
+
+use alloc::{boxed::Box, sync::Arc, vec};
+use core::mem::MaybeUninit;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+use axcpu::uspace::UserContext;
+use extern_trait::extern_trait;
+use starry_vm::{VmError, VmIo, VmResult};
+
+use crate::{
+    SignalAction, Signo,
+    api::{ProcessSignalManager, SignalActions, ThreadSignalManager},
+    sync::SpinNoIrq,
+};
+
+/// Size of the memory pool.
+pub const POOL_SIZE: usize = 0x0100_0000; // 16 MiB
+
+static POOL: LazyLock<Mutex<Box<[u8]>>> =
+    LazyLock::new(|| Mutex::new(vec![0; POOL_SIZE].into_boxed_slice()));
+
+fn pool() -> MutexGuard<'static, Box<[u8]>> {
+    POOL.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Returns the top of the memory pool, to be used as the user stack pointer.
+pub fn initial_sp() -> usize {
+    let pool = pool();
+    pool.as_ptr() as usize + pool.len()
+}
+
+/// Zeroes the memory pool, so that a test doesn't see frames written by
+/// another.
+pub fn reset_pool() {
+    pool().fill(0);
+}
+
+struct Vm(MutexGuard<'static, Box<[u8]>>);
+
+impl Vm {
+    fn range(&self, start: usize, len: usize) -> Result<core::ops::Range<usize>, VmError> {
+        let base = self.0.as_ptr() as usize;
+        let offset = start.checked_sub(base).ok_or(VmError::BadAddress)?;
+        let end = offset.checked_add(len).ok_or(VmError::BadAddress)?;
+        if end > self.0.len() {
+            return Err(VmError::BadAddress);
+        }
+        Ok(offset..end)
+    }
+}
+
+#[extern_trait]
+unsafe impl VmIo for Vm {
+    fn new() -> Self {
+        Vm(pool())
+    }
+
+    fn read(&mut self, start: usize, buf: &mut [MaybeUninit<u8>]) -> VmResult {
+        let range = self.range(start, buf.len())?;
+        buf.write_copy_of_slice(&self.0[range]);
+        Ok(())
+    }
+
+    fn write(&mut self, start: usize, buf: &[u8]) -> VmResult {
+        let range = self.range(start, buf.len())?;
+        self.0[range].copy_from_slice(buf);
+        Ok(())
+    }
+}
+
+/// A process with one thread.
+pub struct TestEnv {
+    /// The process-level signal manager.
+    pub proc: Arc<ProcessSignalManager>,
+    /// The thread-level signal manager of the only thread.
+    pub thread: Arc<ThreadSignalManager>,
+}
+
+impl TestEnv {
+    /// Creates an environment with the default settings of
+    /// [`TestEnvBuilder`].
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Creates a builder.
+    pub fn builder() -> TestEnvBuilder {
+        TestEnvBuilder::default()
+    }
+
+    /// Creates a user context at address `0` with the stack at
+    /// [`initial_sp`].
+    pub fn user_context(&self) -> UserContext {
+        UserContext::new(0, initial_sp().into(), 0)
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builder of [`TestEnv`].
+pub struct TestEnvBuilder {
+    tid: u32,
+    default_restorer: usize,
+    actions: SignalActions,
+}
+
+impl Default for TestEnvBuilder {
+    fn default() -> Self {
+        Self {
+            tid: 1,
+            default_restorer: 0,
+            actions: SignalActions::default(),
+        }
+    }
+}
+
+impl TestEnvBuilder {
+    /// Sets the thread ID. Defaults to `1`.
+    pub fn tid(mut self, tid: u32) -> Self {
+        self.tid = tid;
+        self
+    }
+
+    /// Sets the default restorer. Defaults to `0`.
+    pub fn default_restorer(mut self, restorer: usize) -> Self {
+        self.default_restorer = restorer;
+        self
+    }
+
+    /// Sets the action of a signal.
+    pub fn action(mut self, signo: Signo, action: SignalAction) -> Self {
+        self.actions[signo] = action;
+        self
+    }
+
+    /// Builds the environment.
+    pub fn build(self) -> TestEnv {
+        let proc = Arc::new(ProcessSignalManager::new(
+            Arc::new(SpinNoIrq::new(self.actions)),
+            self.default_restorer,
+        ));
+        let thread = ThreadSignalManager::new(self.tid, proc.clone());
+        TestEnv { proc, thread }
+    }
+}
+
+/// A manually advanced clock.
+///
+/// With the `journal` feature, [`install`](clock::install) makes it the time
+/// source of journal entries.
+pub mod clock {
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    static NOW: AtomicU64 = AtomicU64::new(0);
+
+    /// Gets the current time.
+    pub fn now() -> u64 {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    /// Sets the current time.
+    pub fn set(now: u64) {
+        NOW.store(now, Ordering::Relaxed);
+    }
+
+    /// Advances the current time by `delta`, returning the new time.
+    pub fn advance(delta: u64) -> u64 {
+        NOW.fetch_add(delta, Ordering::Relaxed) + delta
+    }
+
+    /// Makes the clock the time source of journal entries.
+    #[cfg(feature = "journal")]
+    pub fn install() {
+        crate::journal::set_time_source(now);
+    }
+}
@@ -0,0 +1,99 @@
+//! Tracepoints mirroring the `signal:*` and `sigreturn` tracepoints of Linux.
+//!
+//! The OS registers a [`SignalTracepoints`] implementation with [`register`]
+//! to receive structured events, e.g. to feed them into its tracing buffer.
+
+use kspin::SpinNoIrq;
+
+use crate::{SignalActionFlags, Signo};
+
+/// Outcome of a signal generation, like `TRACE_SIGNAL_*` in Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerateResult {
+    /// The signal is queued.
+    Delivered,
+    /// The signal is discarded because it is ignored.
+    Ignored,
+    /// The signal is standard and already pending, so it is merged.
+    AlreadyPending,
+}
+
+/// Event of the `signal_generate` tracepoint.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalGenerate {
+    /// The signal number.
+    pub signo: Signo,
+    /// The `si_errno` of the signal.
+    pub errno: i32,
+    /// The `si_code` of the signal.
+    pub code: i32,
+    /// The thread the signal is directed or routed to, if any.
+    pub tid: Option<u32>,
+    /// Whether the signal is process-directed.
+    pub group: bool,
+    /// The outcome of the generation.
+    pub result: GenerateResult,
+}
+
+/// Event of the `signal_deliver` tracepoint.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalDeliver {
+    /// The receiving thread.
+    pub tid: u32,
+    /// The signal number.
+    pub signo: Signo,
+    /// The `si_errno` of the signal.
+    pub errno: i32,
+    /// The `si_code` of the signal.
+    pub code: i32,
+    /// The handler address, `0` for `SIG_DFL` and `1` for `SIG_IGN`.
+    pub handler: usize,
+    /// The action flags.
+    pub flags: SignalActionFlags,
+}
+
+/// Event of the `sigreturn` tracepoint.
+#[derive(Debug, Clone, Copy)]
+pub struct Sigreturn {
+    /// The returning thread.
+    pub tid: u32,
+    /// The stack pointer the frame is restored from.
+    pub sp: usize,
+}
+
+/// Receiver of signal tracepoint events.
+pub trait SignalTracepoints: Sync {
+    /// Called when a signal is generated.
+    fn signal_generate(&self, event: &SignalGenerate) {
+        let _ = event;
+    }
+
+    /// Called when a signal is delivered.
+    fn signal_deliver(&self, event: &SignalDeliver) {
+        let _ = event;
+    }
+
+    /// Called when a thread returns from a signal handler.
+    fn sigreturn(&self, event: &Sigreturn) {
+        let _ = event;
+    }
+}
+
+static TRACEPOINTS: SpinNoIrq<Option<&'static dyn SignalTracepoints>> = SpinNoIrq::new(None);
+
+/// Registers the receiver of tracepoint events, replacing the previous one.
+pub fn register(tracepoints: &'static dyn SignalTracepoints) {
+    *TRACEPOINTS.lock() = Some(tracepoints);
+}
+
+/// Unregisters the receiver of tracepoint events.
+pub fn unregister() {
+    *TRACEPOINTS.lock() = None;
+}
+
+pub(crate) fn emit(f: impl FnOnce(&dyn SignalTracepoints)) {
+    let tracepoints = *TRACEPOINTS.lock();
+    if let Some(tracepoints) = tracepoints {
+        f(tracepoints);
+    }
+}
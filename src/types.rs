@@ -1,14 +1,18 @@
 use core::{fmt, mem};
 
 use derive_more::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
-use linux_raw_sys::general::{SI_KERNEL, SS_DISABLE, kernel_sigset_t, siginfo_t};
-use strum::{EnumIter, FromRepr, IntoEnumIterator};
+use linux_raw_sys::general::{
+    __sifields, CLD_CONTINUED, CLD_DUMPED, CLD_EXITED, CLD_KILLED, CLD_STOPPED, CLD_TRAPPED,
+    MINSIGSTKSZ, NSIGBUS, NSIGFPE, NSIGILL, NSIGSEGV, NSIGTRAP, SI_KERNEL, SI_TIMER, SI_TKILL,
+    SS_AUTODISARM, SS_DISABLE, SS_FLAG_BITS, SS_ONSTACK, kernel_sigset_t, siginfo_t, stack_t,
+};
+use strum::{EnumIter, FromRepr, IntoEnumIterator, IntoStaticStr};
 
-use crate::DefaultSignalAction;
+use crate::{DefaultSignalAction, SignalError, SignalResult};
 
 /// Signal number.
 #[repr(u8)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, FromRepr, EnumIter)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, FromRepr, EnumIter, IntoStaticStr)]
 pub enum Signo {
     SIGHUP    = 1,
     SIGINT    = 2,
@@ -76,11 +80,153 @@ pub enum Signo {
     SIGRT32   = 64,
 }
 
+/// Number of signals (`_NSIG`). Signals are numbered from 1 to `NSIG`, the
+/// realtime ones starting at [`Signo::SIGRTMIN`].
+///
+/// The action table, the pending queues and the size of a user sigset are
+/// derived from it, so that a wider signal space only needs more [`Signo`]
+/// variants and a wider [`SignalSet`].
+pub const NSIG: usize = Signo::SIGRT32 as usize;
+
+/// Number of realtime signals.
+pub const NSIG_RT: usize = NSIG - Signo::SIGRTMIN as usize + 1;
+
+/// Size of a user sigset in bytes, as expected in the `sigsetsize` argument
+/// of the `rt_sig*` syscalls.
+pub const SIGSET_SIZE: usize = NSIG / 8;
+
+const _: () =
+    assert!(NSIG <= u64::BITS as usize && SIGSET_SIZE == mem::size_of::<kernel_sigset_t>());
+
+/// Realtime signals reserved by the libc threading implementation.
+///
+/// glibc uses signals 32 and 33 for thread cancellation and `setxid`, and
+/// musl one more, so the `SIGRTMIN` seen by applications is above
+/// [`Signo::SIGRTMIN`]. The reserved signals are still valid signals for the
+/// kernel: libc sends them with `tgkill` and installs their handlers with
+/// `rt_sigaction`, so they must not be rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LibcRtReserve(u8);
+
+impl LibcRtReserve {
+    /// The signals reserved by glibc.
+    pub const GLIBC: Self = Self(2);
+    /// The signals reserved by musl.
+    pub const MUSL: Self = Self(3);
+    /// No signal is reserved.
+    pub const NONE: Self = Self(0);
+
+    /// Reserves the first `count` realtime signals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is not less than [`NSIG_RT`].
+    pub const fn new(count: u8) -> Self {
+        assert!((count as usize) < NSIG_RT, "too many reserved signals");
+        Self(count)
+    }
+
+    /// Gets the number of reserved signals.
+    pub const fn count(&self) -> u8 {
+        self.0
+    }
+
+    /// Gets the `SIGRTMIN` seen by applications.
+    pub fn sigrtmin(&self) -> Signo {
+        Signo::from_repr(Signo::SIGRTMIN as u8 + self.0).unwrap()
+    }
+
+    /// Checks if a signal is reserved.
+    pub fn is_reserved(&self, signo: Signo) -> bool {
+        signo.is_realtime() && signo < self.sigrtmin()
+    }
+
+    /// Returns the set of reserved signals.
+    pub fn reserved(&self) -> SignalSet {
+        let mut set = SignalSet::default();
+        for signo in Signo::SIGRTMIN as u8..self.sigrtmin() as u8 {
+            set.add(Signo::from_repr(signo).unwrap());
+        }
+        set
+    }
+
+    /// Gets the signal `SIGRTMIN + offset` as seen by applications, or
+    /// `None` if it's beyond `SIGRTMAX`.
+    pub fn rt_signal(&self, offset: u8) -> Option<Signo> {
+        Signo::from_repr((self.sigrtmin() as u8).checked_add(offset)?)
+    }
+}
+
+impl TryFrom<u32> for Signo {
+    type Error = SignalError;
+
+    fn try_from(signo: u32) -> Result<Self, Self::Error> {
+        u8::try_from(signo)
+            .ok()
+            .and_then(Self::from_repr)
+            .ok_or(SignalError::InvalidSignal)
+    }
+}
+
 impl Signo {
+    /// Converts a signal number from user space, where 0 is the null signal
+    /// (`None`), which `kill` and `tgkill` accept to probe the target.
+    pub fn try_from_nullable(signo: u32) -> SignalResult<Option<Self>> {
+        if signo == 0 {
+            return Ok(None);
+        }
+        Self::try_from(signo).map(Some)
+    }
+
+    /// Checks if this is a real-time signal, `SIGRTMIN` or above.
     pub fn is_realtime(&self) -> bool {
         *self >= Signo::SIGRTMIN
     }
 
+    /// Checks if this is a stop signal: `SIGSTOP`, `SIGTSTP`, `SIGTTIN` or
+    /// `SIGTTOU`.
+    pub fn is_stop(&self) -> bool {
+        matches!(self.default_action(), DefaultSignalAction::Stop)
+    }
+
+    /// Checks if the default action of this signal terminates the process,
+    /// with or without a core dump.
+    pub fn is_fatal_by_default(&self) -> bool {
+        matches!(
+            self.default_action(),
+            DefaultSignalAction::Terminate | DefaultSignalAction::CoreDump
+        )
+    }
+
+    /// Checks if this signal reports a fault of the current instruction,
+    /// with the faulting address in `si_addr` when raised by the kernel:
+    /// `SIGSEGV`, `SIGBUS`, `SIGILL`, `SIGFPE` or `SIGTRAP`.
+    pub fn is_synchronous_fault(&self) -> bool {
+        matches!(
+            self,
+            Signo::SIGSEGV | Signo::SIGBUS | Signo::SIGILL | Signo::SIGFPE | Signo::SIGTRAP
+        )
+    }
+
+    /// Checks if this signal can't be blocked, caught or ignored: `SIGKILL`
+    /// or `SIGSTOP`.
+    pub fn is_unblockable(&self) -> bool {
+        matches!(self, Signo::SIGKILL | Signo::SIGSTOP)
+    }
+
+    /// Returns the `wait` status of a process killed by this signal, with the
+    /// `WCOREDUMP` bit set if a core dump was generated.
+    pub fn wait_status(&self, core_dumped: bool) -> i32 {
+        let status = *self as i32;
+        if core_dumped { status | 0x80 } else { status }
+    }
+
+    /// Returns the exit code a shell reports for a process killed by this
+    /// signal, i.e. `128 + signo`.
+    pub fn shell_exit_code(&self) -> i32 {
+        128 + *self as i32
+    }
+
     pub fn default_action(&self) -> DefaultSignalAction {
         match self {
             Signo::SIGHUP => DefaultSignalAction::Terminate,
@@ -120,7 +266,9 @@ impl Signo {
 }
 
 /// Signal set. Compatible with `struct sigset_t` in libc.
-#[derive(Default, Clone, Copy, Not, BitOr, BitOrAssign, BitAnd, BitAndAssign)]
+#[derive(
+    Default, Clone, Copy, PartialEq, Eq, Hash, Not, BitOr, BitOrAssign, BitAnd, BitAndAssign,
+)]
 #[repr(transparent)]
 pub struct SignalSet(u64);
 
@@ -129,6 +277,48 @@ impl SignalSet {
         1 << (signo as u8 - 1)
     }
 
+    /// Returns the set of synchronous signals, i.e. those generated by a
+    /// fault of the current instruction.
+    pub fn synchronous() -> Self {
+        let mut set = Self::default();
+        for signo in [
+            Signo::SIGSEGV,
+            Signo::SIGBUS,
+            Signo::SIGILL,
+            Signo::SIGTRAP,
+            Signo::SIGFPE,
+            Signo::SIGSYS,
+        ] {
+            set.add(signo);
+        }
+        set
+    }
+
+    /// Returns the set of stop signals: `SIGSTOP`, `SIGTSTP`, `SIGTTIN` and
+    /// `SIGTTOU`.
+    pub fn stop() -> Self {
+        let mut set = Self::default();
+        for signo in [
+            Signo::SIGSTOP,
+            Signo::SIGTSTP,
+            Signo::SIGTTIN,
+            Signo::SIGTTOU,
+        ] {
+            set.add(signo);
+        }
+        set
+    }
+
+    /// Creates a set from raw bits. Bit `n` represents signal `n + 1`.
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw bits of the set. Bit `n` represents signal `n + 1`.
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
     /// Adds a signal to the set.
     pub fn add(&mut self, signal: Signo) -> bool {
         let bit = Self::signo_bit(signal);
@@ -172,6 +362,13 @@ impl SignalSet {
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SignalSet {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self(u.arbitrary()?))
+    }
+}
+
 impl From<SignalSet> for kernel_sigset_t {
     fn from(value: SignalSet) -> Self {
         // SAFETY: `kernel_sigset_t` always has the same layout as `[c_ulong; 1]`.
@@ -198,13 +395,48 @@ impl fmt::Debug for SignalSet {
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SignalInfo {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        // Raw bytes as user space may pass in, except for the signal number,
+        // which is always valid in a `SignalInfo`.
+        let mut bytes = [0u8; mem::size_of::<siginfo_t>()];
+        u.fill_buffer(&mut bytes)?;
+        // SAFETY: `siginfo_t` is a plain C struct.
+        let mut result: Self = unsafe { mem::transmute(bytes) };
+        result.set_signo(Signo::from_repr(u.int_in_range(1..=NSIG as u8)?).unwrap());
+        Ok(result)
+    }
+}
+
+/// `si_code` of a `SIGCHLD`, describing the child state change.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildCode {
+    /// The child exited.
+    Exited    = CLD_EXITED as _,
+    /// The child was killed by a signal.
+    Killed    = CLD_KILLED as _,
+    /// The child was killed by a signal and dumped core.
+    Dumped    = CLD_DUMPED as _,
+    /// The traced child has trapped.
+    Trapped   = CLD_TRAPPED as _,
+    /// The child was stopped.
+    Stopped   = CLD_STOPPED as _,
+    /// The stopped child was continued.
+    Continued = CLD_CONTINUED as _,
+}
+
+/// Size of `siginfo_t` in bytes.
+pub const SIGINFO_SIZE: usize = mem::size_of::<siginfo_t>();
+
 /// Signal information. Compatible with `struct siginfo` in libc.
 #[derive(Clone)]
 #[repr(transparent)]
 pub struct SignalInfo(pub siginfo_t);
 
 impl SignalInfo {
-    pub fn new_kernel(signo: Signo) -> Self {
+    pub const fn new_kernel(signo: Signo) -> Self {
         // FIXME: Zeroable
         let mut result: Self = unsafe { mem::zeroed() };
         result.set_signo(signo);
@@ -227,11 +459,86 @@ impl SignalInfo {
         result
     }
 
+    /// Creates a `SIGCHLD` reporting a state change of child `pid`.
+    ///
+    /// `status` is the exit code for [`ChildCode::Exited`] and the signal
+    /// number otherwise.
+    pub fn new_child(code: ChildCode, pid: u32, uid: u32, status: i32) -> Self {
+        let mut result = Self::new_user(Signo::SIGCHLD, code as _, pid);
+        let fields = result.fields_mut();
+        fields._sigchld._uid = uid;
+        fields._sigchld._status = status;
+        result
+    }
+
+    /// Creates a signal reporting an I/O event on file `fd`, as sent to the
+    /// owner of the file with `F_SETSIG`.
+    ///
+    /// `code` is one of the `POLL_*` codes and `band` the poll events.
+    pub fn new_poll(signo: Signo, code: i32, fd: i32, band: i64) -> Self {
+        let mut result = Self::new_user(signo, code, 0);
+        let fields = result.fields_mut();
+        fields._sigpoll._band = band as _;
+        fields._sigpoll._fd = fd;
+        result
+    }
+
+    /// Creates a signal reporting the expiry of POSIX timer `timer_id`,
+    /// carrying the `sigevent` value `value`.
+    pub fn new_timer(signo: Signo, timer_id: i32, value: usize) -> Self {
+        let mut result = Self::new_user(signo, SI_TIMER, 0);
+        let fields = result.fields_mut();
+        fields._timer._tid = timer_id;
+        fields._timer._overrun = 0;
+        fields._timer._sigval.sival_ptr = value as _;
+        result
+    }
+
+    /// Creates a signal info from a raw `siginfo_t`, such as one from user
+    /// space.
+    ///
+    /// Fails with [`SignalError::InvalidSignal`] if the signal number is not
+    /// in `1..=NSIG`. The other fields are taken as is.
+    pub fn from_raw(raw: siginfo_t) -> SignalResult<Self> {
+        // SAFETY: The signal number is a plain integer.
+        let signo = unsafe { raw.__bindgen_anon_1.__bindgen_anon_1.si_signo };
+        Signo::try_from(signo as u32)?;
+        Ok(Self(raw))
+    }
+
+    /// Gets the raw `siginfo_t`.
+    pub fn as_raw(&self) -> &siginfo_t {
+        &self.0
+    }
+
+    /// Converts into the raw `siginfo_t`.
+    pub fn into_raw(self) -> siginfo_t {
+        self.0
+    }
+
+    /// Creates a signal info from the bytes of a `siginfo_t`, as passed to
+    /// `rt_sigqueueinfo` or `PTRACE_SETSIGINFO`.
+    ///
+    /// Fails with [`SignalError::InvalidArgument`] if `bytes` is not
+    /// [`SIGINFO_SIZE`] long, and as [`Self::from_raw`] otherwise.
+    pub fn from_bytes(bytes: &[u8]) -> SignalResult<Self> {
+        let bytes: [u8; SIGINFO_SIZE] =
+            bytes.try_into().map_err(|_| SignalError::InvalidArgument)?;
+        // SAFETY: `siginfo_t` is a plain C struct, valid for any bytes.
+        Self::from_raw(unsafe { mem::transmute::<[u8; SIGINFO_SIZE], siginfo_t>(bytes) })
+    }
+
+    /// Gets the bytes of the raw `siginfo_t`.
+    pub fn to_bytes(&self) -> [u8; SIGINFO_SIZE] {
+        // SAFETY: `siginfo_t` is a plain C struct without padding.
+        unsafe { mem::transmute_copy(&self.0) }
+    }
+
     pub fn signo(&self) -> Signo {
         unsafe { Signo::from_repr(self.0.__bindgen_anon_1.__bindgen_anon_1.si_signo as _).unwrap() }
     }
 
-    pub fn set_signo(&mut self, signo: Signo) {
+    pub const fn set_signo(&mut self, signo: Signo) {
         self.0.__bindgen_anon_1.__bindgen_anon_1.si_signo = signo as _;
     }
 
@@ -239,7 +546,7 @@ impl SignalInfo {
         unsafe { self.0.__bindgen_anon_1.__bindgen_anon_1.si_code }
     }
 
-    pub fn set_code(&mut self, code: i32) {
+    pub const fn set_code(&mut self, code: i32) {
         self.0.__bindgen_anon_1.__bindgen_anon_1.si_code = code;
     }
 
@@ -249,6 +556,124 @@ impl SignalInfo {
         // anonymous union.
         unsafe { self.0.__bindgen_anon_1.__bindgen_anon_1.si_errno }
     }
+
+    fn fields(&self) -> &__sifields {
+        // SAFETY: Every bit pattern is valid for the plain integer fields of
+        // the union.
+        unsafe { &self.0.__bindgen_anon_1.__bindgen_anon_1._sifields }
+    }
+
+    fn fields_mut(&mut self) -> &mut __sifields {
+        // SAFETY: See `fields`.
+        unsafe { &mut self.0.__bindgen_anon_1.__bindgen_anon_1._sifields }
+    }
+
+    /// Gets the sender pid (`si_pid`). Meaningful for user-sent signals and
+    /// `SIGCHLD`.
+    pub fn pid(&self) -> u32 {
+        unsafe { self.fields()._kill._pid as _ }
+    }
+
+    /// Gets the sender uid (`si_uid`). Meaningful for user-sent signals and
+    /// `SIGCHLD`.
+    pub fn uid(&self) -> u32 {
+        unsafe { self.fields()._kill._uid }
+    }
+
+    /// Sets the sender uid (`si_uid`).
+    pub fn set_uid(&mut self, uid: u32) {
+        self.fields_mut()._kill._uid = uid;
+    }
+
+    /// Gets the exit status or signal of a child (`si_status`). Meaningful for
+    /// `SIGCHLD`.
+    pub fn status(&self) -> i32 {
+        unsafe { self.fields()._sigchld._status }
+    }
+
+    /// Gets the faulting address (`si_addr`). Meaningful for `SIGSEGV`,
+    /// `SIGBUS`, `SIGILL`, `SIGFPE` and `SIGTRAP`.
+    pub fn addr(&self) -> usize {
+        unsafe { self.fields()._sigfault._addr as _ }
+    }
+
+    /// Sets the faulting address (`si_addr`).
+    pub fn set_addr(&mut self, addr: usize) {
+        self.fields_mut()._sigfault._addr = addr as _;
+    }
+
+    /// Gets the integer of the attached value (`si_int`). Meaningful for
+    /// `SI_QUEUE`, `SI_TIMER` and `SI_MESGQ`.
+    pub fn value_int(&self) -> i32 {
+        unsafe { self.fields()._rt._sigval.sival_int }
+    }
+
+    /// Gets the pointer of the attached value (`si_ptr`).
+    pub fn value_ptr(&self) -> usize {
+        unsafe { self.fields()._rt._sigval.sival_ptr as _ }
+    }
+
+    /// Gets the POSIX timer ID (`si_timerid`). Meaningful for `SI_TIMER`.
+    pub fn timer_id(&self) -> i32 {
+        unsafe { self.fields()._timer._tid }
+    }
+
+    /// Gets the timer overrun count (`si_overrun`). Meaningful for
+    /// `SI_TIMER`.
+    pub fn overrun(&self) -> i32 {
+        unsafe { self.fields()._timer._overrun }
+    }
+
+    /// Sets the timer overrun count (`si_overrun`).
+    pub fn set_overrun(&mut self, overrun: i32) {
+        self.fields_mut()._timer._overrun = overrun;
+    }
+
+    /// Whether this reports the expiry of a POSIX timer.
+    pub fn is_timer(&self) -> bool {
+        self.code() == SI_TIMER
+    }
+
+    /// Checks the `si_code` of a signal info supplied by user space to
+    /// `rt_sigqueueinfo` or `rt_tgsigqueueinfo`, as Linux does.
+    ///
+    /// Unless the signal is sent to the sending process itself (`to_self`),
+    /// the code must be negative and not `SI_TKILL`, so that signals from the
+    /// kernel or from `tgkill` cannot be forged. Fails with
+    /// [`SignalError::Permission`] otherwise.
+    pub fn check_user_code(&self, to_self: bool) -> SignalResult {
+        let code = self.code();
+        if !to_self && (code >= 0 || code == SI_TKILL) {
+            return Err(SignalError::Permission);
+        }
+        Ok(())
+    }
+
+    /// Whether this reports a fault raised by the kernel, for which
+    /// [`addr`](Self::addr) is the faulting address: its code is one of
+    /// the `SEGV_*`, `BUS_*`, `ILL_*`, `FPE_*` or `TRAP_*` codes of its
+    /// signal. A signal sent by the kernel with `SI_KERNEL` is not a fault.
+    pub fn is_fault(&self) -> bool {
+        let codes = match self.signo() {
+            Signo::SIGSEGV => NSIGSEGV,
+            Signo::SIGBUS => NSIGBUS,
+            Signo::SIGILL => NSIGILL,
+            Signo::SIGFPE => NSIGFPE,
+            Signo::SIGTRAP => NSIGTRAP,
+            _ => return false,
+        };
+        (1..=codes as i32).contains(&self.code())
+    }
+
+    /// Gets the band event (`si_band`). Meaningful for `SIGIO`/`SIGPOLL`.
+    pub fn band(&self) -> i64 {
+        unsafe { self.fields()._sigpoll._band as _ }
+    }
+
+    /// Gets the file descriptor (`si_fd`). Meaningful for `SIGIO`/`SIGPOLL`.
+    pub fn fd(&self) -> i32 {
+        unsafe { self.fields()._sigpoll._fd }
+    }
 }
 
 unsafe impl Send for SignalInfo {}
@@ -265,7 +690,7 @@ impl fmt::Debug for SignalInfo {
 
 /// Signal stack. Compatible with `struct sigaltstack` in libc.
 #[repr(C)]
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SignalStack {
     pub sp: usize,
     pub flags: u32,
@@ -283,8 +708,78 @@ impl Default for SignalStack {
 }
 
 impl SignalStack {
-    /// Checks if signal stack is disabled.
+    /// Creates an enabled signal stack of `size` bytes starting at `sp`.
+    pub fn new(sp: usize, size: usize) -> Self {
+        Self { sp, flags: 0, size }
+    }
+
+    /// Creates a disabled signal stack.
+    pub fn disabled_stack() -> Self {
+        Self::default()
+    }
+
+    /// Checks if signal stack is disabled, whatever the flags of
+    /// `SS_FLAG_BITS` (such as `SS_AUTODISARM`) along with it.
     pub fn disabled(&self) -> bool {
-        self.flags == SS_DISABLE
+        self.flags & !SS_FLAG_BITS == SS_DISABLE
+    }
+
+    /// Checks if the signal stack is disabled on entry to a handler running
+    /// on it, and restored on return, as with `SS_AUTODISARM`.
+    pub fn autodisarm(&self) -> bool {
+        self.enabled() && self.flags & SS_AUTODISARM != 0
+    }
+
+    /// Checks if signal stack is enabled.
+    pub fn enabled(&self) -> bool {
+        !self.disabled()
+    }
+
+    /// Returns the top of the signal stack, where frames are set up.
+    pub fn top(&self) -> usize {
+        self.sp + self.size
+    }
+
+    /// Checks if `sp` is on the signal stack, i.e. a handler running on it
+    /// has been interrupted.
+    pub fn contains(&self, sp: usize) -> bool {
+        self.enabled() && sp > self.sp && sp - self.sp <= self.size
+    }
+}
+
+impl TryFrom<stack_t> for SignalStack {
+    type Error = SignalError;
+
+    /// Validates a `stack_t` passed to `sigaltstack`.
+    fn try_from(stack: stack_t) -> Result<Self, Self::Error> {
+        let flags = stack.ss_flags as u32;
+        let mode = flags & !SS_FLAG_BITS;
+        if mode == SS_DISABLE {
+            return Ok(Self {
+                flags: SS_DISABLE | (flags & SS_FLAG_BITS),
+                ..Self::default()
+            });
+        }
+        if mode != 0 && mode != SS_ONSTACK {
+            return Err(SignalError::InvalidArgument);
+        }
+        if stack.ss_size < MINSIGSTKSZ as _ {
+            return Err(SignalError::StackTooSmall);
+        }
+        Ok(Self {
+            sp: stack.ss_sp as usize,
+            flags: flags & SS_FLAG_BITS,
+            size: stack.ss_size as usize,
+        })
+    }
+}
+
+impl From<SignalStack> for stack_t {
+    fn from(stack: SignalStack) -> Self {
+        Self {
+            ss_sp: stack.sp as _,
+            ss_flags: stack.flags as _,
+            ss_size: stack.size as _,
+        }
     }
 }
@@ -0,0 +1,52 @@
+//! Watchdog of signals pending for too long, for debugging.
+//!
+//! Pending queues record when each signal became pending. A deliverable
+//! signal left pending for long usually means a lost wakeup: the OS should
+//! call [`ProcessSignalManager::check_stale_signals`] periodically, which
+//! reports such signals.
+//!
+//! [`ProcessSignalManager::check_stale_signals`]: crate::api::ProcessSignalManager::check_stale_signals
+
+use kspin::SpinNoIrq;
+
+use crate::Signo;
+
+/// Reporter of a signal pending for too long, given the thread it is
+/// pending on (or `None` for the process), the signal and how long it has
+/// been pending.
+pub type StaleReporter = fn(tid: Option<u32>, signo: Signo, age: u64);
+
+static TIME_SOURCE: SpinNoIrq<fn() -> u64> = SpinNoIrq::new(|| 0);
+static REPORTER: SpinNoIrq<StaleReporter> = SpinNoIrq::new(warn_stale);
+
+/// Sets the function used to timestamp pending signals.
+///
+/// The unit is up to the OS, and is that of the threshold given to
+/// [`ProcessSignalManager::check_stale_signals`]. Timestamps are `0` until
+/// this is called.
+///
+/// [`ProcessSignalManager::check_stale_signals`]: crate::api::ProcessSignalManager::check_stale_signals
+pub fn set_time_source(source: fn() -> u64) {
+    *TIME_SOURCE.lock() = source;
+}
+
+/// Sets the function called for each signal pending for too long. By
+/// default a warning is logged.
+pub fn set_reporter(reporter: StaleReporter) {
+    *REPORTER.lock() = reporter;
+}
+
+pub(crate) fn now() -> u64 {
+    (TIME_SOURCE.lock())()
+}
+
+pub(crate) fn report(tid: Option<u32>, signo: Signo, age: u64) {
+    (REPORTER.lock())(tid, signo, age)
+}
+
+fn warn_stale(tid: Option<u32>, signo: Signo, age: u64) {
+    match tid {
+        Some(tid) => warn!("{signo:?} pending on thread {tid} for {age} without being delivered"),
+        None => warn!("{signo:?} pending on the process for {age} without being delivered"),
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use starry_signal::{
+    SignalInfo, SignalSet, Signo,
+    api::{PendingAccounting, set_pending_accounting},
+    test_util::TestEnv,
+};
+
+/// Allows two queued signals per user.
+#[derive(Default)]
+struct Counts(Mutex<HashMap<u32, usize>>);
+
+impl PendingAccounting for Counts {
+    fn charge(&self, uid: u32) -> bool {
+        let mut counts = self.0.lock().unwrap();
+        let count = counts.entry(uid).or_default();
+        if *count == 2 {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    fn release(&self, uid: u32) {
+        *self.0.lock().unwrap().get_mut(&uid).unwrap() -= 1;
+    }
+}
+
+fn user_signal(signo: Signo, uid: u32) -> SignalInfo {
+    let mut sig = SignalInfo::new_user(signo, 0, 1);
+    sig.set_uid(uid);
+    sig
+}
+
+#[test]
+fn per_uid_accounting() {
+    let counts = Arc::new(Counts::default());
+    set_pending_accounting(Some(counts.clone()));
+    let count = |uid| counts.0.lock().unwrap().get(&uid).copied().unwrap_or(0);
+
+    let env = TestEnv::builder().tid(7).build();
+    let (proc, thr) = (env.proc, env.thread);
+    let other = TestEnv::builder().tid(8).build();
+    thr.set_blocked(!SignalSet::default());
+
+    let _ = proc.send_signal(user_signal(Signo::SIGUSR1, 1000));
+    // Merged into the pending one.
+    let _ = proc.send_signal(user_signal(Signo::SIGUSR1, 1000));
+    let _ = other.proc.send_signal(user_signal(Signo::SIGUSR2, 1000));
+    assert_eq!(count(1000), 2);
+    // Over the limit.
+    let _ = thr.send_signal(user_signal(Signo::SIGTERM, 1000));
+    assert!(!thr.pending().has(Signo::SIGTERM));
+    // Other users and the kernel are not limited by it.
+    let _ = thr.send_signal(user_signal(Signo::SIGTERM, 1001));
+    let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGHUP));
+    assert_eq!(count(1001), 1);
+    assert!(thr.pending().has(Signo::SIGHUP));
+
+    assert_eq!(
+        thr.dequeue_signal(&!SignalSet::default()).unwrap().signo(),
+        Signo::SIGHUP
+    );
+    assert_eq!(
+        thr.dequeue_signal(&!SignalSet::default()).unwrap().signo(),
+        Signo::SIGTERM
+    );
+    assert_eq!(count(1001), 0);
+    assert_eq!(
+        thr.dequeue_signal(&!SignalSet::default()).unwrap().signo(),
+        Signo::SIGUSR1
+    );
+    assert_eq!(count(1000), 1);
+
+    // Discarded and dropped signals are released too.
+    other.proc.remove_signal(Signo::SIGUSR2);
+    assert_eq!(count(1000), 0);
+    let _ = proc.send_signal(user_signal(Signo::SIGINT, 1000));
+    let _ = other.proc.send_signal(user_signal(Signo::SIGINT, 1000));
+    assert_eq!(count(1000), 2);
+    drop(other);
+    assert_eq!(count(1000), 1);
+    drop((proc, thr));
+    assert_eq!(count(1000), 0);
+    set_pending_accounting(None);
+}
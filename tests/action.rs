@@ -1,5 +1,10 @@
-use linux_raw_sys::general::kernel_sigaction;
-use starry_signal::{SignalAction, SignalActionFlags, SignalDisposition, SignalSet, Signo};
+use linux_raw_sys::general::{
+    SA_NODEFER, SA_NOMASK, SA_ONESHOT, SA_RESETHAND, kernel_sigaction, siginfo_t,
+};
+use starry_signal::{
+    SignalAction, SignalActionFlags, SignalDisposition, SignalError, SignalSet, Signo,
+    arch::UContext, k_sigaction,
+};
 
 #[test]
 fn flags_bits() {
@@ -22,6 +27,7 @@ fn flags_bits() {
 #[test]
 fn convert() {
     unsafe extern "C" fn test_handler(_: i32) {}
+    unsafe extern "C" fn test_sigaction(_: i32, _: *mut siginfo_t, _: *mut UContext) {}
     let flag_disposition = vec![
         (SignalActionFlags::empty(), SignalDisposition::Default),
         (
@@ -29,9 +35,13 @@ fn convert() {
             SignalDisposition::Ignore,
         ),
         (
-            SignalActionFlags::SIGINFO | SignalActionFlags::NODEFER,
+            SignalActionFlags::NODEFER,
             SignalDisposition::Handler(test_handler),
         ),
+        (
+            SignalActionFlags::SIGINFO | SignalActionFlags::NODEFER,
+            SignalDisposition::SigactionHandler(test_sigaction),
+        ),
     ];
 
     for (flags, disposition) in flag_disposition {
@@ -48,29 +58,119 @@ fn convert() {
         };
         let ks: kernel_sigaction = action.clone().into();
         let action2 = SignalAction::from(ks);
-
-        assert_eq!(action.flags.bits(), action2.flags.bits());
-        assert_eq!(
-            action.mask.has(Signo::SIGINT),
-            action2.mask.has(Signo::SIGINT)
-        );
-        assert_eq!(
-            action.mask.has(Signo::SIGRT32),
-            action2.mask.has(Signo::SIGRT32)
-        );
-        match (&action.disposition, &action2.disposition) {
-            (SignalDisposition::Default, SignalDisposition::Default) => {}
-            (SignalDisposition::Ignore, SignalDisposition::Ignore) => {}
-            (SignalDisposition::Handler(h1), SignalDisposition::Handler(h2)) => {
-                let p1 = *h1 as usize;
-                let p2 = *h2 as usize;
-                assert_ne!(p1, 0);
-                assert_eq!(p1, p2);
-            }
-            _ => panic!(
-                "Unexpected disposition combination: {:?} -> {:?}",
-                action.disposition, action2.disposition
-            ),
-        }
+        assert_eq!(action, action2);
     }
 }
+
+#[test]
+fn probe_flags() {
+    let action = SignalAction {
+        flags: SignalActionFlags::SIGINFO
+            | SignalActionFlags::EXPOSE_TAGBITS
+            | SignalActionFlags::UNSUPPORTED,
+        ..Default::default()
+    };
+    let kact: kernel_sigaction = action.into();
+    let action = SignalAction::from(kact);
+    assert_eq!(
+        action.flags,
+        SignalActionFlags::SIGINFO | SignalActionFlags::EXPOSE_TAGBITS
+    );
+}
+
+#[test]
+fn convert_k_sigaction() {
+    unsafe extern "C" fn test_handler(_: i32, _: *mut siginfo_t, _: *mut UContext) {}
+    unsafe extern "C" fn test_restorer() {}
+    let action = SignalAction {
+        flags: SignalActionFlags::SIGINFO | SignalActionFlags::RESTORER,
+        mask: {
+            let mut m = SignalSet::default();
+            m.add(Signo::SIGINT);
+            m
+        },
+        disposition: SignalDisposition::SigactionHandler(test_handler),
+        restorer: Some(test_restorer),
+    };
+    let kact: k_sigaction = action.into();
+    assert!(kact.mask.has(Signo::SIGINT));
+    let action = SignalAction::from(kact);
+    assert!(matches!(
+        action.disposition,
+        SignalDisposition::SigactionHandler(_)
+    ));
+    assert!(action.flags.contains(SignalActionFlags::SIGINFO));
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    assert_eq!(
+        action.restorer.map(|f| f as *const () as usize),
+        Some(test_restorer as *const () as usize)
+    );
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    assert!(action.restorer.is_none());
+}
+
+#[test]
+fn legacy_flags() {
+    let mut raw: kernel_sigaction = unsafe { core::mem::zeroed() };
+    raw.sa_flags = (SA_NOMASK | SA_ONESHOT | 0x20000000) as _;
+    let action = SignalAction::from(raw);
+    assert!(action.flags.contains(SignalActionFlags::NODEFER));
+    assert!(action.flags.contains(SignalActionFlags::RESETHAND));
+    assert!(action.flags.contains(SignalActionFlags::INTERRUPT));
+
+    let raw = kernel_sigaction::from(action);
+    assert_eq!(raw.sa_flags, (SA_NODEFER | SA_RESETHAND | 0x20000000) as _);
+}
+
+#[test]
+fn builder() {
+    unsafe extern "C" fn handler(_: i32) {}
+    unsafe extern "C" fn sigaction(_: i32, _: *mut siginfo_t, _: *mut UContext) {}
+    unsafe extern "C" fn restorer() {}
+    let mut mask = SignalSet::default();
+    mask.add(Signo::SIGINT);
+
+    let action = SignalAction::builder()
+        .handler(handler)
+        .mask(mask)
+        .flags(SignalActionFlags::RESTART)
+        .restorer(restorer)
+        .build()
+        .unwrap();
+    assert_eq!(action.disposition, SignalDisposition::Handler(handler));
+    assert_eq!(action.mask, mask);
+    assert_eq!(
+        action.flags,
+        SignalActionFlags::RESTART | SignalActionFlags::RESTORER
+    );
+    assert!(action.restorer.is_some());
+
+    let action = SignalAction::builder()
+        .sigaction(sigaction)
+        .build()
+        .unwrap();
+    assert_eq!(action.flags, SignalActionFlags::SIGINFO);
+
+    assert_eq!(
+        SignalAction::builder()
+            .handler(handler)
+            .flags(SignalActionFlags::SIGINFO)
+            .build(),
+        Err(SignalError::InvalidArgument)
+    );
+    assert_eq!(
+        SignalAction::builder()
+            .flags(SignalActionFlags::RESTORER)
+            .build(),
+        Err(SignalError::InvalidArgument)
+    );
+    assert_eq!(
+        SignalAction::builder()
+            .disposition(SignalDisposition::Ignore)
+            .build(),
+        Ok(SignalAction {
+            disposition: SignalDisposition::Ignore,
+            ..Default::default()
+        })
+    );
+}
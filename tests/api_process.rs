@@ -1,9 +1,18 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use kspin::SpinNoIrq;
 use starry_signal::{
-    SignalActionFlags, SignalDisposition, SignalInfo, Signo,
-    api::{ProcessSignalManager, SignalActions, ThreadSignalManager},
+    ChildCode, EINTR, ERESTART_RESTARTBLOCK, ERESTARTSYS, RestartPolicy, SignalActionFlags,
+    SignalDisposition, SignalError, SignalInfo, SignalSet, Signo, SleepRestart,
+    api::{
+        ActionsInheritance, CpuClock, CpuItimer, CpuTimeAccounting, CpuTimeTick, CpuTimer, Itimer,
+        PressureThresholds, ProcessSignalManager, SignalActions, SignalPressure,
+        SignalPressureHook, SignalReadiness, SignalWake, StopEvent, ThreadSignalManager,
+        TimerSignalHook, WaitOptions, notify_winch,
+    },
 };
 
 struct TestEnv {
@@ -24,17 +33,93 @@ fn send_wakes_sets_pending() {
     let _thr = ThreadSignalManager::new(9, env.proc.clone());
     let sig = SignalInfo::new_user(Signo::SIGTERM, 0, 100);
 
-    assert_eq!(env.proc.send_signal(sig.clone()), Some(9));
+    assert_eq!(env.proc.send_signal(sig.clone()), SignalWake::All(vec![9]));
     assert!(env.proc.pending().has(Signo::SIGTERM));
 }
 
+#[test]
+fn send_round_robin() {
+    let env = TestEnv::new();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    env.proc.actions().lock()[Signo::SIGRTMIN].disposition =
+        SignalDisposition::Handler(test_handler);
+    let threads: Vec<_> = (1..=3)
+        .map(|tid| ThreadSignalManager::new(tid, env.proc.clone()))
+        .collect();
+    let send = || {
+        env.proc
+            .send_signal(SignalInfo::new_user(Signo::SIGRTMIN, 0, 100))
+    };
+
+    assert_eq!(
+        [send(), send(), send(), send()],
+        [1, 2, 3, 1].map(SignalWake::One)
+    );
+
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGRTMIN);
+    threads[2].set_blocked(blocked);
+    assert_eq!([send(), send(), send()], [2, 1, 2].map(SignalWake::One));
+}
+
+#[test]
+fn send_broadcast() {
+    let env = TestEnv::new();
+    let _threads = [1, 2].map(|tid| ThreadSignalManager::new(tid, env.proc.clone()));
+
+    let wake = env.proc.send_signal(SignalInfo::new_kernel(Signo::SIGKILL));
+    assert_eq!(wake, SignalWake::All(vec![1, 2]));
+    let wake = env.proc.send_signal(SignalInfo::new_kernel(Signo::SIGSTOP));
+    assert_eq!(wake, SignalWake::All(vec![2, 1]));
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    env.proc.actions().lock()[Signo::SIGTERM].disposition =
+        SignalDisposition::Handler(test_handler);
+    let wake = env.proc.send_signal(SignalInfo::new_kernel(Signo::SIGTERM));
+    assert_eq!(wake, SignalWake::One(1));
+    assert_eq!(wake.tids(), [1]);
+}
+
+#[test]
+fn send_batch() {
+    let env = TestEnv::new();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    for signo in [Signo::SIGUSR1, Signo::SIGRTMIN] {
+        env.proc.actions().lock()[signo].disposition = SignalDisposition::Handler(test_handler);
+    }
+    let thr = ThreadSignalManager::new(1, env.proc.clone());
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGUSR1);
+    thr.set_blocked(blocked);
+
+    let wake = env.proc.send_signals(&[
+        SignalInfo::new_user(Signo::SIGUSR1, 0, 100),
+        SignalInfo::new_user(Signo::SIGRTMIN, 1, 100),
+        SignalInfo::new_user(Signo::SIGCHLD, 0, 100),
+        SignalInfo::new_user(Signo::SIGRTMIN, 2, 100),
+    ]);
+    assert_eq!(wake, SignalWake::One(1));
+    assert!(env.proc.pending().has(Signo::SIGUSR1));
+    assert!(!env.proc.pending().has(Signo::SIGCHLD));
+    let codes: Vec<_> = std::iter::from_fn(|| thr.dequeue_signal(&!blocked))
+        .map(|sig| sig.code())
+        .collect();
+    assert_eq!(codes, [1, 2]);
+
+    let wake = env.proc.send_signals(&[
+        SignalInfo::new_user(Signo::SIGRTMIN, 0, 100),
+        SignalInfo::new_user(Signo::SIGTERM, 0, 100),
+    ]);
+    assert_eq!(wake, SignalWake::All(vec![1]));
+}
+
 #[test]
 fn signal_ignore() {
     let env = TestEnv::new();
-    env.proc.actions.lock()[Signo::SIGTERM].disposition = SignalDisposition::Ignore;
+    env.proc.actions().lock()[Signo::SIGTERM].disposition = SignalDisposition::Ignore;
     let sig = SignalInfo::new_user(Signo::SIGTERM, 0, 100);
 
-    assert_eq!(env.proc.send_signal(sig), None);
+    assert_eq!(env.proc.send_signal(sig), SignalWake::None);
     assert!(!env.proc.pending().has(Signo::SIGTERM));
 }
 
@@ -43,8 +128,755 @@ fn can_restart() {
     let env = TestEnv::new();
     assert!(!env.proc.can_restart(Signo::SIGTERM));
 
-    env.proc.actions.lock()[Signo::SIGTERM]
+    env.proc.actions().lock()[Signo::SIGTERM]
         .flags
         .insert(SignalActionFlags::RESTART);
     assert!(env.proc.can_restart(Signo::SIGTERM));
 }
+
+#[test]
+fn on_exec() {
+    let env = TestEnv::new();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    {
+        let table = env.proc.actions();
+        let mut actions = table.lock();
+        actions[Signo::SIGTERM].disposition = SignalDisposition::Handler(test_handler);
+        actions[Signo::SIGTERM].flags = SignalActionFlags::RESTART;
+        actions[Signo::SIGINT].disposition = SignalDisposition::Ignore;
+        actions[Signo::SIGINT].flags = SignalActionFlags::RESTART;
+    }
+    let _thr = ThreadSignalManager::new(9, env.proc.clone());
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 100));
+
+    // A process sharing the table, as with `CLONE_SIGHAND`, keeps it.
+    let sibling = env.proc.new_child(ActionsInheritance::Share);
+    env.proc.on_exec(0x1000);
+    assert!(
+        sibling.actions().lock()[Signo::SIGTERM]
+            .disposition
+            .is_handler()
+    );
+    assert_eq!(
+        sibling.actions().lock()[Signo::SIGINT].flags,
+        SignalActionFlags::RESTART
+    );
+
+    let table = env.proc.actions();
+    let actions = table.lock();
+    assert!(matches!(
+        actions[Signo::SIGTERM].disposition,
+        SignalDisposition::Default
+    ));
+    assert!(actions[Signo::SIGTERM].flags.is_empty());
+    assert!(matches!(
+        actions[Signo::SIGINT].disposition,
+        SignalDisposition::Ignore
+    ));
+    assert!(actions[Signo::SIGINT].flags.is_empty());
+    drop(actions);
+
+    assert_eq!(env.proc.default_restorer(), 0x1000);
+    assert!(env.proc.pending().has(Signo::SIGTERM));
+}
+
+#[test]
+fn inherit_actions() {
+    let env = TestEnv::new();
+    env.proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Ignore;
+
+    let shared = env.proc.new_child(ActionsInheritance::Share);
+    let copied = env.proc.new_child(ActionsInheritance::Copy);
+    let cow = env.proc.new_child(ActionsInheritance::CopyOnWrite);
+    for child in [&shared, &copied, &cow] {
+        assert!(child.signal_ignored(Signo::SIGINT));
+    }
+
+    env.proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Default;
+    assert!(!shared.signal_ignored(Signo::SIGINT));
+    assert!(copied.signal_ignored(Signo::SIGINT));
+    assert!(cow.signal_ignored(Signo::SIGINT));
+
+    cow.actions().lock()[Signo::SIGTERM].disposition = SignalDisposition::Ignore;
+    assert!(cow.signal_ignored(Signo::SIGTERM));
+    assert!(!env.proc.signal_ignored(Signo::SIGTERM));
+}
+
+#[test]
+fn replace_actions() {
+    let env = TestEnv::new();
+    let proc = &env.proc;
+    let _thr = ThreadSignalManager::new(9, proc.clone());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+
+    let mut table = SignalActions::default();
+    table[Signo::SIGUSR1].disposition = SignalDisposition::Ignore;
+    table[Signo::SIGKILL].disposition = SignalDisposition::Ignore;
+    let old = proc.replace_actions(table);
+    assert!(matches!(
+        old[Signo::SIGUSR1].disposition,
+        SignalDisposition::Handler(_)
+    ));
+    assert!(proc.signal_ignored(Signo::SIGUSR1));
+    assert!(!proc.signal_ignored(Signo::SIGKILL));
+    assert!(!proc.pending().has(Signo::SIGUSR1));
+    assert!(proc.pending().has(Signo::SIGUSR2));
+
+    let restored = proc.actions().lock().replace(old);
+    assert!(matches!(
+        restored[Signo::SIGUSR1].disposition,
+        SignalDisposition::Ignore
+    ));
+    assert!(!proc.signal_ignored(Signo::SIGUSR1));
+}
+
+#[test]
+fn cputime_tick() {
+    let env = TestEnv::new();
+    let proc = &env.proc;
+    let tick = |secs| proc.on_cputime_tick(Duration::from_secs(secs), 2, 4);
+
+    assert_eq!(tick(1), None);
+    assert_eq!(tick(2), Some(Signo::SIGXCPU));
+    assert_eq!(tick(2), None);
+    assert_eq!(tick(3), Some(Signo::SIGXCPU));
+    assert_eq!(tick(4), Some(Signo::SIGKILL));
+    assert_eq!(
+        proc.on_cputime_tick(Duration::from_secs(100), u64::MAX, u64::MAX),
+        None
+    );
+}
+
+#[test]
+fn notify_child() {
+    let env = TestEnv::new();
+    let proc = &env.proc;
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGCHLD].disposition = SignalDisposition::Handler(test_handler);
+
+    let _ = proc.notify_child(ChildCode::Stopped, 42, 1000, Signo::SIGTSTP as i32);
+    assert!(proc.pending().has(Signo::SIGCHLD));
+    let thr = ThreadSignalManager::new(1, proc.clone());
+    let sig = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!(sig.code(), ChildCode::Stopped as i32);
+    assert_eq!(sig.pid(), 42);
+    assert_eq!(sig.uid(), 1000);
+    assert_eq!(sig.status(), Signo::SIGTSTP as i32);
+
+    proc.actions().lock()[Signo::SIGCHLD].flags = SignalActionFlags::NOCLDSTOP;
+    let _ = proc.notify_child(ChildCode::Stopped, 42, 1000, Signo::SIGTSTP as i32);
+    let _ = proc.notify_child(ChildCode::Continued, 42, 1000, Signo::SIGCONT as i32);
+    let _ = proc.notify_child(ChildCode::Trapped, 42, 1000, Signo::SIGTRAP as i32);
+    assert!(!proc.pending().has(Signo::SIGCHLD));
+    let _ = proc.notify_child(ChildCode::Exited, 42, 1000, 0);
+    assert!(proc.pending().has(Signo::SIGCHLD));
+}
+
+#[test]
+fn autoreap_children() {
+    let env = TestEnv::new();
+    let proc = &env.proc;
+    unsafe extern "C" fn test_handler(_: i32) {}
+    assert!(!proc.should_autoreap_children());
+
+    proc.actions().lock()[Signo::SIGCHLD].disposition = SignalDisposition::Ignore;
+    assert!(proc.should_autoreap_children());
+
+    proc.actions().lock()[Signo::SIGCHLD].disposition = SignalDisposition::Handler(test_handler);
+    assert!(!proc.should_autoreap_children());
+    proc.actions().lock()[Signo::SIGCHLD].flags = SignalActionFlags::NOCLDWAIT;
+    assert!(proc.should_autoreap_children());
+
+    let _ = proc.notify_child(ChildCode::Exited, 42, 0, 0);
+    let _ = proc.notify_child(ChildCode::Killed, 42, 0, Signo::SIGKILL as i32);
+    assert!(!proc.pending().has(Signo::SIGCHLD));
+    let _ = proc.notify_child(ChildCode::Stopped, 42, 0, Signo::SIGSTOP as i32);
+    assert!(proc.pending().has(Signo::SIGCHLD));
+}
+
+#[test]
+fn restart_policy() {
+    let env = TestEnv::new();
+    let proc = &env.proc;
+    unsafe extern "C" fn test_handler(_: i32) {}
+
+    assert!(proc.restarts(Signo::SIGCHLD, RestartPolicy::NotAfterHandler));
+
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    assert!(!proc.restarts(Signo::SIGUSR1, RestartPolicy::Restartable));
+    assert!(proc.restarts(Signo::SIGUSR1, RestartPolicy::Always));
+
+    proc.actions().lock()[Signo::SIGUSR1].flags = SignalActionFlags::RESTART;
+    assert!(proc.can_restart(Signo::SIGUSR1));
+    assert!(proc.restarts(Signo::SIGUSR1, RestartPolicy::Restartable));
+    assert!(!proc.restarts(Signo::SIGUSR1, RestartPolicy::NotAfterHandler));
+}
+
+#[test]
+fn sleep_restart() {
+    let env = TestEnv::new();
+    let proc = &env.proc;
+    unsafe extern "C" fn test_handler(_: i32) {}
+    let left = Duration::from_millis(30);
+
+    // Without a handler, the sleep resumes with the time left.
+    let outcome = proc.sleep_restart(Signo::SIGCONT, RestartPolicy::NotAfterHandler, Some(left));
+    assert_eq!(outcome, SleepRestart::RestartBlock(left));
+    assert_eq!(
+        outcome.errno(RestartPolicy::NotAfterHandler),
+        ERESTART_RESTARTBLOCK
+    );
+    let outcome = proc.sleep_restart(Signo::SIGCONT, RestartPolicy::Restartable, None);
+    assert_eq!(outcome, SleepRestart::Restart);
+    assert_eq!(outcome.errno(RestartPolicy::Restartable), ERESTARTSYS);
+
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGUSR1].flags = SignalActionFlags::RESTART;
+    let outcome = proc.sleep_restart(Signo::SIGUSR1, RestartPolicy::NotAfterHandler, Some(left));
+    assert_eq!(outcome, SleepRestart::Interrupted);
+    assert_eq!(outcome.errno(RestartPolicy::NotAfterHandler), EINTR);
+    assert_eq!(
+        proc.sleep_restart(Signo::SIGUSR1, RestartPolicy::Restartable, None),
+        SleepRestart::Restart
+    );
+}
+
+#[test]
+fn winch() {
+    let (default, handled, ignored) = (TestEnv::new(), TestEnv::new(), TestEnv::new());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    handled.proc.actions().lock()[Signo::SIGWINCH].disposition =
+        SignalDisposition::Handler(test_handler);
+    ignored.proc.actions().lock()[Signo::SIGWINCH].disposition = SignalDisposition::Ignore;
+    let _threads = [
+        ThreadSignalManager::new(1, default.proc.clone()),
+        ThreadSignalManager::new(2, handled.proc.clone()),
+        ThreadSignalManager::new(3, ignored.proc.clone()),
+    ];
+
+    let woken = notify_winch([&*default.proc, &*handled.proc, &*ignored.proc]);
+    assert_eq!(woken, [2]);
+    assert!(!default.proc.pending().has(Signo::SIGWINCH));
+    assert!(handled.proc.pending().has(Signo::SIGWINCH));
+    assert!(!ignored.proc.pending().has(Signo::SIGWINCH));
+}
+
+#[test]
+fn remove_signal() {
+    let env = TestEnv::new();
+    let threads = [1, 2].map(|tid| ThreadSignalManager::new(tid, env.proc.clone()));
+    let sig = SignalInfo::new_user(Signo::SIGTSTP, 0, 100);
+    let _ = env.proc.send_signal(sig.clone());
+    assert!(threads[0].send_signal(sig.clone()));
+    assert!(threads[1].send_signal(SignalInfo::new_user(Signo::SIGINT, 0, 100)));
+
+    assert!(env.proc.remove_signal(Signo::SIGTSTP));
+    assert!(!env.proc.pending().has(Signo::SIGTSTP));
+    assert!(!threads[0].pending().has(Signo::SIGTSTP));
+    assert!(threads[1].pending().has(Signo::SIGINT));
+    assert!(!env.proc.remove_signal(Signo::SIGTSTP));
+
+    assert!(threads[1].remove_signal(Signo::SIGINT));
+    assert!(threads[1].pending().is_empty());
+}
+
+#[test]
+fn flush_stop_signals() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(1, env.proc.clone());
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGTTIN, 0, 100));
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 100));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGTSTP, 0, 100)));
+
+    assert!(env.proc.flush_stop_signals());
+    assert!((thr.pending() & SignalSet::stop()).is_empty());
+    assert!(thr.pending().has(Signo::SIGTERM));
+    assert!(!env.proc.flush_stop_signals());
+}
+
+#[test]
+fn clear_all() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(1, env.proc.clone());
+    drop(ThreadSignalManager::new(2, env.proc.clone()));
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 100));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGINT, 0, 100)));
+    assert_eq!(thr.notify(3), Ok(true));
+    assert!(env.proc.stop(Signo::SIGSTOP));
+
+    env.proc.clear_all();
+    assert!(env.proc.is_exiting());
+    assert!(!env.proc.is_stopped());
+    assert!(
+        env.proc
+            .take_event(WaitOptions::STOPPED | WaitOptions::CONTINUED)
+            .is_none()
+    );
+    assert!(thr.pending().is_empty());
+    assert_eq!(thr.notifications(), 0);
+
+    let sig = SignalInfo::new_user(Signo::SIGKILL, 0, 100);
+    assert_eq!(env.proc.send_signal(sig.clone()), SignalWake::None);
+    assert!(!thr.send_signal(sig));
+    assert!(thr.pending().is_empty());
+}
+
+#[test]
+fn stop_cont() {
+    let env = TestEnv::new();
+    let proc = &env.proc;
+    let all = WaitOptions::STOPPED | WaitOptions::CONTINUED;
+    assert!(!proc.is_stopped());
+    assert!(!proc.cont());
+
+    assert!(proc.stop(Signo::SIGTSTP));
+    assert!(!proc.stop(Signo::SIGSTOP));
+    assert!(proc.is_stopped());
+    assert_eq!(proc.stop_signal(), Some(Signo::SIGTSTP));
+    assert_eq!(proc.take_event(WaitOptions::CONTINUED), None);
+    let stopped = Some(StopEvent::Stopped(Signo::SIGTSTP));
+    assert_eq!(proc.take_event(all | WaitOptions::NOWAIT), stopped);
+    assert_eq!(proc.take_event(all), stopped);
+    assert_eq!(proc.take_event(all), None);
+
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTTOU, 0, 100));
+    assert!(proc.cont());
+    assert!(!proc.is_stopped());
+    assert!(!proc.pending().has(Signo::SIGTTOU));
+    assert_eq!(proc.take_event(WaitOptions::STOPPED), None);
+    assert_eq!(proc.take_event(all), Some(StopEvent::Continued));
+    assert_eq!(proc.take_event(all), None);
+
+    // A continue not yet reported supersedes the stop.
+    assert!(proc.stop(Signo::SIGSTOP));
+    assert!(proc.cont());
+    assert_eq!(proc.take_event(all), Some(StopEvent::Continued));
+}
+
+#[test]
+fn readiness_notified_on_send() {
+    struct Consumer {
+        interest: SignalSet,
+        ready: Mutex<Vec<(Option<u32>, Signo)>>,
+    }
+
+    impl SignalReadiness for Consumer {
+        fn interest(&self) -> SignalSet {
+            self.interest
+        }
+
+        fn notify_ready(&self, tid: Option<u32>, signo: Signo) {
+            self.ready.lock().unwrap().push((tid, signo));
+        }
+    }
+
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    let mut interest = SignalSet::default();
+    interest.add(Signo::SIGUSR1);
+    interest.add(Signo::SIGCHLD);
+    let consumer = Arc::new(Consumer {
+        interest,
+        ready: Mutex::new(Vec::new()),
+    });
+    let dyn_consumer: Arc<dyn SignalReadiness> = consumer.clone();
+    env.proc.register_readiness(&dyn_consumer);
+
+    // Blocked signals are reported too.
+    thr.set_blocked(interest);
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    // Not of interest.
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+    // Ignored by default.
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGCHLD, 0, 1));
+    assert_eq!(
+        *consumer.ready.lock().unwrap(),
+        [(None, Signo::SIGUSR1), (Some(9), Signo::SIGUSR1)]
+    );
+
+    env.proc.unregister_readiness(&dyn_consumer);
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    assert_eq!(consumer.ready.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn timer_overrun() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    for _ in 0..3 {
+        let _ = env
+            .proc
+            .send_signal(SignalInfo::new_timer(Signo::SIGALRM, 5, 0));
+    }
+    assert_eq!(env.proc.timer_overrun(5), 0);
+
+    let sig = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!(sig.overrun(), 2);
+    assert_eq!(env.proc.timer_overrun(5), 2);
+
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_timer(Signo::SIGALRM, 5, 0));
+    assert_eq!(
+        thr.dequeue_signal(&!SignalSet::default())
+            .unwrap()
+            .overrun(),
+        0
+    );
+    assert_eq!(env.proc.timer_overrun(5), 0);
+
+    let _ = thr.send_signal(SignalInfo::new_timer(Signo::SIGALRM, 5, 0));
+    let _ = thr.send_signal(SignalInfo::new_timer(Signo::SIGALRM, 5, 0));
+    let _ = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!(env.proc.timer_overrun(5), 1);
+    env.proc.forget_timer(5);
+    assert_eq!(env.proc.timer_overrun(5), 0);
+}
+
+#[test]
+fn timer_single_instance() {
+    struct Hook(Mutex<Vec<(i32, i32)>>);
+    impl TimerSignalHook for Hook {
+        fn on_dequeue(&self, timer_id: i32, overrun: i32) {
+            self.0.lock().unwrap().push((timer_id, overrun));
+        }
+    }
+
+    let env = TestEnv::new();
+    let hook = Arc::new(Hook(Mutex::new(Vec::new())));
+    env.proc.set_timer_hook(Some(hook.clone()));
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+
+    // The signal is in flight in the queue of the thread, so an expiry sent
+    // to the process is counted in it.
+    let _ = thr.send_signal(SignalInfo::new_timer(Signo::SIGALRM, 4, 0));
+    let _ = env.proc.send_signals(&[
+        SignalInfo::new_timer(Signo::SIGALRM, 4, 0),
+        SignalInfo::new_timer(Signo::SIGALRM, 6, 0),
+    ]);
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_timer(Signo::SIGALRM, 4, 0));
+    assert!(env.proc.pending().has(Signo::SIGALRM));
+    let sig = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!((sig.timer_id(), sig.overrun()), (4, 2));
+    let sig = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!((sig.timer_id(), sig.overrun()), (6, 0));
+    assert!(thr.dequeue_signal(&!SignalSet::default()).is_none());
+    assert_eq!(*hook.0.lock().unwrap(), [(4, 2), (6, 0)]);
+
+    // A discarded signal is no longer in flight.
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_timer(Signo::SIGALRM, 4, 0));
+    env.proc.remove_signal(Signo::SIGALRM);
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_timer(Signo::SIGALRM, 4, 0));
+    let sig = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!((sig.timer_id(), sig.overrun()), (4, 0));
+}
+
+#[test]
+fn cpu_timer() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    let other = ThreadSignalManager::new(10, env.proc.clone());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    env.proc.actions().lock()[Signo::SIGALRM].disposition =
+        SignalDisposition::Handler(test_handler);
+    let ms = Duration::from_millis;
+    let timer = CpuTimer {
+        clock: CpuClock::Process,
+        signo: Signo::SIGALRM,
+        value: 0x1234,
+        target: None,
+        expires: ms(10),
+        interval: ms(10),
+    };
+    assert!(env.proc.set_cpu_timer(1, timer.clone()).is_none());
+    assert!(
+        env.proc
+            .set_cpu_timer(
+                2,
+                CpuTimer {
+                    clock: CpuClock::Thread(10),
+                    signo: Signo::SIGUSR1,
+                    target: Some(10),
+                    interval: Duration::ZERO,
+                    ..timer.clone()
+                }
+            )
+            .is_none()
+    );
+
+    assert!(thr.on_task_tick(ms(100), ms(5)).is_empty());
+    // Three periods missed.
+    assert_eq!(thr.on_task_tick(ms(100), ms(42)), [9]);
+    let sig = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!((sig.timer_id(), sig.overrun()), (1, 3));
+    assert_eq!(sig.value_ptr(), 0x1234);
+    assert_eq!(env.proc.cpu_timer(1).unwrap().expires, ms(50));
+
+    // The thread clock of another thread is not measured by this tick.
+    assert!(thr.on_task_tick(ms(100), ms(45)).is_empty());
+    assert!(other.on_task_tick(ms(10), ms(50)).contains(&10));
+    assert!(!env.proc.cpu_timer(2).unwrap().armed());
+    let sig = other.dequeue_signal(&!SignalSet::default()).unwrap();
+    assert_eq!((sig.signo(), sig.timer_id()), (Signo::SIGUSR1, 2));
+
+    assert_eq!(env.proc.delete_cpu_timer(1).unwrap().expires, ms(60));
+    assert!(env.proc.cpu_timer(1).is_none());
+}
+
+#[test]
+fn cpu_itimer() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    thr.set_blocked(!SignalSet::default());
+    let ms = Duration::from_millis;
+    let itimer = Itimer {
+        value: ms(10),
+        interval: ms(10),
+    };
+    assert_eq!(
+        env.proc.set_cpu_itimer(CpuItimer::Virtual, itimer),
+        Itimer::default()
+    );
+    env.proc.set_cpu_itimer(
+        CpuItimer::Prof,
+        Itimer {
+            interval: Duration::ZERO,
+            ..itimer
+        },
+    );
+
+    let _ = env.proc.charge(ms(4), ms(4));
+    assert_eq!(env.proc.cpu_itimer(CpuItimer::Virtual).value, ms(6));
+    assert_eq!(env.proc.cpu_itimer(CpuItimer::Prof).value, ms(2));
+    assert!(env.proc.pending().is_empty());
+
+    // The virtual timer only counts user time.
+    let _ = env.proc.charge(ms(1), ms(3));
+    let mut expected = SignalSet::default();
+    expected.add(Signo::SIGPROF);
+    assert_eq!(env.proc.pending(), expected);
+    assert_eq!(env.proc.cpu_itimer(CpuItimer::Virtual).value, ms(5));
+    assert_eq!(env.proc.cpu_itimer(CpuItimer::Prof), Itimer::default());
+
+    // Reloaded with the overshoot deducted.
+    let _ = env.proc.charge(ms(8), Duration::ZERO);
+    expected.add(Signo::SIGVTALRM);
+    assert_eq!(env.proc.pending(), expected);
+    assert_eq!(env.proc.cpu_itimer(CpuItimer::Virtual).value, ms(7));
+}
+
+#[test]
+fn real_itimer() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    thr.set_blocked(!SignalSet::default());
+    let ms = Duration::from_millis;
+    let old = env.proc.set_real_itimer(
+        Itimer {
+            value: ms(10),
+            interval: ms(10),
+        },
+        ms(1000),
+    );
+    assert_eq!(old, Itimer::default());
+    assert_eq!(env.proc.real_itimer_deadline(), Some(ms(1010)));
+    assert_eq!(env.proc.real_itimer(ms(1004)).value, ms(6));
+
+    // Early or stale expiries are ignored.
+    assert!(env.proc.expire_real_itimer(ms(1009)).is_none());
+    assert!(env.proc.pending().is_empty());
+
+    // Late by 2ms: the next period is still at 1020.
+    let _ = env.proc.expire_real_itimer(ms(1012));
+    assert!(env.proc.pending().has(Signo::SIGALRM));
+    assert_eq!(env.proc.real_itimer_deadline(), Some(ms(1020)));
+
+    // The previous SIGALRM is still pending: the expiry is folded into it,
+    // but the timer is rearmed, skipping the periods missed.
+    let _ = env.proc.expire_real_itimer(ms(1045));
+    assert_eq!(env.proc.real_itimer_deadline(), Some(ms(1050)));
+    assert_eq!(
+        thr.dequeue_signal(&!SignalSet::default()).unwrap().signo(),
+        Signo::SIGALRM
+    );
+    assert!(thr.dequeue_signal(&!SignalSet::default()).is_none());
+
+    // A one-shot timer is disarmed on expiry.
+    env.proc.set_real_itimer(
+        Itimer {
+            value: ms(5),
+            interval: Duration::ZERO,
+        },
+        ms(2000),
+    );
+    let _ = env.proc.expire_real_itimer(ms(2005));
+    assert_eq!(env.proc.real_itimer_deadline(), None);
+    assert_eq!(env.proc.real_itimer(ms(2006)), Itimer::default());
+}
+
+#[test]
+fn rt_queue_limit() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    env.proc.actions().lock()[Signo::SIGRTMIN].disposition =
+        SignalDisposition::Handler(test_handler);
+    env.proc.set_rt_queue_limit(2);
+    assert_eq!(env.proc.rt_queue_limit(), 2);
+
+    let sig = SignalInfo::new_user(Signo::SIGRTMIN, 0, 1);
+    for _ in 0..2 {
+        assert!(env.proc.try_send_signal(sig.clone()).is_ok());
+        assert!(thr.try_send_signal(sig.clone()).is_ok());
+    }
+    assert_eq!(
+        env.proc.try_send_signal(sig.clone()),
+        Err(SignalError::WouldBlock)
+    );
+    assert_eq!(
+        thr.try_send_signal(sig.clone()),
+        Err(SignalError::WouldBlock)
+    );
+    // Dropped by the plain version.
+    let _ = env.proc.send_signal(sig.clone());
+    // Threads created later get the cap too.
+    let other = ThreadSignalManager::new(10, env.proc.clone());
+    assert!(other.try_send_signal(sig.clone()).is_ok());
+    assert!(other.try_send_signal(sig.clone()).is_ok());
+    assert!(other.try_send_signal(sig.clone()).is_err());
+    // Standard signals are merged, never refused.
+    let _ = thr
+        .try_send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1))
+        .unwrap();
+    let _ = thr
+        .try_send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1))
+        .unwrap();
+
+    let mut count = 0;
+    while thr.dequeue_signal(&!SignalSet::default()).is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 5);
+}
+
+#[test]
+fn fifo_order() {
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    let signo = |sig: Option<SignalInfo>| sig.map(|sig| sig.signo());
+    let all = !SignalSet::default();
+
+    // Lowest-numbered first by default.
+    assert!(!env.proc.fifo_order());
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1)));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 1)));
+    assert_eq!(signo(thr.dequeue_signal(&all)), Some(Signo::SIGHUP));
+    assert_eq!(signo(thr.dequeue_signal(&all)), Some(Signo::SIGTERM));
+
+    env.proc.set_fifo_order(true);
+    assert!(env.proc.fifo_order());
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 2)));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 3)));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGINT, 0, 4)));
+    // The mask is still honoured.
+    let mut mask = !SignalSet::default();
+    mask.remove(Signo::SIGTERM);
+    let sig = thr.dequeue_signal(&mask);
+    assert_eq!(signo(sig.clone()), Some(Signo::SIGHUP));
+    assert_eq!(sig.unwrap().pid(), 3);
+    let sig = thr.dequeue_signal(&all).unwrap();
+    assert_eq!((sig.signo(), sig.pid()), (Signo::SIGTERM, 2));
+    assert_eq!(signo(thr.dequeue_signal(&all)), Some(Signo::SIGINT));
+
+    // Threads created later get the order too.
+    let other = ThreadSignalManager::new(10, env.proc.clone());
+    assert!(other.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1)));
+    assert!(other.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1)));
+    assert_eq!(signo(other.dequeue_signal(&all)), Some(Signo::SIGUSR2));
+}
+
+#[test]
+fn pressure_hook() {
+    #[derive(Default)]
+    struct Hook(Mutex<Vec<(Option<u32>, Signo, SignalPressure)>>);
+    impl SignalPressureHook for Hook {
+        fn on_pressure(&self, tid: Option<u32>, sig: &SignalInfo, pressure: SignalPressure) {
+            self.0.lock().unwrap().push((tid, sig.signo(), pressure));
+        }
+    }
+
+    let env = TestEnv::new();
+    let thr = ThreadSignalManager::new(9, env.proc.clone());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    env.proc.actions().lock()[Signo::SIGRTMIN].disposition =
+        SignalDisposition::Handler(test_handler);
+    let hook = Arc::new(Hook::default());
+    env.proc.set_pressure_hook(
+        Some(hook.clone()),
+        PressureThresholds {
+            total: 4,
+            rt_depth: 2,
+        },
+    );
+
+    let rt = SignalInfo::new_user(Signo::SIGRTMIN, 0, 1);
+    let _ = env.proc.send_signal(rt.clone());
+    let _ = env.proc.send_signal(rt.clone());
+    assert_eq!(
+        *hook.0.lock().unwrap(),
+        [(None, Signo::SIGRTMIN, SignalPressure::RtDepth(2))]
+    );
+    // Only crossing the threshold counts.
+    let _ = env.proc.send_signal(rt.clone());
+    assert_eq!(hook.0.lock().unwrap().len(), 1);
+
+    // Signals queued to threads count towards the total.
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1)));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1)));
+    assert_eq!(
+        hook.0.lock().unwrap()[1..],
+        [(Some(9), Signo::SIGUSR1, SignalPressure::Total(4))]
+    );
+    // Merged signals don't count.
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1)));
+    assert_eq!(hook.0.lock().unwrap().len(), 2);
+
+    // Crossing again once below the threshold is reported again.
+    while thr.dequeue_signal(&!SignalSet::default()).is_some() {}
+    let _ = env
+        .proc
+        .send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 1));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1)));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1)));
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1)));
+    assert_eq!(
+        hook.0.lock().unwrap()[2..],
+        [(Some(9), Signo::SIGTERM, SignalPressure::Total(4))]
+    );
+}
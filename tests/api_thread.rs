@@ -1,5 +1,28 @@
+use std::{
+    future::poll_fn,
+    pin::pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    task::{Context, Poll, Wake, Waker},
+    time::Duration,
+};
+
 use axcpu::uspace::UserContext;
-use starry_signal::{SignalDisposition, SignalInfo, SignalOSAction, SignalSet, Signo};
+use linux_raw_sys::general::{SS_AUTODISARM, siginfo_t};
+use starry_signal::{
+    EINTR, RestartKind, SignalActionFlags, SignalDisposition, SignalError, SignalInfo,
+    SignalOSAction, SignalSet, SignalStack, Signo, SyscallRestart,
+    api::{
+        ActionsInheritance, FilterDecision, MonotonicClock, PipeAction, SIGNAL_FRAME_ALIGN,
+        SIGNAL_FRAME_MAX_USAGE, SIGNAL_FRAME_SIGINFO_OFFSET, SIGNAL_FRAME_SIZE,
+        SIGNAL_FRAME_UCONTEXT_OFFSET, SignalEventListener, SignalFilter, SignalStackUsage,
+        SignalTracer, SignalWake, SpawnSignalAttrs, StopEvent, StopKind, ThreadSignalManager,
+        TraceDecision, UserStackHook, WaitOptions, XfszAction,
+    },
+    arch::{SYSCALL_INSN_LEN, SYSNO_RESTART_SYSCALL, UContext},
+};
 
 mod common;
 use common::*;
@@ -12,7 +35,7 @@ fn dequeue_signal() {
     assert!(thr.send_signal(sig1));
 
     let sig2 = SignalInfo::new_user(Signo::SIGTERM, 9, 9);
-    assert_eq!(proc.send_signal(sig2), Some(TID));
+    assert_eq!(proc.send_signal(sig2), SignalWake::All(vec![TID]));
 
     let mask = !SignalSet::default();
     assert_eq!(thr.dequeue_signal(&mask).unwrap().signo(), Signo::SIGINT);
@@ -28,13 +51,13 @@ fn handle_signal() {
     let sig = SignalInfo::new_user(signo, 9, 9);
 
     unsafe extern "C" fn test_handler(_: i32) {}
-    proc.actions.lock()[signo].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[signo].disposition = SignalDisposition::Handler(test_handler);
 
     let initial = UserContext::new(0, initial_sp().into(), 0);
 
     let mut uctx = initial;
     let restore_blocked = thr.blocked();
-    let action = proc.actions.lock()[signo].clone();
+    let action = proc.actions().lock()[signo].clone();
     let result = thr.handle_signal(&mut uctx, restore_blocked, &sig, &action);
 
     assert_eq!(result, Some(SignalOSAction::Handler));
@@ -55,7 +78,7 @@ fn block_ignore_send_signal() {
         sig.signo()
     );
 
-    proc.actions.lock()[signo].disposition = SignalDisposition::Ignore;
+    proc.actions().lock()[signo].disposition = SignalDisposition::Ignore;
     assert!(!thr.send_signal(sig.clone()));
     assert!(!thr.pending().has(signo));
 
@@ -66,7 +89,7 @@ fn block_ignore_send_signal() {
     assert!(!thr.send_signal(sig.clone()));
     assert!(!thr.pending().has(signo));
 
-    proc.actions.lock()[signo].disposition = SignalDisposition::Default;
+    proc.actions().lock()[signo].disposition = SignalDisposition::Default;
     assert!(!thr.send_signal(sig.clone()));
     assert!(thr.pending().has(signo));
 
@@ -84,7 +107,7 @@ fn check_signals() {
     let signo = Signo::SIGTERM;
     let sig = SignalInfo::new_user(signo, 0, 1);
 
-    assert_eq!(proc.send_signal(sig.clone()), Some(TID));
+    assert_eq!(proc.send_signal(sig.clone()), SignalWake::All(vec![TID]));
     let (si, _os_action) = thr.check_signals(&mut uctx, None).unwrap();
     assert_eq!(si.signo(), signo);
 
@@ -101,19 +124,1329 @@ fn restore() {
     let sig = SignalInfo::new_user(signo, 0, 1);
 
     unsafe extern "C" fn test_handler(_: i32) {}
-    proc.actions.lock()[signo].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[signo].disposition = SignalDisposition::Handler(test_handler);
 
     let initial = UserContext::new(0x219, initial_sp().into(), 0);
 
     let mut uctx = initial;
     let restore_blocked = thr.blocked();
-    let action = proc.actions.lock()[sig.signo()].clone();
+    let action = proc.actions().lock()[sig.signo()].clone();
     thr.handle_signal(&mut uctx, restore_blocked, &sig, &action);
 
     let new_sp = uctx.sp() + 8;
     uctx.set_sp(new_sp);
-    thr.restore(&mut uctx);
+    thr.restore(&mut uctx).unwrap();
 
     assert_eq!(uctx.ip(), initial.ip());
     assert_eq!(uctx.sp(), initial.sp());
 }
+
+#[test]
+fn handle_signal_on_stack() {
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGUSR1].flags = SignalActionFlags::ONSTACK;
+    let stack = SignalStack::new(initial_sp() - 0x10000, 0x8000);
+    thr.set_stack(stack.clone());
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 9, 9);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let outer = uctx.sp();
+    assert!(stack.contains(outer));
+
+    // A nested handler continues below the interrupted one.
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    assert!(stack.contains(uctx.sp()));
+    assert!(uctx.sp() < outer);
+}
+
+#[test]
+fn handle_signal_autodisarm() {
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGUSR1].flags = SignalActionFlags::ONSTACK;
+    let stack = SignalStack {
+        flags: SS_AUTODISARM,
+        ..SignalStack::new(initial_sp() - 0x10000, 0x8000)
+    };
+    thr.set_stack(stack.clone());
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 9, 9);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    assert!(stack.contains(uctx.sp()));
+    // Disarmed while the handler runs, so a nested one stays on its stack.
+    assert!(thr.stack().disabled());
+
+    let new_sp = uctx.sp() + 8;
+    uctx.set_sp(new_sp);
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(thr.stack(), stack);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn ucontext_flags() {
+    use starry_signal::arch::{UC_FP_XSTATE, UC_SIGCONTEXT_SS};
+
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 9, 9);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+
+    let ucontext = unsafe { &*(uctx.arg2() as *const UContext) };
+    assert_ne!(ucontext.flags & UC_SIGCONTEXT_SS, 0);
+    assert_eq!(ucontext.flags & UC_FP_XSTATE, 0);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn fault_mcontext() {
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGSEGV].disposition = SignalDisposition::Handler(test_handler);
+    let action = proc.actions().lock()[Signo::SIGSEGV].clone();
+
+    let mut sig = SignalInfo::new_kernel(Signo::SIGSEGV);
+    // SEGV_MAPERR
+    sig.set_code(1);
+    sig.set_addr(0xdead_0000);
+    let mut uctx = UserContext::new(0, (initial_sp() - 0xb0000).into(), 0);
+    uctx.error_code = 6;
+    uctx.vector = 14;
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+
+    let ucontext = unsafe { &*(uctx.arg2() as *const UContext) };
+    assert_eq!(ucontext.mcontext.err(), 6);
+    assert_eq!(ucontext.mcontext.trapno(), 14);
+    assert_eq!(ucontext.mcontext.fault_address(), 0xdead_0000);
+
+    // A user-sent SIGSEGV has no faulting address.
+    let sig = SignalInfo::new_user(Signo::SIGSEGV, 0, 1);
+    let mut uctx = UserContext::new(0, (initial_sp() - 0xb0000).into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let ucontext = unsafe { &*(uctx.arg2() as *const UContext) };
+    assert_eq!(ucontext.mcontext.fault_address(), 0);
+}
+
+#[test]
+fn mcontext_accessors() {
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 9, 9);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0x1000, initial_sp().into(), 42);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+
+    let ucontext = unsafe { &mut *(uctx.arg2() as *mut UContext) };
+    assert_eq!(ucontext.mcontext.pc(), 0x1000);
+    assert_eq!(ucontext.mcontext.sp(), initial_sp());
+    assert_eq!(ucontext.mcontext.arg(0), 42);
+
+    // The handler redirects the interrupted code.
+    ucontext.mcontext.set_pc(0x2000);
+    ucontext.mcontext.set_retval(7);
+
+    let new_sp = uctx.sp() + if cfg!(target_arch = "x86_64") { 8 } else { 0 };
+    uctx.set_sp(new_sp);
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(uctx.ip(), 0x2000);
+    assert_eq!(uctx.retval(), 7);
+    assert_eq!(uctx.sp(), initial_sp());
+}
+
+#[test]
+fn restore_checks_magic() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    proc.set_frame_cookie(0x1234);
+    let sp = initial_sp() - 0x30000;
+
+    // Not a frame.
+    let mut uctx = UserContext::new(0, (sp - 0x1000).into(), 0);
+    assert_eq!(thr.restore(&mut uctx), Err(SignalError::BadAddress));
+    assert_eq!(uctx.sp(), sp - 0x1000);
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    #[cfg(target_arch = "x86_64")]
+    {
+        let new_sp = uctx.sp() + 8;
+        uctx.set_sp(new_sp);
+    }
+
+    // The cookie changed, e.g. by `execve`.
+    proc.set_frame_cookie(0x5678);
+    let mut forged = uctx;
+    assert_eq!(thr.restore(&mut forged), Err(SignalError::BadAddress));
+
+    proc.set_frame_cookie(0x1234);
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(uctx.sp(), sp);
+
+    let child = proc.new_child(ActionsInheritance::Copy);
+    assert_eq!(child.frame_cookie(), 0x1234);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn restore_sanitizes_rflags() {
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 9, 9);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+
+    // The handler sets IOPL 3 and CF in the saved `eflags`.
+    let ucontext = unsafe { &mut *(uctx.arg2() as *mut UContext) };
+    let eflags = unsafe { &mut *(&raw mut ucontext.mcontext as *mut usize).add(17) };
+    *eflags |= 0x3001;
+
+    let new_sp = uctx.sp() + 8;
+    uctx.set_sp(new_sp);
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(uctx.rflags & 0x3000, 0);
+    assert_eq!(uctx.rflags & 1, 1);
+}
+
+#[test]
+fn on_exec() {
+    let (proc, thr) = new_test_env();
+
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGINT);
+    thr.set_blocked(blocked);
+    thr.set_stack(SignalStack {
+        sp: 0x1000,
+        flags: 0,
+        size: 0x1000,
+    });
+
+    thr.on_exec(0x2000);
+
+    assert!(thr.stack().disabled());
+    assert!(thr.signal_blocked(Signo::SIGINT));
+    assert_eq!(proc.default_restorer(), 0x2000);
+}
+
+#[test]
+fn export_import_state() {
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Ignore;
+    proc.actions().lock()[Signo::SIGRTMIN].disposition = SignalDisposition::Handler(test_handler);
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGRTMIN, 0, 2));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGRTMIN, 0, 3));
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGUSR1);
+    thr.set_blocked(blocked);
+    assert!(proc.stop(Signo::SIGTSTP));
+    thr.enter_group_stop();
+
+    let proc_state = proc.export_state();
+    let thr_state = thr.export_state();
+    assert_eq!(proc_state.pending.len(), 1);
+    assert_eq!(thr_state.pending.len(), 2);
+
+    // A standard signal can't be pending twice; nothing is imported then.
+    let (proc2, thr2) = new_test_env();
+    let mut bad = proc_state.clone();
+    bad.pending.push(bad.pending[0].clone());
+    assert_eq!(proc2.import_state(bad), Err(SignalError::InvalidArgument));
+    assert!(!proc2.is_stopped());
+
+    // Queued signals are restored over the limit.
+    proc2.set_rt_queue_limit(1);
+    proc2.import_state(proc_state).unwrap();
+    thr2.import_state(thr_state).unwrap();
+
+    assert!(proc2.signal_ignored(Signo::SIGINT));
+    assert!(thr2.signal_blocked(Signo::SIGUSR1));
+    assert_eq!(proc2.stop_signal(), Some(Signo::SIGTSTP));
+    assert_eq!(
+        proc2.take_event(WaitOptions::STOPPED),
+        Some(StopEvent::Stopped(Signo::SIGTSTP))
+    );
+    assert_eq!(thr2.stop_kind(), Some(StopKind::Group));
+    let mask = !SignalSet::default();
+    assert_eq!(thr2.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRTMIN);
+    assert_eq!(thr2.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRTMIN);
+    assert_eq!(thr2.dequeue_signal(&mask).unwrap().signo(), Signo::SIGTERM);
+    assert!(thr2.dequeue_signal(&mask).is_none());
+}
+
+#[test]
+fn status() {
+    let (proc, thr) = new_test_env();
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Ignore;
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 1)));
+    assert_eq!(
+        proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1)),
+        SignalWake::All(vec![TID])
+    );
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGQUIT);
+    thr.set_blocked(blocked);
+
+    let status = thr.status();
+    assert_eq!(status.thread_pending.bits(), 1 << 0);
+    assert_eq!(status.shared_pending.bits(), 1 << 14);
+    assert_eq!(status.blocked.bits(), 1 << 2);
+    assert!(status.ignored.has(Signo::SIGINT));
+    assert_eq!(status.caught.bits(), 1 << 9);
+
+    let text = status.to_string();
+    assert!(text.starts_with("SigPnd:\t0000000000000001\nShdPnd:\t0000000000004000\n"));
+    assert!(text.ends_with("SigCgt:\t0000000000000200\n"));
+}
+
+#[test]
+fn fault_routing() {
+    let (proc, thr) = new_test_env();
+    let fault = || {
+        let mut sig = SignalInfo::new_kernel(Signo::SIGSEGV);
+        sig.set_code(1);
+        sig.set_addr(0xdead);
+        sig
+    };
+
+    // Never placed on the shared queue.
+    assert_eq!(proc.send_signal(fault()), SignalWake::None);
+    assert_eq!(proc.send_signals(&[fault()]), SignalWake::None);
+    assert!(proc.pending().is_empty());
+
+    // Sent by the kernel with `SI_KERNEL`, so not a fault.
+    assert!(!SignalInfo::new_kernel(Signo::SIGSEGV).is_fault());
+    let _ = proc.send_signal(SignalInfo::new_kernel(Signo::SIGSEGV));
+    assert!(proc.pending().has(Signo::SIGSEGV));
+    let _ = proc.send_signals(&[SignalInfo::new_kernel(Signo::SIGTRAP)]);
+    assert!(proc.pending().has(Signo::SIGTRAP));
+    assert_eq!(
+        thr.send_fault(SignalInfo::new_kernel(Signo::SIGSEGV)),
+        Err(SignalError::InvalidArgument)
+    );
+    proc.remove_signal(Signo::SIGSEGV);
+    proc.remove_signal(Signo::SIGTRAP);
+
+    assert_eq!(
+        thr.send_fault(SignalInfo::new_user(Signo::SIGSEGV, 0, 1)),
+        Err(SignalError::InvalidArgument)
+    );
+
+    proc.actions().lock()[Signo::SIGSEGV].disposition = SignalDisposition::Ignore;
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGSEGV);
+    thr.set_blocked(blocked);
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGSEGV, 0, 1));
+    assert_eq!(thr.send_fault(fault()), Ok(true));
+    assert!(!thr.signal_blocked(Signo::SIGSEGV));
+    assert!(!proc.signal_ignored(Signo::SIGSEGV));
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGSEGV, 0, 1));
+
+    // The fault is taken from the thread, with its info.
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    let (sig, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.addr(), 0xdead);
+    assert_eq!(action, SignalOSAction::CoreDump);
+    assert!(proc.pending().has(Signo::SIGSEGV));
+}
+
+#[test]
+fn poll_signal() {
+    let (_proc, thr) = new_test_env();
+    let mut mask = SignalSet::default();
+    mask.add(Signo::SIGUSR1);
+    mask.add(Signo::SIGKILL);
+    assert!(thr.poll_signal(&mask).is_none());
+
+    thr.set_blocked(mask);
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGKILL));
+    assert_eq!(thr.poll_signal(&mask).unwrap().signo(), Signo::SIGUSR1);
+    assert!(thr.poll_signal(&mask).is_none());
+    assert!(thr.pending().has(Signo::SIGUSR2));
+    assert!(thr.pending().has(Signo::SIGKILL));
+}
+
+#[test]
+fn snapshot() {
+    let (proc, thr) = new_test_env();
+    proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Ignore;
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 1)));
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGQUIT);
+    thr.set_blocked(blocked);
+
+    let snapshot = thr.snapshot();
+    assert!(snapshot.thread_pending.has(Signo::SIGHUP));
+    assert!(snapshot.shared_pending.has(Signo::SIGTERM));
+    assert_eq!(snapshot.pending(), thr.pending());
+    assert_eq!(snapshot.blocked, blocked);
+    assert!(snapshot.dispositions().0.has(Signo::SIGINT));
+
+    // Later changes don't show through.
+    proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Default;
+    assert!(matches!(
+        snapshot.actions[Signo::SIGINT].disposition,
+        SignalDisposition::Ignore
+    ));
+    assert!(snapshot.status().ignored.has(Signo::SIGINT));
+    assert!(!thr.status().ignored.has(Signo::SIGINT));
+}
+
+#[test]
+fn event_listener() {
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl SignalEventListener for Recorder {
+        fn on_send(&self, tid: Option<u32>, sig: &SignalInfo) {
+            let signo = sig.signo();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("send {tid:?} {signo:?}"));
+        }
+
+        fn on_deliver(&self, tid: u32, sig: &SignalInfo, action: SignalOSAction) {
+            let signo = sig.signo();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("deliver {tid} {signo:?} {action:?}"));
+        }
+
+        fn on_ignore(&self, tid: Option<u32>, sig: &SignalInfo) {
+            let signo = sig.signo();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("ignore {tid:?} {signo:?}"));
+        }
+
+        fn on_coredump(&self, tid: u32, sig: &SignalInfo) {
+            let signo = sig.signo();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("coredump {tid} {signo:?}"));
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    let recorder = Arc::new(Recorder::default());
+    proc.set_listener(Some(recorder.clone()));
+
+    proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Ignore;
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGINT, 0, 1));
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGQUIT, 0, 1));
+    // Already pending, so not queued again.
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGQUIT, 0, 1));
+
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    let _ = thr.check_signals(&mut uctx, None);
+    let _ = thr.check_signals(&mut uctx, None);
+
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            "ignore None SIGINT",
+            "send Some(7) SIGTERM",
+            "send Some(7) SIGQUIT",
+            "coredump 7 SIGQUIT",
+            "deliver 7 SIGTERM Terminate",
+        ]
+    );
+}
+
+#[test]
+fn tracer() {
+    struct Tracer;
+
+    impl SignalTracer for Tracer {
+        fn on_signal(&self, _tid: u32, sig: &SignalInfo) -> TraceDecision {
+            match sig.signo() {
+                Signo::SIGINT => TraceDecision::Stop,
+                Signo::SIGHUP => TraceDecision::Suppress,
+                Signo::SIGQUIT => TraceDecision::Replace(SignalInfo::new_kernel(Signo::SIGTERM)),
+                _ => TraceDecision::Deliver,
+            }
+        }
+    }
+
+    let (_proc, thr) = new_test_env();
+    thr.set_tracer(Some(Arc::new(Tracer)));
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGINT)));
+    let (sig, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGINT);
+    assert_eq!(action, SignalOSAction::TraceStop);
+    assert_eq!(thr.last_siginfo().unwrap().signo(), Signo::SIGINT);
+    assert!(thr.set_last_siginfo(SignalInfo::new_user(Signo::SIGUSR1, 0, 42)));
+    let sig = thr.take_last_siginfo().unwrap();
+    assert_eq!(sig.signo(), Signo::SIGUSR1);
+    assert_eq!(sig.pid(), 42);
+    assert!(thr.last_siginfo().is_none());
+    assert!(!thr.set_last_siginfo(SignalInfo::new_kernel(Signo::SIGUSR1)));
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGHUP)));
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    assert!(!thr.pending().has(Signo::SIGHUP));
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGQUIT)));
+    let (sig, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGTERM);
+    assert_eq!(action, SignalOSAction::Terminate);
+}
+
+#[test]
+fn group_and_trace_stop() {
+    struct Tracer;
+    impl SignalTracer for Tracer {
+        fn on_signal(&self, _tid: u32, _sig: &SignalInfo) -> TraceDecision {
+            TraceDecision::Stop
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    let other = ThreadSignalManager::new(TID + 1, proc.clone());
+    thr.set_tracer(Some(Arc::new(Tracer)));
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGINT)));
+    let (_, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(action, SignalOSAction::TraceStop);
+    assert_eq!(thr.stop_kind(), Some(StopKind::Trace));
+
+    assert!(proc.stop(Signo::SIGSTOP));
+    thr.enter_group_stop();
+    other.enter_group_stop();
+    assert_eq!(thr.stop_kind(), Some(StopKind::Trace));
+    assert_eq!(other.stop_kind(), Some(StopKind::Group));
+    assert!(!other.trace_cont());
+
+    // SIGCONT only resumes the threads in group-stop.
+    assert_eq!(proc.cont_threads(), Some(vec![TID + 1]));
+    assert_eq!(proc.cont_threads(), None);
+    assert_eq!(proc.stopped_threads(), [(TID, StopKind::Trace)]);
+    assert!(thr.trace_cont());
+    assert!(thr.last_siginfo().is_none());
+    assert_eq!(thr.stop_kind(), None);
+    assert!(proc.stopped_threads().is_empty());
+
+    assert!(proc.stop(Signo::SIGSTOP));
+    other.enter_group_stop();
+    other.exit_signals();
+    assert!(proc.stopped_threads().is_empty());
+}
+
+#[test]
+fn security_filter() {
+    struct Filter;
+    impl SignalFilter for Filter {
+        fn filter(&self, _tid: u32, sig: &SignalInfo) -> FilterDecision {
+            match sig.signo() {
+                Signo::SIGUSR1 => FilterDecision::Drop,
+                Signo::SIGUSR2 => FilterDecision::Rewrite(SignalInfo::new_kernel(Signo::SIGSYS)),
+                Signo::SIGTERM => FilterDecision::Rewrite(SignalInfo::new_kernel(Signo::SIGHUP)),
+                _ => FilterDecision::Allow,
+            }
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    proc.set_filter(Some(Arc::new(Filter)));
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR1)));
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    assert!(thr.pending().is_empty());
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR2)));
+    let (sig, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGSYS);
+    assert_eq!(action, SignalOSAction::CoreDump);
+
+    // A blocked replacement is queued instead.
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGHUP);
+    thr.set_blocked(blocked);
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM)));
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    assert!(thr.pending().has(Signo::SIGHUP));
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGINT)));
+    let (sig, _) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGINT);
+}
+
+#[test]
+fn spawn_attrs() {
+    let (proc, _thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGTERM].disposition = SignalDisposition::Ignore;
+    proc.actions().lock()[Signo::SIGCHLD].disposition = SignalDisposition::Handler(test_handler);
+
+    let child = Arc::new(proc.new_child(ActionsInheritance::CopyOnWrite));
+    let thr = ThreadSignalManager::new(TID + 1, child.clone());
+    let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGCHLD));
+
+    let mut sigdefault = SignalSet::default();
+    sigdefault.add(Signo::SIGTERM);
+    sigdefault.add(Signo::SIGCHLD);
+    let mut sigmask = SignalSet::default();
+    sigmask.add(Signo::SIGUSR1);
+    let mut attrs = SpawnSignalAttrs {
+        sigdefault: Some(sigdefault),
+        sigmask: Some(sigmask),
+    };
+    thr.apply_spawn_attrs(&attrs).unwrap();
+    assert!(!child.signal_ignored(Signo::SIGTERM));
+    assert!(child.signal_ignored(Signo::SIGCHLD));
+    assert!(!thr.pending().has(Signo::SIGCHLD));
+    assert!(
+        child.actions().lock()[Signo::SIGINT]
+            .disposition
+            .is_handler()
+    );
+    assert_eq!(thr.blocked(), sigmask);
+    // The parent keeps its table.
+    assert!(proc.signal_ignored(Signo::SIGTERM));
+
+    sigdefault.add(Signo::SIGKILL);
+    attrs.sigdefault = Some(sigdefault);
+    attrs.sigmask = Some(SignalSet::default());
+    assert_eq!(
+        thr.apply_spawn_attrs(&attrs),
+        Err(SignalError::InvalidArgument)
+    );
+    assert_eq!(thr.blocked(), sigmask);
+
+    // A shared table is unshared before being reset, even if the OS holds
+    // another reference to it.
+    let shared = Arc::new(proc.new_child(ActionsInheritance::Share));
+    let _held = shared.actions();
+    let thr = ThreadSignalManager::new(TID + 2, shared.clone());
+    let mut sigdefault = SignalSet::default();
+    sigdefault.add(Signo::SIGTERM);
+    attrs.sigdefault = Some(sigdefault);
+    thr.apply_spawn_attrs(&attrs).unwrap();
+    assert!(!shared.signal_ignored(Signo::SIGTERM));
+    assert!(proc.signal_ignored(Signo::SIGTERM));
+}
+
+#[test]
+fn inject_signal() {
+    struct Tracer;
+
+    impl SignalTracer for Tracer {
+        fn on_signal(&self, _tid: u32, _sig: &SignalInfo) -> TraceDecision {
+            TraceDecision::Stop
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    thr.set_tracer(Some(Arc::new(Tracer)));
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    proc.actions().lock()[Signo::SIGTERM].disposition = SignalDisposition::Ignore;
+    assert!(!thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM)));
+    assert!(!thr.pending().has(Signo::SIGTERM));
+    proc.actions().lock()[Signo::SIGTERM].disposition = SignalDisposition::Default;
+
+    thr.inject_signal(SignalInfo::new_kernel(Signo::SIGTERM));
+    let (sig, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGTERM);
+    assert_eq!(action, SignalOSAction::Terminate);
+
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGTERM);
+    thr.set_blocked(set);
+    thr.inject_signal(SignalInfo::new_kernel(Signo::SIGTERM));
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    assert!(thr.pending().has(Signo::SIGTERM));
+}
+
+#[test]
+fn send_xfsz() {
+    let (proc, thr) = new_test_env();
+
+    assert_eq!(thr.send_xfsz(), XfszAction::Deliver);
+    assert!(thr.pending().has(Signo::SIGXFSZ));
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    let (_, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(action, SignalOSAction::CoreDump);
+
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGXFSZ);
+    thr.set_blocked(set);
+    assert_eq!(thr.send_xfsz(), XfszAction::ReturnEfbig);
+    assert!(thr.pending().has(Signo::SIGXFSZ));
+
+    thr.set_blocked(SignalSet::default());
+    assert!(thr.check_signals(&mut uctx, None).is_some());
+    proc.actions().lock()[Signo::SIGXFSZ].disposition = SignalDisposition::Ignore;
+    assert_eq!(thr.send_xfsz(), XfszAction::ReturnEfbig);
+    assert!(!thr.pending().has(Signo::SIGXFSZ));
+}
+
+#[test]
+fn send_sigpipe() {
+    let (proc, thr) = new_test_env();
+
+    assert_eq!(thr.send_sigpipe(true), PipeAction::ReturnEpipe);
+    assert!(!thr.pending().has(Signo::SIGPIPE));
+
+    assert_eq!(thr.send_sigpipe(false), PipeAction::Fatal);
+    assert!(thr.pending().has(Signo::SIGPIPE));
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    let (_, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(action, SignalOSAction::Terminate);
+
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGPIPE);
+    thr.set_blocked(set);
+    assert_eq!(thr.send_sigpipe(false), PipeAction::ReturnEpipe);
+    assert!(thr.pending().has(Signo::SIGPIPE));
+    thr.set_blocked(SignalSet::default());
+    assert!(thr.check_signals(&mut uctx, None).is_some());
+
+    unsafe extern "C" fn handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGPIPE].disposition = SignalDisposition::Handler(handler);
+    assert_eq!(thr.send_sigpipe(false), PipeAction::Deliver);
+    assert!(thr.check_signals(&mut uctx, None).is_some());
+
+    proc.actions().lock()[Signo::SIGPIPE].disposition = SignalDisposition::Ignore;
+    assert_eq!(thr.send_sigpipe(false), PipeAction::ReturnEpipe);
+    assert!(!thr.pending().has(Signo::SIGPIPE));
+}
+
+#[test]
+fn freeze_point() {
+    let (proc, thr) = new_test_env();
+    let mut cx = Context::from_waker(Waker::noop());
+
+    assert!(pin!(thr.freeze_point()).poll(&mut cx).is_ready());
+
+    proc.freeze();
+    assert!(proc.is_frozen());
+    let mut fut = pin!(thr.freeze_point());
+    assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+    assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+
+    proc.thaw();
+    assert!(!proc.is_frozen());
+    assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(()));
+}
+
+#[test]
+fn wait_signal() {
+    let (proc, thr) = new_test_env();
+    let mut cx = Context::from_waker(Waker::noop());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR2].disposition = SignalDisposition::Handler(test_handler);
+
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGUSR1);
+    set.add(Signo::SIGUSR2);
+    thr.set_blocked(set);
+    let mut fut = pin!(thr.wait_signal(set));
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+    let Poll::Ready(Ok(sig)) = fut.as_mut().poll(&mut cx) else {
+        panic!("signal not taken");
+    };
+    assert_eq!(sig.signo(), Signo::SIGUSR2);
+    assert!(thr.pending().is_empty());
+
+    // An unblocked signal outside the set interrupts the wait.
+    let mut fut = pin!(thr.wait_signal(set));
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    assert!(matches!(
+        fut.as_mut().poll(&mut cx),
+        Poll::Ready(Err(SignalError::Interrupted))
+    ));
+}
+
+struct TestClock(AtomicU64);
+
+impl MonotonicClock for TestClock {
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.0.load(Ordering::Relaxed))
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> impl Future<Output = ()> {
+        poll_fn(move |_| {
+            if self.now() >= deadline {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+}
+
+#[test]
+fn dequeue_signal_until() {
+    let (proc, thr) = new_test_env();
+    let mut cx = Context::from_waker(Waker::noop());
+    let clock = TestClock(AtomicU64::new(100));
+    let deadline = Duration::from_millis(150);
+
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGUSR1);
+    thr.set_blocked(set);
+    let mut fut = pin!(thr.dequeue_signal_until(set, deadline, &clock));
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    clock.0.store(150, Ordering::Relaxed);
+    assert!(matches!(
+        fut.as_mut().poll(&mut cx),
+        Poll::Ready(Err(SignalError::WouldBlock))
+    ));
+
+    // A deadline passed only polls.
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    let mut fut = pin!(thr.dequeue_signal_until(set, deadline, &clock));
+    let Poll::Ready(Ok(sig)) = fut.as_mut().poll(&mut cx) else {
+        panic!("signal not taken");
+    };
+    assert_eq!(sig.signo(), Signo::SIGUSR1);
+    let mut fut = pin!(thr.dequeue_signal_until(set, deadline, &clock));
+    assert!(matches!(
+        fut.as_mut().poll(&mut cx),
+        Poll::Ready(Err(SignalError::WouldBlock))
+    ));
+
+    let deadline = Duration::from_millis(200);
+    let mut fut = pin!(thr.dequeue_signal_until(set, deadline, &clock));
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    assert!(matches!(fut.as_mut().poll(&mut cx), Poll::Ready(Ok(_))));
+}
+
+#[test]
+fn notifications() {
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    let flag = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // A notification wakes up and interrupts a wait for signals.
+    let mut fut = pin!(thr.wait_signal(SignalSet::default()));
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    assert_eq!(thr.notify(3), Ok(true));
+    assert!(flag.0.load(Ordering::Relaxed));
+    assert!(matches!(
+        fut.as_mut().poll(&mut cx),
+        Poll::Ready(Err(SignalError::Interrupted))
+    ));
+
+    assert_eq!(thr.notify(3), Ok(false));
+    assert_eq!(proc.notify_all(0), Ok(()));
+    assert_eq!(thr.notify(64), Err(SignalError::InvalidArgument));
+    assert_eq!(proc.notify_all(64), Err(SignalError::InvalidArgument));
+    assert_eq!(thr.notifications(), 0b1001);
+    assert!(thr.pending().is_empty());
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+
+    assert_eq!(thr.take_notifications(), 0b1001);
+    assert_eq!(thr.take_notifications(), 0);
+}
+
+#[test]
+fn delivery_gate() {
+    let (_proc, thr) = new_test_env();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    let outer = thr.delivery_gate();
+    let inner = thr.delivery_gate();
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM)));
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    drop(inner);
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    assert!(thr.pending().has(Signo::SIGTERM));
+
+    drop(outer);
+    let (sig, _) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGTERM);
+}
+
+#[test]
+fn check_signals_filtered() {
+    let (_proc, thr) = new_test_env();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGINT)));
+    let filter = SignalSet::synchronous();
+    assert!(
+        thr.check_signals_filtered(&mut uctx, None, &filter)
+            .is_none()
+    );
+    assert!(thr.pending().has(Signo::SIGINT));
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGSEGV)));
+    let (sig, _) = thr
+        .check_signals_filtered(&mut uctx, None, &filter)
+        .unwrap();
+    assert_eq!(sig.signo(), Signo::SIGSEGV);
+    assert!(
+        thr.check_signals_filtered(&mut uctx, None, &filter)
+            .is_none()
+    );
+
+    let (sig, _) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGINT);
+}
+
+#[test]
+fn check_signals_batch() {
+    let (proc, thr) = new_test_env();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGUSR2].disposition = SignalDisposition::Handler(test_handler);
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR1)));
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR2)));
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM)));
+
+    let handled = thr.check_signals_batch(&mut uctx, None, 2);
+    assert_eq!(handled.len(), 2);
+    assert_eq!(handled[0].0.signo(), Signo::SIGUSR1);
+    assert_eq!(handled[1].0.signo(), Signo::SIGUSR2);
+    assert!(
+        handled
+            .iter()
+            .all(|(_, action)| *action == SignalOSAction::Handler)
+    );
+    assert!(thr.signal_blocked(Signo::SIGUSR1));
+    assert!(thr.signal_blocked(Signo::SIGUSR2));
+
+    // The second frame returns to the first handler with its mask.
+    let new_sp = uctx.sp() + 8;
+    uctx.set_sp(new_sp);
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(uctx.ip(), test_handler as *const () as usize);
+    assert!(uctx.sp() > new_sp);
+    assert!(thr.signal_blocked(Signo::SIGUSR1));
+    assert!(!thr.signal_blocked(Signo::SIGUSR2));
+
+    let handled = thr.check_signals_batch(&mut uctx, None, 8);
+    assert_eq!(handled.len(), 1);
+    assert_eq!(handled[0].1, SignalOSAction::Terminate);
+}
+
+#[test]
+fn syscall_restart() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+
+    let ip = 0x1000 + SYSCALL_INSN_LEN;
+    let interrupted = |kind| {
+        let mut uctx = UserContext::new(ip, initial_sp().into(), 0);
+        uctx.set_retval(-RestartKind::errno(&kind) as usize);
+        thr.set_syscall_restart(SyscallRestart {
+            kind,
+            sysno: 7,
+            arg0: 42,
+        });
+        uctx
+    };
+
+    // No handler runs: restarted transparently.
+    let mut uctx = interrupted(RestartKind::NoHand);
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    assert_eq!(uctx.ip(), 0x1000);
+    assert_eq!(uctx.sysno(), 7);
+    assert_eq!(uctx.arg0(), 42);
+
+    let mut uctx = interrupted(RestartKind::RestartBlock);
+    assert!(thr.check_signals(&mut uctx, None).is_none());
+    assert_eq!(uctx.sysno(), SYSNO_RESTART_SYSCALL);
+    thr.set_restart_block(Box::new(|| 5));
+    assert_eq!(thr.take_restart_block().unwrap()(), 5);
+    assert!(thr.take_restart_block().is_none());
+
+    // A handler without `SA_RESTART` runs: fails with `EINTR`.
+    let mut uctx = interrupted(RestartKind::Sys);
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR1)));
+    let (_, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(action, SignalOSAction::Handler);
+    let new_sp = uctx.sp() + 8;
+    uctx.set_sp(new_sp);
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(uctx.ip(), ip);
+    assert_eq!(uctx.retval(), -EINTR as usize);
+
+    // A handler with `SA_RESTART` runs: restarted after the handler.
+    proc.actions().lock()[Signo::SIGUSR1].flags = SignalActionFlags::RESTART;
+    let mut uctx = interrupted(RestartKind::Sys);
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGUSR1)));
+    thr.check_signals(&mut uctx, None).unwrap();
+    let new_sp = uctx.sp() + 8;
+    uctx.set_sp(new_sp);
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(uctx.ip(), 0x1000);
+    assert_eq!(uctx.sysno(), 7);
+}
+
+#[test]
+fn siginfo_only_for_sigaction_handler() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn handler(_: i32) {}
+    unsafe extern "C" fn sigaction(_: i32, _: *mut siginfo_t, _: *mut UContext) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition =
+        SignalDisposition::SigactionHandler(sigaction);
+    proc.actions().lock()[Signo::SIGUSR2].disposition = SignalDisposition::Handler(handler);
+    // Away from the frames of the other tests.
+    let sp = initial_sp() - 0x10000;
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let siginfo = uctx.arg1() as *const SignalInfo;
+    assert_eq!(unsafe { &*siginfo }.signo(), Signo::SIGUSR1);
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR2, 0, 1);
+    let action = proc.actions().lock()[Signo::SIGUSR2].clone();
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    assert_eq!(uctx.arg1() as *const SignalInfo, siginfo);
+    // Not overwritten.
+    assert_eq!(unsafe { &*siginfo }.signo(), Signo::SIGUSR1);
+}
+
+#[test]
+fn signal_frame_layout() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(handler);
+
+    let sp = initial_sp() - 0x20000;
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+
+    let used = sp - uctx.sp();
+    assert!((SIGNAL_FRAME_SIZE..=SIGNAL_FRAME_MAX_USAGE).contains(&used));
+    let frame = uctx.arg2() - SIGNAL_FRAME_UCONTEXT_OFFSET;
+    assert_eq!(frame % SIGNAL_FRAME_ALIGN, 0);
+    assert_eq!(uctx.arg1(), frame + SIGNAL_FRAME_SIGINFO_OFFSET);
+}
+
+#[test]
+fn outstanding_frames() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let sp = initial_sp() - 0x40000;
+
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let outer = uctx.arg2() - SIGNAL_FRAME_UCONTEXT_OFFSET;
+    assert_eq!(unsafe { &*(uctx.arg2() as *const UContext) }.link, 0);
+    // Two nested handlers.
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    assert_eq!(
+        unsafe { &*(uctx.arg2() as *const UContext) }.link,
+        outer + SIGNAL_FRAME_UCONTEXT_OFFSET
+    );
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let frames = thr.outstanding_frames();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0], outer);
+
+    // The outer handler returns after the inner ones were left.
+    let mut uctx = UserContext::new(0, outer.into(), 0);
+    thr.restore(&mut uctx).unwrap();
+    assert!(thr.outstanding_frames().is_empty());
+}
+
+#[test]
+fn abandoned_frames() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let sp = initial_sp() - 0x48000;
+
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    assert_eq!(thr.outstanding_frames().len(), 1);
+
+    // The handler leaves with `siglongjmp`, back above its frame, without
+    // `sigreturn`.
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let frame = uctx.arg2() - SIGNAL_FRAME_UCONTEXT_OFFSET;
+    assert_eq!(thr.outstanding_frames(), [frame]);
+    assert_eq!(unsafe { &*(uctx.arg2() as *const UContext) }.link, 0);
+}
+
+#[test]
+fn stack_hook_bounds() {
+    struct Stack(core::ops::Range<usize>);
+
+    impl UserStackHook for Stack {
+        fn stack_bounds(&self, _tid: u32, addr: usize) -> Option<core::ops::Range<usize>> {
+            self.0.contains(&addr).then(|| self.0.clone())
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let sp = initial_sp() - 0x50000;
+
+    // Room for one frame above the guard page.
+    proc.set_stack_hook(Some(Arc::new(Stack(sp - SIGNAL_FRAME_MAX_USAGE..sp))));
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    assert_eq!(
+        thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action),
+        Some(SignalOSAction::Handler)
+    );
+    let inner = uctx.sp();
+    assert_eq!(
+        thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action),
+        Some(SignalOSAction::CoreDump)
+    );
+    assert_eq!(uctx.sp(), inner);
+}
+
+#[test]
+fn stack_hook_prepare() {
+    struct GrowingStack {
+        bounds: Mutex<core::ops::Range<usize>>,
+        limit: usize,
+    }
+
+    impl UserStackHook for GrowingStack {
+        fn stack_bounds(&self, _tid: u32, addr: usize) -> Option<core::ops::Range<usize>> {
+            let bounds = self.bounds.lock().unwrap().clone();
+            bounds.contains(&addr).then_some(bounds)
+        }
+
+        fn prepare_frame(&self, _tid: u32, range: core::ops::Range<usize>) -> bool {
+            let mut bounds = self.bounds.lock().unwrap();
+            if range.start < self.limit {
+                return false;
+            }
+            bounds.start = bounds.start.min(range.start);
+            true
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let sp = initial_sp() - 0x60000;
+
+    // The stack grows on demand, up to room for two frames.
+    proc.set_stack_hook(Some(Arc::new(GrowingStack {
+        bounds: Mutex::new(sp - 0x10..sp),
+        limit: sp - 2 * SIGNAL_FRAME_MAX_USAGE,
+    })));
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    for _ in 0..2 {
+        assert_eq!(
+            thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action),
+            Some(SignalOSAction::Handler)
+        );
+    }
+    assert_eq!(
+        thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action),
+        Some(SignalOSAction::CoreDump)
+    );
+}
+
+#[test]
+fn stack_usage() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGUSR1].flags = SignalActionFlags::ONSTACK;
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    let sp = initial_sp() - 0x70000;
+
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let usage = thr.stack_usage();
+    assert_eq!(usage.max_frame, sp - uctx.sp());
+    assert_eq!(usage.altstack_high_water, 0);
+
+    let altstack = sp - 0x8000;
+    thr.set_stack(SignalStack::new(altstack, 0x4000));
+    let mut uctx = UserContext::new(0, sp.into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let first = altstack + 0x4000 - uctx.sp();
+    assert_eq!(thr.stack_usage().altstack_high_water, first);
+    // Nested on the alternate stack.
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    assert_eq!(
+        thr.stack_usage().altstack_high_water,
+        altstack + 0x4000 - uctx.sp()
+    );
+    assert!(thr.stack_usage().altstack_high_water >= 2 * first - SIGNAL_FRAME_ALIGN);
+
+    thr.reset_stack_usage();
+    assert_eq!(thr.stack_usage(), SignalStackUsage::default());
+}
+
+#[test]
+fn handler_mask_keeps_kill_stop_unblocked() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGUSR1].mask = !SignalSet::default();
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+
+    let mut uctx = UserContext::new(0, (initial_sp() - 0x80000).into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    assert!(thr.signal_blocked(Signo::SIGTERM));
+    assert!(!thr.signal_blocked(Signo::SIGKILL));
+    assert!(!thr.signal_blocked(Signo::SIGSTOP));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "already has a signal manager")]
+fn duplicate_thread() {
+    let (proc, _thr) = new_test_env();
+    let _ = ThreadSignalManager::new(TID, proc);
+}
+
+#[test]
+fn restore_keeps_kill_stop_unblocked() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+
+    let mut uctx = UserContext::new(0, (initial_sp() - 0x90000).into(), 0);
+    // As if the mask in the frame was tampered with.
+    thr.handle_signal(&mut uctx, !SignalSet::default(), &sig, &action);
+    let new_sp = uctx.sp() + if cfg!(target_arch = "x86_64") { 8 } else { 0 };
+    uctx.set_sp(new_sp);
+    thr.restore(&mut uctx).unwrap();
+    assert!(thr.signal_blocked(Signo::SIGTERM));
+    assert!(!thr.signal_blocked(Signo::SIGKILL));
+    assert!(!thr.signal_blocked(Signo::SIGSTOP));
+}
+
+#[test]
+fn restore_rejects_bad_ucontext_flags() {
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+    let action = proc.actions().lock()[Signo::SIGUSR1].clone();
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+
+    let mut uctx = UserContext::new(0x1000, (initial_sp() - 0xa0000).into(), 0);
+    thr.handle_signal(&mut uctx, thr.blocked(), &sig, &action);
+    let blocked = thr.blocked();
+    let ucontext = unsafe { &mut *(uctx.arg2() as *mut UContext) };
+    let flags = ucontext.flags;
+    ucontext.flags = 1 << 40;
+
+    let new_sp = uctx.sp() + if cfg!(target_arch = "x86_64") { 8 } else { 0 };
+    uctx.set_sp(new_sp);
+    assert_eq!(thr.restore(&mut uctx), Err(SignalError::InvalidArgument));
+    assert_eq!(thr.blocked(), blocked);
+    assert_eq!(uctx.sp(), new_sp);
+
+    ucontext.flags = flags;
+    thr.restore(&mut uctx).unwrap();
+    assert_eq!(uctx.ip(), 0x1000);
+}
+
+#[test]
+fn profiling_signal() {
+    #[derive(Default)]
+    struct Counter(AtomicU64);
+
+    impl SignalEventListener for Counter {
+        fn on_send(&self, _tid: Option<u32>, _sig: &SignalInfo) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGPROF].disposition = SignalDisposition::Handler(test_handler);
+    let counter = Arc::new(Counter::default());
+    proc.set_listener(Some(counter.clone()));
+
+    for _ in 0..2 {
+        assert!(thr.send_profiling_signal());
+        // Merged into the pending one.
+        assert!(!thr.send_profiling_signal());
+        let sig = thr.dequeue_signal(&!SignalSet::default()).unwrap();
+        assert_eq!(sig.signo(), Signo::SIGPROF);
+        assert_eq!(sig.code(), SignalInfo::new_kernel(Signo::SIGPROF).code());
+        assert!(thr.pending().is_empty());
+    }
+    // Merged ticks are not reported.
+    assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+
+    proc.actions().lock()[Signo::SIGPROF].disposition = SignalDisposition::Ignore;
+    assert!(!thr.send_profiling_signal());
+    assert!(thr.pending().is_empty());
+}
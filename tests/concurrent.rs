@@ -88,7 +88,7 @@ fn concurrent_check_signals() {
     let (proc, thr) = new_test_env();
 
     unsafe extern "C" fn test_handler(_: i32) {}
-    proc.actions.lock()[Signo::SIGTERM].disposition = SignalDisposition::Handler(test_handler);
+    proc.actions().lock()[Signo::SIGTERM].disposition = SignalDisposition::Handler(test_handler);
 
     let mut uctx = UserContext::new(0, initial_sp().into(), 0);
 
@@ -113,7 +113,7 @@ fn concurrent_check_signals() {
 
     let new_sp = uctx.sp() + 8;
     uctx.set_sp(new_sp);
-    thr.restore(&mut uctx);
+    thr.restore(&mut uctx).unwrap();
 
     assert!(!thr.signal_blocked(Signo::SIGTERM));
 
@@ -125,3 +125,35 @@ fn concurrent_check_signals() {
         delivered.has(Signo::SIGINT) && delivered.has(Signo::SIGTERM)
     }));
 }
+
+#[test]
+fn concurrent_try_send_signal() {
+    const LIMIT: usize = 8;
+    let (proc, thr) = new_test_env();
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGRTMIN].disposition = SignalDisposition::Handler(test_handler);
+    proc.set_rt_queue_limit(LIMIT);
+
+    // Every signal is either queued or refused, never silently dropped.
+    let accepted: usize = thread::scope(|s| {
+        let senders: Vec<_> = (0..4)
+            .map(|_| {
+                s.spawn(|| {
+                    (0..LIMIT)
+                        .filter(|_| {
+                            thr.try_send_signal(SignalInfo::new_user(Signo::SIGRTMIN, -1, 1))
+                                .is_ok()
+                        })
+                        .count()
+                })
+            })
+            .collect();
+        senders.into_iter().map(|s| s.join().unwrap()).sum()
+    });
+    assert_eq!(accepted, LIMIT);
+
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGRTMIN);
+    let queued = std::iter::from_fn(|| thr.dequeue_signal(&set)).count();
+    assert_eq!(queued, LIMIT);
+}
@@ -0,0 +1,69 @@
+use std::convert::Infallible;
+
+use axcpu::uspace::UserContext;
+use starry_signal::{
+    SignalInfo, SignalOSAction, Signo,
+    coredump::{CoreDumpContext, CoreDumpWriter, NT_PRSTATUS, NT_SIGINFO},
+};
+
+mod common;
+use common::*;
+
+#[derive(Default)]
+struct Notes(Vec<(String, u32, Vec<u8>)>);
+
+impl CoreDumpWriter for Notes {
+    type Error = Infallible;
+
+    fn write_note(&mut self, name: &str, ty: u32, desc: &[u8]) -> Result<(), Infallible> {
+        self.0.push((name.into(), ty, desc.to_vec()));
+        Ok(())
+    }
+
+    fn write_prstatus(&mut self, ctx: &CoreDumpContext) -> Result<(), Infallible> {
+        let pr_cursig = ctx.siginfo.signo() as u16;
+        self.write_note("CORE", NT_PRSTATUS, &pr_cursig.to_ne_bytes())
+    }
+}
+
+#[test]
+fn coredump_context() {
+    let (_proc, thr) = new_test_env();
+    let mut uctx = UserContext::new(0x1000, initial_sp().into(), 0);
+
+    let mut sig = SignalInfo::new_kernel(Signo::SIGSEGV);
+    sig.set_addr(0xdead);
+    assert!(thr.send_signal(sig));
+    let (_, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(action, SignalOSAction::CoreDump);
+
+    let ctx = thr.take_coredump().unwrap();
+    assert!(thr.take_coredump().is_none());
+    assert_eq!(ctx.siginfo.signo(), Signo::SIGSEGV);
+    assert_eq!(ctx.siginfo.addr(), 0xdead);
+    assert_eq!(ctx.uctx.ip(), 0x1000);
+    assert_eq!(ctx.blocked, thr.blocked());
+
+    let mut notes = Notes::default();
+    ctx.write_notes(&mut notes).unwrap();
+    assert_eq!(notes.0.len(), 2);
+    assert_eq!(notes.0[0].1, NT_PRSTATUS);
+    assert_eq!(notes.0[1].0, "CORE");
+    assert_eq!(notes.0[1].1, NT_SIGINFO);
+    assert_eq!(notes.0[1].2, ctx.siginfo_bytes());
+    assert_eq!(
+        i32::from_ne_bytes(notes.0[1].2[..4].try_into().unwrap()),
+        Signo::SIGSEGV as i32
+    );
+}
+
+#[test]
+fn no_coredump_on_terminate() {
+    let (_proc, thr) = new_test_env();
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+
+    assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM)));
+    let (_, action) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(action, SignalOSAction::Terminate);
+    assert!(thr.take_coredump().is_none());
+}
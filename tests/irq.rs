@@ -0,0 +1,115 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use starry_signal::{
+    SignalDisposition, SignalError, SignalInfo, SignalSet, Signo,
+    api::{IRQ_QUEUE_LEN, flush_irq_signals, set_cpu_id},
+    test_util::TestEnv,
+};
+
+const TID: u32 = 7;
+
+/// CPU shared by the threads of [`shared_queue`].
+const SHARED_CPU: usize = 63;
+
+thread_local! {
+    static ON_SHARED_CPU: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Gives each test thread a CPU of its own, except those put on
+/// [`SHARED_CPU`].
+fn cpu_id() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static ID: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    if ON_SHARED_CPU.get() {
+        return SHARED_CPU;
+    }
+    ID.with(|id| *id)
+}
+
+#[test]
+fn deferred_until_flush() {
+    set_cpu_id(cpu_id);
+    let env = TestEnv::builder().tid(TID).build();
+    let (proc, thr) = (env.proc, env.thread);
+
+    proc.send_signal_from_irq(SignalInfo::new_kernel(Signo::SIGUSR1))
+        .unwrap();
+    thr.send_signal_from_irq(SignalInfo::new_kernel(Signo::SIGUSR2))
+        .unwrap();
+    assert!(proc.pending().is_empty());
+    assert!(thr.pending().is_empty());
+
+    assert_eq!(flush_irq_signals(), [TID]);
+    assert!(proc.pending().has(Signo::SIGUSR1));
+    assert!(thr.pending().has(Signo::SIGUSR2));
+    assert!(flush_irq_signals().is_empty());
+}
+
+#[test]
+fn dropped_target() {
+    set_cpu_id(cpu_id);
+    let env = TestEnv::builder().tid(TID).build();
+    env.thread
+        .send_signal_from_irq(SignalInfo::new_kernel(Signo::SIGUSR1))
+        .unwrap();
+    drop(env);
+    assert!(flush_irq_signals().is_empty());
+}
+
+#[test]
+fn full_queue() {
+    set_cpu_id(cpu_id);
+    let env = TestEnv::builder().tid(TID).build();
+    let proc = env.proc;
+
+    for _ in 0..IRQ_QUEUE_LEN {
+        proc.send_signal_from_irq(SignalInfo::new_kernel(Signo::SIGUSR1))
+            .unwrap();
+    }
+    assert_eq!(
+        proc.send_signal_from_irq(SignalInfo::new_kernel(Signo::SIGUSR1)),
+        Err(SignalError::WouldBlock)
+    );
+    assert_eq!(flush_irq_signals(), [TID]);
+    proc.send_signal_from_irq(SignalInfo::new_kernel(Signo::SIGUSR1))
+        .unwrap();
+    flush_irq_signals();
+}
+
+#[test]
+fn shared_queue() {
+    set_cpu_id(cpu_id);
+    ON_SHARED_CPU.set(true);
+    let env = TestEnv::builder().tid(TID).build();
+    let proc = env.proc;
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGRTMIN].disposition = SignalDisposition::Handler(test_handler);
+
+    // CPUs sharing a queue push to it concurrently.
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                ON_SHARED_CPU.set(true);
+                for _ in 0..IRQ_QUEUE_LEN / 2 {
+                    proc.send_signal_from_irq(SignalInfo::new_kernel(Signo::SIGRTMIN))
+                        .unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(flush_irq_signals(), [TID]);
+
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGRTMIN);
+    let mut count = 0;
+    while env.thread.dequeue_signal(&set).is_some() {
+        count += 1;
+    }
+    assert_eq!(count, IRQ_QUEUE_LEN);
+}
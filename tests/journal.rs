@@ -0,0 +1,44 @@
+#![cfg(feature = "journal")]
+
+use axcpu::uspace::UserContext;
+use starry_signal::{
+    SignalDisposition, SignalInfo, Signo,
+    api::SignalWake,
+    journal::{self, JournalEvent},
+};
+
+mod common;
+use common::*;
+
+#[test]
+fn journal_records_events() {
+    let (proc, thr) = new_test_env();
+    journal::set_time_source(|| 42);
+
+    proc.actions().lock()[Signo::SIGINT].disposition = SignalDisposition::Ignore;
+    assert!(!thr.send_signal(SignalInfo::new_user(Signo::SIGINT, 0, 1)));
+    assert_eq!(
+        proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 3, 1)),
+        SignalWake::All(vec![TID])
+    );
+
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    let (sig, _) = thr.check_signals(&mut uctx, None).unwrap();
+    assert_eq!(sig.signo(), Signo::SIGTERM);
+
+    let entries = proc.journal();
+    let events: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.event, entry.tid, entry.signo))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            (JournalEvent::Ignore, TID, Some(Signo::SIGINT)),
+            (JournalEvent::Send, TID, Some(Signo::SIGTERM)),
+            (JournalEvent::Deliver, TID, Some(Signo::SIGTERM)),
+        ]
+    );
+    assert_eq!(entries[1].code, 3);
+    assert_eq!(entries[2].timestamp, 42);
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "lock-stats")]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use starry_signal::{
+    SignalInfo, SignalSet, Signo,
+    lockstat::{LockStats, set_time_source},
+    test_util::TestEnv,
+};
+
+const TID: u32 = 7;
+
+fn tick() -> u64 {
+    static NOW: AtomicU64 = AtomicU64::new(0);
+    NOW.fetch_add(1, Ordering::Relaxed)
+}
+
+#[test]
+fn counted() {
+    set_time_source(tick);
+    let env = TestEnv::builder().tid(TID).build();
+    let (proc, thr) = (env.proc, env.thread);
+    proc.reset_lock_stats();
+    thr.reset_lock_stats();
+
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1)));
+    let stats = thr.lock_stats();
+    assert!(stats.acquisitions > 0);
+    assert_eq!(stats.contended, 0);
+    assert!(stats.max_hold > 0);
+    assert!(proc.lock_stats().actions.acquisitions > 0);
+
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+    assert!(proc.lock_stats().pending.acquisitions > 0);
+    let all = !SignalSet::default();
+    while thr.dequeue_signal(&all).is_some() {}
+
+    proc.reset_lock_stats();
+    thr.reset_lock_stats();
+    assert_eq!(proc.lock_stats().pending, LockStats::default());
+    assert_eq!(proc.lock_stats().actions, LockStats::default());
+    assert_eq!(thr.lock_stats(), LockStats::default());
+}
@@ -0,0 +1,105 @@
+//! Model checks of the concurrency core. Run with
+//! `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+#![cfg(loom)]
+
+use std::sync::Arc;
+
+use axcpu::uspace::UserContext;
+use loom::thread;
+use starry_signal::{SignalInfo, SignalOSAction, SignalSet, Signo, api::ThreadSignalManager};
+
+mod common;
+use common::*;
+
+fn check(thr: &ThreadSignalManager) -> Option<Signo> {
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    thr.check_signals(&mut uctx, None).map(|(sig, action)| {
+        assert_eq!(action, SignalOSAction::Terminate);
+        sig.signo()
+    })
+}
+
+/// A signal sent concurrently with `check_signals` is either delivered by it
+/// or left for the next call.
+#[test]
+fn send_check() {
+    loom::model(|| {
+        let (_proc, thr) = new_test_env();
+        let sender = {
+            let thr = Arc::clone(&thr);
+            thread::spawn(move || assert!(thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM))))
+        };
+        let first = check(&thr);
+        sender.join().unwrap();
+        let second = check(&thr);
+        assert!(first.is_some() != second.is_some());
+        assert!(check(&thr).is_none());
+    });
+}
+
+/// A process-directed signal is delivered to exactly one thread.
+#[test]
+fn process_send_two_threads() {
+    loom::model(|| {
+        let (proc, thr1) = new_test_env();
+        let thr2 = ThreadSignalManager::new(TID + 1, proc.clone());
+        let sender = {
+            let proc = Arc::clone(&proc);
+            thread::spawn(move || proc.send_signal(SignalInfo::new_kernel(Signo::SIGTERM)))
+        };
+        let other = thread::spawn(move || check(&thr2).is_some() as usize);
+        let mut delivered = check(&thr1).is_some() as usize;
+        assert!(!sender.join().unwrap().is_none());
+        delivered += other.join().unwrap();
+        delivered += check(&thr1).is_some() as usize;
+        assert_eq!(delivered, 1);
+    });
+}
+
+/// Unblocking a signal concurrently sent while blocked makes it deliverable.
+#[test]
+fn send_unblock() {
+    loom::model(|| {
+        let (_proc, thr) = new_test_env();
+        let mut set = SignalSet::default();
+        set.add(Signo::SIGTERM);
+        thr.set_blocked(set);
+
+        let sender = {
+            let thr = Arc::clone(&thr);
+            thread::spawn(move || {
+                let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM));
+            })
+        };
+        assert!(check(&thr).is_none());
+        thr.set_blocked(SignalSet::default());
+        let first = check(&thr);
+        sender.join().unwrap();
+        let second = check(&thr);
+        assert!(first.is_some() != second.is_some());
+    });
+}
+
+/// A signal unblocked by another thread concurrently with `check_signals`
+/// is either delivered by it or left flagged for the next call.
+#[test]
+fn remote_unblock() {
+    loom::model(|| {
+        let (_proc, thr) = new_test_env();
+        let mut set = SignalSet::default();
+        set.add(Signo::SIGTERM);
+        thr.set_blocked(set);
+        let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGTERM));
+
+        let unblocker = {
+            let thr = Arc::clone(&thr);
+            thread::spawn(move || {
+                thr.set_blocked(SignalSet::default());
+            })
+        };
+        let first = check(&thr);
+        unblocker.join().unwrap();
+        let second = check(&thr);
+        assert!(first.is_some() != second.is_some());
+    });
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use starry_signal::{
+    SignalAction, SignalDisposition, SignalSet, Signo,
+    api::{FileOwner, OwnerLookup, OwnerTarget, ProcessSignalManager, ThreadSignalManager},
+    test_util::TestEnv,
+};
+
+const PID: u32 = 20;
+
+struct Lookup {
+    env: TestEnv,
+    uid: u32,
+}
+
+impl OwnerLookup for Lookup {
+    fn thread(&self, tid: u32) -> Option<(u32, Arc<ThreadSignalManager>)> {
+        (tid == self.env.thread.tid()).then(|| (PID, self.env.thread.clone()))
+    }
+
+    fn processes(&self, target: OwnerTarget) -> Vec<(u32, Arc<ProcessSignalManager>)> {
+        match target {
+            OwnerTarget::Process(PID) | OwnerTarget::ProcessGroup(PID) => {
+                vec![(PID, self.env.proc.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn permitted(&self, owner: &FileOwner, _pid: u32) -> bool {
+        owner.uid == 0 || owner.euid == self.uid
+    }
+}
+
+fn owner(target: OwnerTarget, uid: u32) -> FileOwner {
+    FileOwner {
+        target,
+        uid,
+        euid: uid,
+        signo: None,
+    }
+}
+
+unsafe extern "C" fn test_handler(_: i32) {}
+
+fn handled() -> SignalAction {
+    SignalAction {
+        disposition: SignalDisposition::Handler(test_handler),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sigurg_to_process_and_group() {
+    let lookup = Lookup {
+        env: TestEnv::builder()
+            .tid(5)
+            .action(Signo::SIGURG, handled())
+            .build(),
+        uid: 1000,
+    };
+
+    let tids = owner(OwnerTarget::Process(PID), 1000).send_sigurg(&lookup);
+    assert_eq!(tids, [5]);
+    assert!(lookup.env.thread.pending().has(Signo::SIGURG));
+    let _ = lookup.env.thread.dequeue_signal(&!SignalSet::default());
+
+    let tids = owner(OwnerTarget::ProcessGroup(PID), 1000).send_sigurg(&lookup);
+    assert_eq!(tids, [5]);
+    assert!(
+        owner(OwnerTarget::ProcessGroup(PID + 1), 1000)
+            .send_sigurg(&lookup)
+            .is_empty()
+    );
+}
+
+#[test]
+fn sigurg_checks_permission() {
+    let lookup = Lookup {
+        env: TestEnv::builder()
+            .tid(5)
+            .action(Signo::SIGURG, handled())
+            .build(),
+        uid: 1000,
+    };
+
+    assert!(
+        owner(OwnerTarget::Process(PID), 1001)
+            .send_sigurg(&lookup)
+            .is_empty()
+    );
+    assert!(
+        owner(OwnerTarget::Thread(5), 1001)
+            .send_sigurg(&lookup)
+            .is_empty()
+    );
+    assert!(!lookup.env.thread.pending().has(Signo::SIGURG));
+
+    assert_eq!(owner(OwnerTarget::Thread(5), 0).send_sigurg(&lookup), [5]);
+    assert!(lookup.env.thread.pending().has(Signo::SIGURG));
+}
+
+#[test]
+fn sigurg_ignored_by_default() {
+    let lookup = Lookup {
+        env: TestEnv::builder().tid(5).build(),
+        uid: 1000,
+    };
+
+    assert!(
+        owner(OwnerTarget::Process(PID), 1000)
+            .send_sigurg(&lookup)
+            .is_empty()
+    );
+    assert!(!lookup.env.thread.pending().has(Signo::SIGURG));
+}
+
+#[test]
+fn sigio_with_setsig() {
+    let lookup = Lookup {
+        env: TestEnv::builder()
+            .tid(5)
+            .action(Signo::SIGRTMIN, handled())
+            .build(),
+        uid: 1000,
+    };
+    let owner = FileOwner {
+        signo: Some(Signo::SIGRTMIN),
+        ..owner(OwnerTarget::Thread(5), 1000)
+    };
+
+    assert_eq!(owner.send_sigio(&lookup, 3, 1, 0x41), [5]);
+    let sig = lookup
+        .env
+        .thread
+        .dequeue_signal(&!SignalSet::default())
+        .unwrap();
+    assert_eq!(sig.signo(), Signo::SIGRTMIN);
+    assert_eq!(sig.code(), 1);
+    assert_eq!(sig.fd(), 3);
+    assert_eq!(sig.band(), 0x41);
+}
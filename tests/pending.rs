@@ -26,7 +26,7 @@ fn standard_signal() {
     assert!(ps.put_signal(sig4));
     assert!(ps.put_signal(sig5));
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGTERM);
-    assert!(ps.set.has(Signo::SIGQUIT));
+    assert!(ps.is_pending(Signo::SIGQUIT));
 }
 
 #[test]
@@ -47,7 +47,7 @@ fn realtime_signal() {
     assert!(ps.put_signal(sig3));
     assert!(ps.put_signal(sig4));
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRTMIN);
-    assert!(ps.set.has(Signo::SIGRTMIN));
+    assert!(ps.is_pending(Signo::SIGRTMIN));
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRTMIN);
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRT1);
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRT3);
@@ -58,7 +58,7 @@ fn realtime_signal() {
     assert!(ps.put_signal(sig5));
     assert!(ps.put_signal(sig6));
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRT3);
-    assert!(ps.set.has(Signo::SIGRT2));
+    assert!(ps.is_pending(Signo::SIGRT2));
 }
 
 #[test]
@@ -83,7 +83,84 @@ fn mixed_signal() {
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGTERM);
 
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRTMIN);
-    assert!(ps.set.has(Signo::SIGRTMIN));
+    assert!(ps.is_pending(Signo::SIGRTMIN));
     assert_eq!(ps.dequeue_signal(&mask).unwrap().signo(), Signo::SIGRTMIN);
     assert!(ps.dequeue_signal(&mask).is_none());
 }
+
+#[test]
+fn remove_signals() {
+    let mut ps = PendingSignals::default();
+    for signo in [
+        Signo::SIGINT,
+        Signo::SIGRTMIN,
+        Signo::SIGRTMIN,
+        Signo::SIGTERM,
+    ] {
+        assert!(ps.put_signal(SignalInfo::new_user(signo, 9, 9)));
+    }
+
+    let mut mask = SignalSet::default();
+    mask.add(Signo::SIGINT);
+    mask.add(Signo::SIGRTMIN);
+    mask.add(Signo::SIGHUP);
+    assert!(ps.remove_signals(&mask));
+    assert!(!ps.remove_signals(&mask));
+    assert_eq!(
+        ps.iter().map(|sig| sig.signo()).collect::<Vec<_>>(),
+        [Signo::SIGTERM]
+    );
+    assert!(ps.dequeue_signal(&mask).is_none());
+
+    // A removed real-time signal queues again from scratch.
+    assert!(ps.put_signal(SignalInfo::new_user(Signo::SIGRTMIN, 1, 9)));
+    assert_eq!(ps.dequeue_signal(&mask).unwrap().code(), 1);
+    assert!(ps.dequeue_signal(&mask).is_none());
+}
+
+#[test]
+fn timer_overrun() {
+    let mut ps = PendingSignals::default();
+    let mask = !SignalSet::default();
+    assert!(ps.put_signal(SignalInfo::new_timer(Signo::SIGRTMIN, 1, 0)));
+    assert!(ps.put_signal(SignalInfo::new_timer(Signo::SIGRTMIN, 2, 0)));
+    assert!(!ps.put_signal(SignalInfo::new_timer(Signo::SIGRTMIN, 1, 0)));
+    assert!(!ps.put_signal(SignalInfo::new_timer(Signo::SIGRTMIN, 1, 0)));
+    // Other signals are still queued.
+    assert!(ps.put_signal(SignalInfo::new_user(Signo::SIGRTMIN, 9, 9)));
+
+    let sig = ps.dequeue_signal(&mask).unwrap();
+    assert_eq!((sig.timer_id(), sig.overrun()), (1, 2));
+    let sig = ps.dequeue_signal(&mask).unwrap();
+    assert_eq!((sig.timer_id(), sig.overrun()), (2, 0));
+    assert!(!ps.dequeue_signal(&mask).unwrap().is_timer());
+
+    let mut sig = SignalInfo::new_timer(Signo::SIGALRM, 1, 0);
+    assert!(ps.put_signal(sig.clone()));
+    sig.set_overrun(i32::MAX);
+    assert!(!ps.put_signal(sig));
+    assert_eq!(ps.dequeue_signal(&mask).unwrap().overrun(), i32::MAX);
+}
+
+#[test]
+fn introspection() {
+    let mut ps = PendingSignals::default();
+    assert!(ps.is_empty());
+    assert!(ps.put_signal(SignalInfo::new_user(Signo::SIGINT, 0, 1)));
+    assert!(!ps.put_signal(SignalInfo::new_user(Signo::SIGINT, 0, 1)));
+    for _ in 0..3 {
+        assert!(ps.put_signal(SignalInfo::new_user(Signo::SIGRT2, 0, 1)));
+    }
+    assert_eq!(ps.count(Signo::SIGINT), 1);
+    assert_eq!(ps.count(Signo::SIGRT2), 3);
+    assert_eq!(ps.count(Signo::SIGTERM), 0);
+    assert_eq!(ps.len(), 4);
+    assert!(!ps.is_empty());
+
+    let mut mask = SignalSet::default();
+    mask.add(Signo::SIGRT2);
+    ps.dequeue_signal(&mask).unwrap();
+    assert_eq!(ps.count(Signo::SIGRT2), 2);
+    assert_eq!(ps.len(), 3);
+    assert!(ps.is_pending(Signo::SIGRT2));
+}
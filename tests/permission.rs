@@ -0,0 +1,123 @@
+use starry_signal::{
+    SignalError, SignalInfo, Signo,
+    api::{
+        Credentials, CredentialsProvider, KillPermission, LinuxKillPermission, SignalWake,
+        kill_process, kill_thread,
+    },
+    test_util::TestEnv,
+};
+
+struct Table;
+
+impl CredentialsProvider for Table {
+    fn credentials(&self, pid: u32) -> Option<Credentials> {
+        let user = Credentials {
+            uid: 1000,
+            euid: 1000,
+            suid: 1000,
+            cap_kill: false,
+            sid: 10,
+        };
+        Some(match pid {
+            1 => Credentials {
+                uid: 0,
+                euid: 0,
+                suid: 0,
+                cap_kill: true,
+                sid: 1,
+            },
+            2 => user,
+            // A setuid program started by user 1000.
+            3 => Credentials {
+                euid: 0,
+                suid: 0,
+                ..user
+            },
+            4 => Credentials {
+                uid: 1001,
+                euid: 1001,
+                suid: 1001,
+                ..user
+            },
+            5 => Credentials { sid: 20, ..user },
+            6 => Credentials {
+                uid: 1002,
+                euid: 1002,
+                suid: 1002,
+                ..user
+            },
+            _ => return None,
+        })
+    }
+}
+
+#[test]
+fn linux_kill_permission() {
+    let perm = LinuxKillPermission(Table);
+    let term = SignalInfo::new_user(Signo::SIGTERM, 0, 0);
+    let cont = SignalInfo::new_user(Signo::SIGCONT, 0, 0);
+    let kernel = SignalInfo::new_kernel(Signo::SIGTERM);
+    let check = |sender, target, sig| perm.check_kill(sender, target, sig);
+
+    // CAP_KILL.
+    assert_eq!(check(1, 2, Some(&term)), Ok(()));
+    // Matching real user IDs, even if the target is setuid.
+    assert_eq!(check(2, 3, Some(&term)), Ok(()));
+    assert_eq!(check(2, 5, None), Ok(()));
+    assert_eq!(check(2, 1, Some(&term)), Err(SignalError::Permission));
+    assert_eq!(check(2, 1, None), Err(SignalError::Permission));
+    assert_eq!(check(4, 2, Some(&term)), Err(SignalError::Permission));
+    // SIGCONT within the session.
+    assert_eq!(check(4, 2, Some(&cont)), Ok(()));
+    assert_eq!(check(6, 5, Some(&cont)), Err(SignalError::Permission));
+    // Within the process, or from the kernel.
+    assert_eq!(check(4, 4, Some(&term)), Ok(()));
+    assert_eq!(check(4, 2, Some(&kernel)), Ok(()));
+
+    assert_eq!(check(2, 9, None), Err(SignalError::NoSuchProcess));
+    assert_eq!(check(9, 2, Some(&term)), Err(SignalError::NoSuchProcess));
+}
+
+#[test]
+fn null_signal_probe() {
+    let perm = LinuxKillPermission(Table);
+    let env = TestEnv::builder().tid(7).build();
+    let (proc, thr) = (Some(&*env.proc), Some(&*env.thread));
+    let term = || Some(SignalInfo::new_user(Signo::SIGTERM, 0, 2));
+
+    assert_eq!(Signo::try_from_nullable(0), Ok(None));
+    assert_eq!(Signo::try_from_nullable(15), Ok(Some(Signo::SIGTERM)));
+    assert_eq!(
+        Signo::try_from_nullable(65),
+        Err(SignalError::InvalidSignal)
+    );
+
+    assert_eq!(kill_process(&perm, 2, 3, proc, None), Ok(SignalWake::None));
+    assert_eq!(kill_thread(&perm, 2, 3, thr, None), Ok(false));
+    assert!(env.proc.pending().is_empty());
+    assert!(env.thread.pending().is_empty());
+    assert_eq!(
+        kill_process(&perm, 2, 1, proc, None),
+        Err(SignalError::Permission)
+    );
+    assert_eq!(
+        kill_thread(&perm, 2, 1, thr, None),
+        Err(SignalError::Permission)
+    );
+    assert_eq!(
+        kill_process(&perm, 2, 3, None, None),
+        Err(SignalError::NoSuchProcess)
+    );
+    assert_eq!(
+        kill_thread(&perm, 2, 3, None, None),
+        Err(SignalError::NoSuchProcess)
+    );
+
+    assert_eq!(
+        kill_process(&perm, 2, 1, proc, term()),
+        Err(SignalError::Permission)
+    );
+    assert!(env.proc.pending().is_empty());
+    assert!(kill_thread(&perm, 2, 3, thr, term()).unwrap());
+    assert!(env.thread.pending().has(Signo::SIGTERM));
+}
@@ -0,0 +1,58 @@
+use starry_signal::{SignalError, SignalInfo, SignalSet, Signo, registry::ThreadRegistry};
+
+#[test]
+fn insert_remove() {
+    let mut registry = ThreadRegistry::<2>::default();
+    assert!(registry.is_empty());
+    let a = registry.insert(7).unwrap();
+    let b = registry.insert(8).unwrap();
+    assert_eq!(registry.insert(7), Err(SignalError::InvalidArgument));
+    assert_eq!(registry.insert(9), Err(SignalError::WouldBlock));
+    assert_eq!(registry.index_of(8), Some(b));
+    assert_eq!(registry.get(a).unwrap().tid(), 7);
+
+    assert_eq!(registry.remove(a).unwrap().tid(), 7);
+    assert!(registry.get(a).is_none());
+    assert_eq!(registry.insert(9), Ok(a));
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn routing() {
+    let mut registry = ThreadRegistry::<4>::default();
+    let a = registry.insert(7).unwrap();
+    let b = registry.insert(8).unwrap();
+    registry.get_mut(a).unwrap().blocked.add(Signo::SIGUSR1);
+
+    let sig = SignalInfo::new_user(Signo::SIGUSR1, 0, 1);
+    assert!(!registry.send_to_thread(a, sig.clone()));
+    assert!(registry.send_to_thread(b, sig.clone()));
+    assert_eq!(
+        registry.send_to_process(SignalInfo::new_user(Signo::SIGUSR1, 0, 2)),
+        Some(b)
+    );
+    // Taken in turn.
+    assert_eq!(
+        registry.send_to_process(SignalInfo::new_user(Signo::SIGUSR2, 0, 2)),
+        Some(a)
+    );
+
+    let all = !SignalSet::default();
+    assert_eq!(
+        registry.dequeue_signal(a, &all).map(|sig| sig.signo()),
+        Some(Signo::SIGUSR1)
+    );
+    assert_eq!(
+        registry.dequeue_signal(a, &all).map(|sig| sig.signo()),
+        Some(Signo::SIGUSR1)
+    );
+    assert_eq!(
+        registry.dequeue_signal(b, &all).map(|sig| sig.signo()),
+        Some(Signo::SIGUSR1)
+    );
+    assert_eq!(
+        registry.dequeue_signal(b, &all).map(|sig| sig.signo()),
+        Some(Signo::SIGUSR2)
+    );
+    assert!(registry.dequeue_signal(b, &all).is_none());
+}
@@ -0,0 +1,178 @@
+use std::{
+    pin::pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use starry_signal::{
+    SignalDisposition, SignalError, SignalInfo, SignalSet, Signo,
+    api::{ReschedHook, ThreadSignalManager, set_resched_hook},
+    test_util::TestEnv,
+};
+
+static KICKS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+struct Kicks;
+
+impl ReschedHook for Kicks {
+    fn kick(&self, tid: u32) {
+        KICKS.lock().unwrap().push(tid);
+    }
+}
+
+/// Takes the kicks of threads `tids`, as tests run concurrently.
+fn take_kicks(tids: &[u32]) -> Vec<u32> {
+    let mut kicks = KICKS.lock().unwrap();
+    let (taken, rest) = kicks.iter().partition(|tid| tids.contains(tid));
+    *kicks = rest;
+    taken
+}
+
+#[test]
+fn kicks_woken_threads() {
+    const TIDS: &[u32] = &[7, 8];
+    set_resched_hook(Some(Arc::new(Kicks)));
+
+    let env = TestEnv::builder().tid(7).build();
+    let (proc, thr) = (env.proc, env.thread);
+    let other = ThreadSignalManager::new(8, proc.clone());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR2].disposition = SignalDisposition::Handler(test_handler);
+
+    assert!(thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1)));
+    assert_eq!(take_kicks(TIDS), [7]);
+
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGUSR2);
+    thr.set_blocked(blocked);
+    assert!(!thr.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1)));
+    assert!(take_kicks(TIDS).is_empty());
+
+    // Only the other thread can take it.
+    let wake = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+    assert_eq!(wake.tids(), [8]);
+    assert_eq!(take_kicks(TIDS), [8]);
+
+    // Killing the process interrupts every thread.
+    let wake = proc.send_signal(SignalInfo::new_user(Signo::SIGKILL, 0, 1));
+    let mut kicked = take_kicks(TIDS);
+    kicked.sort_unstable();
+    assert_eq!(kicked, [7, 8]);
+    assert_eq!(wake.tids().len(), 2);
+
+    drop(other);
+}
+
+#[test]
+fn retarget_on_block() {
+    const TIDS: &[u32] = &[17, 18];
+    set_resched_hook(Some(Arc::new(Kicks)));
+
+    let env = TestEnv::builder().tid(17).build();
+    let (proc, thr) = (env.proc, env.thread);
+    let other = ThreadSignalManager::new(18, proc.clone());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGUSR1);
+    other.set_blocked(blocked);
+    let wake = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    assert_eq!(wake.tids(), [17]);
+    other.set_blocked(SignalSet::default());
+    take_kicks(TIDS);
+
+    // Left to the thread that now blocks it.
+    thr.set_blocked(blocked);
+    assert_eq!(take_kicks(TIDS), [18]);
+    // Not pending any more, or not newly blocked.
+    thr.set_blocked(blocked);
+    assert!(take_kicks(TIDS).is_empty());
+}
+
+#[test]
+fn handoff_on_exit() {
+    const TIDS: &[u32] = &[27, 28];
+    set_resched_hook(Some(Arc::new(Kicks)));
+
+    let env = TestEnv::builder().tid(27).build();
+    let (proc, thr) = (env.proc, env.thread);
+    let other = ThreadSignalManager::new(28, proc.clone());
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGUSR1].disposition = SignalDisposition::Handler(test_handler);
+
+    let wake = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    assert_eq!(wake.tids(), [27]);
+    assert_eq!(take_kicks(TIDS), [27]);
+
+    thr.exit_signals();
+    assert!(thr.is_exiting());
+    assert_eq!(take_kicks(TIDS), [28]);
+
+    // The exiting thread is never picked again.
+    for _ in 0..2 {
+        let wake = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+        assert_eq!(wake.tids(), [28]);
+    }
+    take_kicks(TIDS);
+
+    other.exit_signals();
+    let wake = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    assert!(wake.is_none());
+}
+
+#[test]
+fn kick_on_unblock() {
+    const TIDS: &[u32] = &[37];
+    set_resched_hook(Some(Arc::new(Kicks)));
+
+    let env = TestEnv::builder().tid(37).build();
+    let thr = env.thread;
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGUSR1);
+    blocked.add(Signo::SIGUSR2);
+    thr.set_blocked(blocked);
+    assert!(!thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1)));
+
+    // Unblocking nothing pending.
+    blocked.remove(Signo::SIGUSR2);
+    thr.set_blocked(blocked);
+    assert!(take_kicks(TIDS).is_empty());
+
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut set = SignalSet::default();
+    set.add(Signo::SIGUSR2);
+    let mut fut = pin!(thr.wait_signal(set));
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    thr.set_blocked(SignalSet::default());
+    assert_eq!(take_kicks(TIDS), [37]);
+    assert!(matches!(
+        fut.as_mut().poll(&mut cx),
+        Poll::Ready(Err(SignalError::Interrupted))
+    ));
+}
+
+#[test]
+fn kick_on_freeze() {
+    const TIDS: &[u32] = &[47, 48];
+    set_resched_hook(Some(Arc::new(Kicks)));
+
+    let env = TestEnv::builder().tid(47).build();
+    let (proc, thr) = (env.proc, env.thread);
+    let other = ThreadSignalManager::new(48, proc.clone());
+
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut fut = pin!(thr.wait_signal(SignalSet::default()));
+    assert!(fut.as_mut().poll(&mut cx).is_pending());
+    proc.freeze();
+    let mut kicked = take_kicks(TIDS);
+    kicked.sort_unstable();
+    assert_eq!(kicked, [47, 48]);
+    assert!(matches!(
+        fut.as_mut().poll(&mut cx),
+        Poll::Ready(Err(SignalError::Interrupted))
+    ));
+    proc.thaw();
+
+    drop(other);
+}
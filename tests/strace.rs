@@ -0,0 +1,51 @@
+use starry_signal::{
+    SignalAction, SignalActionFlags, SignalDisposition, SignalInfo, SignalSet, Signo,
+    strace::{format_action, format_delivery, format_siginfo},
+};
+
+#[test]
+fn siginfo() {
+    let sig = SignalInfo::new_user(Signo::SIGTERM, 0, 42);
+    assert_eq!(
+        format_siginfo(&sig).to_string(),
+        "{si_signo=SIGTERM, si_code=SI_USER, si_pid=42, si_uid=0}"
+    );
+
+    let mut sig = SignalInfo::new_kernel(Signo::SIGSEGV);
+    sig.set_code(1);
+    sig.set_addr(0x1000);
+    assert_eq!(
+        format_delivery(&sig).to_string(),
+        "--- SIGSEGV {si_signo=SIGSEGV, si_code=SEGV_MAPERR, si_addr=0x1000} ---"
+    );
+
+    let sig = SignalInfo::new_kernel(Signo::SIGINT);
+    assert_eq!(
+        format_siginfo(&sig).to_string(),
+        "{si_signo=SIGINT, si_code=SI_KERNEL}"
+    );
+}
+
+#[test]
+fn action() {
+    let action = SignalAction::default();
+    assert_eq!(
+        format_action(&action).to_string(),
+        "{sa_handler=SIG_DFL, sa_mask=[], sa_flags=0}"
+    );
+
+    let mut mask = SignalSet::default();
+    mask.add(Signo::SIGINT);
+    mask.add(Signo::SIGTERM);
+    mask.add(Signo::SIGRT2);
+    let action = SignalAction {
+        flags: SignalActionFlags::RESTART | SignalActionFlags::NODEFER,
+        mask,
+        disposition: SignalDisposition::Ignore,
+        restorer: None,
+    };
+    assert_eq!(
+        format_action(&action).to_string(),
+        "{sa_handler=SIG_IGN, sa_mask=[INT TERM RT_2], sa_flags=SA_NODEFER|SA_RESTART}"
+    );
+}
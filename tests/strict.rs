@@ -0,0 +1,84 @@
+#![cfg(feature = "linux-strict")]
+
+use starry_signal::{
+    SignalDisposition, SignalInfo, SignalSet, Signo, api::ThreadSignalManager, test_util::TestEnv,
+};
+
+const TID: u32 = 7;
+
+#[test]
+fn synchronous_first() {
+    let env = TestEnv::builder().tid(TID).build();
+    let thr = env.thread;
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGINT, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGSEGV));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 1));
+
+    let all = !SignalSet::default();
+    let order: Vec<_> = std::iter::from_fn(|| thr.dequeue_signal(&all))
+        .map(|sig| sig.signo())
+        .collect();
+    assert_eq!(order, [Signo::SIGSEGV, Signo::SIGHUP, Signo::SIGINT]);
+}
+
+#[test]
+fn stop_cont_interaction() {
+    let env = TestEnv::builder().tid(TID).build();
+    let (proc, thr) = (env.proc, env.thread);
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGCONT);
+    blocked.add(Signo::SIGTSTP);
+    thr.set_blocked(blocked);
+
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGCONT, 0, 1));
+    assert!(proc.pending().has(Signo::SIGCONT));
+    // A stop signal discards the pending SIGCONT.
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTSTP, 0, 1));
+    assert!(!proc.pending().has(Signo::SIGCONT));
+    assert!(proc.pending().has(Signo::SIGTSTP));
+
+    // SIGCONT continues the process and discards the stop signals.
+    assert!(proc.stop(Signo::SIGTSTP));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGCONT, 0, 1));
+    assert!(!proc.is_stopped());
+    assert!(!thr.pending().has(Signo::SIGTSTP));
+
+    // The threads leaving group-stop are returned, even though SIGCONT is
+    // blocked and doesn't affect every thread.
+    let other = ThreadSignalManager::new(TID + 1, proc.clone());
+    assert!(proc.stop(Signo::SIGTSTP));
+    thr.enter_group_stop();
+    other.enter_group_stop();
+    let wake = proc.send_signal(SignalInfo::new_user(Signo::SIGCONT, 0, 1));
+    let mut tids = wake.tids().to_vec();
+    tids.sort_unstable();
+    assert_eq!(tids, [TID, TID + 1]);
+    assert!(proc.stopped_threads().is_empty());
+
+    assert!(proc.stop(Signo::SIGTSTP));
+    thr.enter_group_stop();
+    let wake = proc.send_signals(&[SignalInfo::new_user(Signo::SIGCONT, 0, 1)]);
+    assert!(wake.tids().contains(&TID));
+}
+
+#[test]
+fn kernel_signals_over_rt_limit() {
+    let env = TestEnv::builder().tid(TID).build();
+    let (proc, thr) = (env.proc, env.thread);
+    unsafe extern "C" fn test_handler(_: i32) {}
+    proc.actions().lock()[Signo::SIGRTMIN].disposition = SignalDisposition::Handler(test_handler);
+    proc.set_rt_queue_limit(1);
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGRTMIN);
+    thr.set_blocked(blocked);
+
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGRTMIN, -1, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGRTMIN, -1, 1));
+    let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGRTMIN));
+    let mut mask = SignalSet::default();
+    mask.add(Signo::SIGRTMIN);
+    let codes: Vec<_> = std::iter::from_fn(|| thr.dequeue_signal(&mask))
+        .map(|sig| sig.code())
+        .collect();
+    assert_eq!(codes, [-1, 0x80]);
+}
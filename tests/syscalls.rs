@@ -0,0 +1,239 @@
+use core::{
+    future::{Future, pending, ready},
+    mem::size_of,
+    pin::pin,
+    ptr,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use linux_raw_sys::general::{
+    __kernel_timespec, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK, SS_DISABLE, SS_ONSTACK,
+    kernel_sigaction, kernel_sigset_t, siginfo_t, stack_t,
+};
+use starry_signal::{
+    SignalAction, SignalDisposition, SignalError, SignalInfo, SignalResult, SignalSet, SignalStack,
+    Signo,
+    api::{ThreadSignalManager, syscalls},
+    test_util::POOL_SIZE,
+};
+
+mod common;
+use common::*;
+
+const SIGSETSIZE: usize = size_of::<kernel_sigset_t>();
+
+/// Returns a pointer into the memory pool, away from where frames are set up.
+fn user_ptr<T>(slot: usize) -> *mut T {
+    (initial_sp() - POOL_SIZE + slot * 0x100) as *mut T
+}
+
+fn set_of(signals: &[Signo]) -> SignalSet {
+    let mut set = SignalSet::default();
+    for &signo in signals {
+        set.add(signo);
+    }
+    set
+}
+
+#[test]
+fn rt_sigaction() {
+    let (proc, thr) = new_test_env();
+    let act = user_ptr::<kernel_sigaction>(0);
+    let oldact = user_ptr::<kernel_sigaction>(1);
+
+    let new = SignalAction {
+        disposition: SignalDisposition::Ignore,
+        ..Default::default()
+    };
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    unsafe { act.write(new.into()) };
+    syscalls::rt_sigaction(&thr, Signo::SIGUSR1 as _, act, oldact, SIGSETSIZE).unwrap();
+    assert!(proc.signal_ignored(Signo::SIGUSR1));
+    // Pending instances of a newly ignored signal are discarded.
+    assert!(!thr.pending().has(Signo::SIGUSR1));
+    let old = SignalAction::from(unsafe { oldact.read() });
+    assert!(matches!(old.disposition, SignalDisposition::Default));
+
+    syscalls::rt_sigaction(&thr, Signo::SIGUSR1 as _, ptr::null(), oldact, SIGSETSIZE).unwrap();
+    let old = SignalAction::from(unsafe { oldact.read() });
+    assert!(matches!(old.disposition, SignalDisposition::Ignore));
+
+    assert_eq!(
+        syscalls::rt_sigaction(&thr, Signo::SIGKILL as _, act, ptr::null_mut(), SIGSETSIZE),
+        Err(SignalError::InvalidArgument)
+    );
+    assert_eq!(
+        syscalls::rt_sigaction(&thr, 0, act, ptr::null_mut(), SIGSETSIZE),
+        Err(SignalError::InvalidSignal)
+    );
+    assert_eq!(
+        syscalls::rt_sigaction(&thr, Signo::SIGUSR2 as _, act, ptr::null_mut(), 4),
+        Err(SignalError::InvalidArgument)
+    );
+    assert_eq!(
+        syscalls::rt_sigaction(
+            &thr,
+            Signo::SIGUSR2 as _,
+            8 as _,
+            ptr::null_mut(),
+            SIGSETSIZE
+        ),
+        Err(SignalError::BadAddress)
+    );
+}
+
+#[test]
+fn rt_sigprocmask() {
+    let (_proc, thr) = new_test_env();
+    let set = user_ptr::<kernel_sigset_t>(2);
+    let oldset = user_ptr::<kernel_sigset_t>(3);
+
+    unsafe { set.write(set_of(&[Signo::SIGINT, Signo::SIGKILL]).into()) };
+    syscalls::rt_sigprocmask(&thr, SIG_BLOCK, set, oldset, SIGSETSIZE).unwrap();
+    assert_eq!(thr.blocked(), set_of(&[Signo::SIGINT]));
+    assert!(SignalSet::from(unsafe { oldset.read() }).is_empty());
+
+    unsafe { set.write(set_of(&[Signo::SIGTERM]).into()) };
+    syscalls::rt_sigprocmask(&thr, SIG_BLOCK, set, ptr::null_mut(), SIGSETSIZE).unwrap();
+    unsafe { set.write(set_of(&[Signo::SIGINT]).into()) };
+    syscalls::rt_sigprocmask(&thr, SIG_UNBLOCK, set, oldset, SIGSETSIZE).unwrap();
+    assert_eq!(thr.blocked(), set_of(&[Signo::SIGTERM]));
+    assert_eq!(
+        SignalSet::from(unsafe { oldset.read() }),
+        set_of(&[Signo::SIGINT, Signo::SIGTERM])
+    );
+
+    syscalls::rt_sigprocmask(&thr, SIG_SETMASK, set, ptr::null_mut(), SIGSETSIZE).unwrap();
+    assert_eq!(thr.blocked(), set_of(&[Signo::SIGINT]));
+
+    assert_eq!(
+        syscalls::rt_sigprocmask(&thr, 3, set, ptr::null_mut(), SIGSETSIZE),
+        Err(SignalError::InvalidArgument)
+    );
+    assert_eq!(thr.blocked(), set_of(&[Signo::SIGINT]));
+}
+
+#[test]
+fn rt_sigpending() {
+    let (proc, thr) = new_test_env();
+    let set = user_ptr::<kernel_sigset_t>(9);
+
+    thr.set_blocked(set_of(&[Signo::SIGUSR1, Signo::SIGUSR2]));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    assert!(thr.pending().has(Signo::SIGTERM));
+
+    syscalls::rt_sigpending(&thr, set, SIGSETSIZE).unwrap();
+    assert_eq!(
+        SignalSet::from(unsafe { set.read() }),
+        set_of(&[Signo::SIGUSR1, Signo::SIGUSR2])
+    );
+    assert_eq!(
+        syscalls::rt_sigpending(&thr, set, 4),
+        Err(SignalError::InvalidArgument)
+    );
+}
+
+#[test]
+fn sigaltstack() {
+    let (_proc, thr) = new_test_env();
+    let ss = user_ptr::<stack_t>(4);
+    let old_ss = user_ptr::<stack_t>(5);
+
+    unsafe { ss.write(SignalStack::new(0x10000, 0x4000).into()) };
+    syscalls::sigaltstack(&thr, ss, old_ss, 0x1000).unwrap();
+    assert_eq!(thr.stack().top(), 0x14000);
+    assert_eq!(unsafe { old_ss.read() }.ss_flags, SS_DISABLE as _);
+
+    // Running on the stack.
+    assert_eq!(
+        syscalls::sigaltstack(&thr, ss, old_ss, 0x12000),
+        Err(SignalError::Permission)
+    );
+    syscalls::sigaltstack(&thr, ptr::null(), old_ss, 0x12000).unwrap();
+    let old = unsafe { old_ss.read() };
+    assert_eq!(old.ss_flags, SS_ONSTACK as _);
+    assert_eq!(old.ss_size, 0x4000);
+
+    unsafe { ss.write(SignalStack::new(0x10000, 0x100).into()) };
+    assert_eq!(
+        syscalls::sigaltstack(&thr, ss, ptr::null_mut(), 0x1000),
+        Err(SignalError::StackTooSmall)
+    );
+}
+
+fn poll_sigtimedwait<F: Future<Output = ()>>(
+    thr: &ThreadSignalManager,
+    set: *const kernel_sigset_t,
+    info: *mut siginfo_t,
+    timeout: *const __kernel_timespec,
+    sleep: impl FnOnce(Duration) -> F,
+) -> Poll<SignalResult<Signo>> {
+    let mut cx = Context::from_waker(Waker::noop());
+    let fut = pin!(syscalls::rt_sigtimedwait(
+        thr, set, info, timeout, SIGSETSIZE, sleep
+    ));
+    fut.poll(&mut cx)
+}
+
+#[test]
+fn rt_sigtimedwait() {
+    let (_proc, thr) = new_test_env();
+    let set = user_ptr::<kernel_sigset_t>(6);
+    let info = user_ptr::<siginfo_t>(7);
+    let timeout = user_ptr::<__kernel_timespec>(8);
+
+    unsafe { set.write(set_of(&[Signo::SIGUSR1]).into()) };
+    thr.set_blocked(set_of(&[Signo::SIGUSR1]));
+    unsafe {
+        timeout.write(__kernel_timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        })
+    };
+    let poll = |timeout, sleep| poll_sigtimedwait(&thr, set, info, timeout, sleep);
+    let expire = |_| ready(());
+    assert_eq!(
+        poll(timeout, expire),
+        Poll::Ready(Err(SignalError::WouldBlock))
+    );
+
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 42));
+    assert_eq!(poll(timeout, expire), Poll::Ready(Ok(Signo::SIGUSR1)));
+    let sig = SignalInfo(unsafe { info.read() });
+    assert_eq!(sig.signo(), Signo::SIGUSR1);
+    assert_eq!(sig.pid(), 42);
+
+    unsafe {
+        timeout.write(__kernel_timespec {
+            tv_sec: 1,
+            tv_nsec: 0,
+        })
+    };
+    assert_eq!(
+        poll(timeout, expire),
+        Poll::Ready(Err(SignalError::WouldBlock))
+    );
+    let never = |_| pending();
+    assert_eq!(
+        poll_sigtimedwait(&thr, set, info, timeout, never),
+        Poll::Pending
+    );
+    assert_eq!(
+        poll_sigtimedwait(&thr, set, info, ptr::null(), never),
+        Poll::Pending
+    );
+
+    unsafe {
+        timeout.write(__kernel_timespec {
+            tv_sec: 0,
+            tv_nsec: 1_000_000_000,
+        })
+    };
+    assert_eq!(
+        poll(timeout, expire),
+        Poll::Ready(Err(SignalError::InvalidArgument))
+    );
+}
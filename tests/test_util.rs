@@ -0,0 +1,35 @@
+use starry_signal::{
+    SignalAction, SignalDisposition, SignalInfo, Signo,
+    test_util::{TestEnv, clock, initial_sp},
+};
+
+#[test]
+fn builder() {
+    let env = TestEnv::builder()
+        .tid(3)
+        .default_restorer(0x1000)
+        .action(
+            Signo::SIGTERM,
+            SignalAction {
+                disposition: SignalDisposition::Ignore,
+                ..Default::default()
+            },
+        )
+        .build();
+    assert_eq!(env.thread.tid(), 3);
+    assert_eq!(env.proc.default_restorer(), 0x1000);
+    assert!(
+        !env.thread
+            .send_signal(SignalInfo::new_kernel(Signo::SIGTERM))
+    );
+
+    let uctx = env.user_context();
+    assert_eq!(uctx.sp(), initial_sp());
+}
+
+#[test]
+fn manual_clock() {
+    clock::set(10);
+    assert_eq!(clock::advance(5), 15);
+    assert_eq!(clock::now(), 15);
+}
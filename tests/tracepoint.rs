@@ -0,0 +1,70 @@
+#![cfg(feature = "tracepoint")]
+
+use std::sync::{LazyLock, Mutex};
+
+use axcpu::uspace::UserContext;
+use starry_signal::{
+    SignalInfo, Signo,
+    tracepoint::{self, GenerateResult, SignalDeliver, SignalGenerate, SignalTracepoints},
+};
+
+mod common;
+use common::*;
+
+#[derive(Default)]
+struct Recorder {
+    generated: Mutex<Vec<SignalGenerate>>,
+    delivered: Mutex<Vec<(u32, Signo)>>,
+}
+
+impl SignalTracepoints for Recorder {
+    fn signal_generate(&self, event: &SignalGenerate) {
+        self.generated.lock().unwrap().push(*event);
+    }
+
+    fn signal_deliver(&self, event: &SignalDeliver) {
+        self.delivered
+            .lock()
+            .unwrap()
+            .push((event.tid, event.signo));
+    }
+}
+
+#[test]
+fn tracepoints() {
+    static RECORDER: LazyLock<Recorder> = LazyLock::new(Recorder::default);
+    tracepoint::register(&*RECORDER);
+
+    let (proc, thr) = new_test_env();
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGHUP, 0, 1));
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGCHLD, 0, 1));
+
+    let mut uctx = UserContext::new(0, initial_sp().into(), 0);
+    let _ = thr.check_signals(&mut uctx, None);
+    tracepoint::unregister();
+
+    let generated: Vec<_> = RECORDER
+        .generated
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|event| (event.signo, event.tid, event.group, event.result))
+        .collect();
+    assert_eq!(
+        generated,
+        [
+            (Signo::SIGTERM, Some(TID), true, GenerateResult::Delivered),
+            (Signo::SIGHUP, Some(TID), false, GenerateResult::Delivered),
+            (
+                Signo::SIGHUP,
+                Some(TID),
+                false,
+                GenerateResult::AlreadyPending
+            ),
+            (Signo::SIGCHLD, Some(TID), false, GenerateResult::Ignored),
+        ]
+    );
+    assert_eq!(*RECORDER.delivered.lock().unwrap(), [(TID, Signo::SIGHUP)]);
+}
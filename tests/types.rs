@@ -1,4 +1,8 @@
-use starry_signal::{SignalInfo, SignalSet, Signo};
+use linux_raw_sys::general::{MINSIGSTKSZ, SS_AUTODISARM, SS_DISABLE, SS_ONSTACK, stack_t};
+use starry_signal::{
+    LibcRtReserve, SIGINFO_SIZE, SignalError, SignalInfo, SignalSet, SignalStack, Signo,
+};
+use strum::IntoEnumIterator;
 
 #[test]
 fn signalset_add_remove_has_is_empty() {
@@ -81,3 +85,158 @@ fn signalinfo_new_user() {
     );
     assert_eq!(si.errno(), 0);
 }
+
+#[test]
+fn signalinfo_raw_roundtrip() {
+    let si = SignalInfo::new_user(Signo::SIGUSR1, -1, 42);
+    let back = SignalInfo::from_raw(si.clone().into_raw()).unwrap();
+    assert_eq!(back.signo(), Signo::SIGUSR1);
+    assert_eq!(back.code(), -1);
+
+    let bytes = si.to_bytes();
+    assert_eq!(bytes.len(), SIGINFO_SIZE);
+    let back = SignalInfo::from_bytes(&bytes).unwrap();
+    assert_eq!(back.to_bytes(), bytes);
+    assert_eq!(
+        unsafe { back.as_raw().__bindgen_anon_1.__bindgen_anon_1.si_signo },
+        10
+    );
+}
+
+#[test]
+fn signalinfo_from_bytes_validates() {
+    let mut bytes = SignalInfo::new_kernel(Signo::SIGTERM).to_bytes();
+    assert_eq!(
+        SignalInfo::from_bytes(&bytes[..SIGINFO_SIZE - 1]).err(),
+        Some(SignalError::InvalidArgument)
+    );
+
+    bytes[..4].copy_from_slice(&0i32.to_ne_bytes());
+    assert_eq!(
+        SignalInfo::from_bytes(&bytes).err(),
+        Some(SignalError::InvalidSignal)
+    );
+    bytes[..4].copy_from_slice(&65i32.to_ne_bytes());
+    assert_eq!(
+        SignalInfo::from_bytes(&bytes).err(),
+        Some(SignalError::InvalidSignal)
+    );
+    bytes[..4].copy_from_slice(&(-1i32).to_ne_bytes());
+    assert_eq!(
+        SignalInfo::from_bytes(&bytes).err(),
+        Some(SignalError::InvalidSignal)
+    );
+}
+
+#[test]
+fn signalinfo_check_user_code() {
+    let queued = SignalInfo::new_user(Signo::SIGUSR1, -1, 1);
+    assert_eq!(queued.check_user_code(false), Ok(()));
+    let kernel = SignalInfo::new_kernel(Signo::SIGUSR1);
+    assert_eq!(kernel.check_user_code(false), Err(SignalError::Permission));
+    assert_eq!(kernel.check_user_code(true), Ok(()));
+    // SI_USER and SI_TKILL
+    for code in [0, -6] {
+        let sig = SignalInfo::new_user(Signo::SIGUSR1, code, 1);
+        assert_eq!(sig.check_user_code(false), Err(SignalError::Permission));
+    }
+}
+
+#[test]
+fn signo_exit_codes() {
+    assert_eq!(Signo::SIGKILL.wait_status(false), 9);
+    assert_eq!(Signo::SIGSEGV.wait_status(true), 0x8b);
+    assert_eq!(Signo::SIGINT.shell_exit_code(), 130);
+    assert_eq!(Signo::SIGTERM.shell_exit_code(), 143);
+}
+
+#[test]
+fn signo_predicates() {
+    assert!(Signo::SIGRTMIN.is_realtime() && !Signo::SIGSYS.is_realtime());
+    let stop: Vec<_> = Signo::iter().filter(Signo::is_stop).collect();
+    assert_eq!(
+        stop,
+        [
+            Signo::SIGSTOP,
+            Signo::SIGTSTP,
+            Signo::SIGTTIN,
+            Signo::SIGTTOU
+        ]
+    );
+    assert!(Signo::SIGTERM.is_fatal_by_default());
+    assert!(Signo::SIGSEGV.is_fatal_by_default());
+    assert!(!Signo::SIGCHLD.is_fatal_by_default());
+    assert!(!Signo::SIGSTOP.is_fatal_by_default());
+    assert!(Signo::SIGBUS.is_synchronous_fault());
+    assert!(!Signo::SIGSYS.is_synchronous_fault());
+    let unblockable: Vec<_> = Signo::iter().filter(Signo::is_unblockable).collect();
+    assert_eq!(unblockable, [Signo::SIGKILL, Signo::SIGSTOP]);
+}
+
+#[test]
+fn signo_try_from() {
+    assert_eq!(Signo::try_from(9), Ok(Signo::SIGKILL));
+    assert_eq!(Signo::try_from(64), Ok(Signo::SIGRT32));
+    assert_eq!(Signo::try_from(0), Err(SignalError::InvalidSignal));
+    assert_eq!(Signo::try_from(65), Err(SignalError::InvalidSignal));
+    assert_eq!(Signo::try_from(0x109), Err(SignalError::InvalidSignal));
+}
+
+#[test]
+fn signalstack_conversions() {
+    let raw = |sp: usize, flags: u32, size: usize| stack_t {
+        ss_sp: sp as _,
+        ss_flags: flags as _,
+        ss_size: size as _,
+    };
+
+    let stack = SignalStack::try_from(raw(0x1000, SS_ONSTACK, 0x4000)).unwrap();
+    assert!(stack.enabled());
+    assert_eq!((stack.sp, stack.flags, stack.size), (0x1000, 0, 0x4000));
+    assert!(!stack.contains(0x1000));
+    assert!(stack.contains(0x5000));
+    assert!(!stack.contains(0x5001));
+
+    let stack = SignalStack::try_from(raw(0x1000, SS_AUTODISARM, 0x4000)).unwrap();
+    assert_eq!(stack.flags, SS_AUTODISARM);
+    assert!(stack.autodisarm());
+
+    let stack = SignalStack::try_from(raw(0x1000, SS_DISABLE | SS_AUTODISARM, 0x4000)).unwrap();
+    assert!(stack.disabled());
+    assert!(!stack.autodisarm());
+
+    let stack = SignalStack::try_from(raw(0x1000, SS_DISABLE, 0x4000)).unwrap();
+    assert!(stack.disabled());
+    assert!(!stack.contains(0x2000));
+
+    assert_eq!(
+        SignalStack::try_from(raw(0x1000, 3, 0x4000)).err(),
+        Some(SignalError::InvalidArgument)
+    );
+    assert_eq!(
+        SignalStack::try_from(raw(0x1000, 0, MINSIGSTKSZ as usize - 1)).err(),
+        Some(SignalError::StackTooSmall)
+    );
+
+    let back = stack_t::from(SignalStack::new(0x1000, 0x4000));
+    assert_eq!(back.ss_sp as usize, 0x1000);
+    assert_eq!(back.ss_flags, 0);
+    assert_eq!(back.ss_size as usize, 0x4000);
+}
+
+#[test]
+fn libc_rt_reserve() {
+    assert_eq!(LibcRtReserve::NONE.sigrtmin(), Signo::SIGRTMIN);
+    assert_eq!(LibcRtReserve::GLIBC.sigrtmin() as u8, 34);
+    assert_eq!(LibcRtReserve::MUSL.sigrtmin() as u8, 35);
+
+    let glibc = LibcRtReserve::GLIBC;
+    assert!(glibc.is_reserved(Signo::SIGRTMIN));
+    assert!(glibc.is_reserved(Signo::SIGRT1));
+    assert!(!glibc.is_reserved(Signo::SIGRT2));
+    assert!(!glibc.is_reserved(Signo::SIGUSR1));
+    assert_eq!(glibc.reserved().bits(), 0b11 << 31);
+    assert_eq!(glibc.rt_signal(0), Some(Signo::SIGRT2));
+    assert_eq!(glibc.rt_signal(30), Some(Signo::SIGRT32));
+    assert_eq!(glibc.rt_signal(31), None);
+}
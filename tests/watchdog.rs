@@ -0,0 +1,69 @@
+#![cfg(feature = "watchdog")]
+
+use std::sync::{
+    Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+use starry_signal::{
+    SignalDisposition, SignalInfo, SignalSet, Signo, test_util::TestEnv, watchdog,
+};
+
+const TID: u32 = 7;
+
+static NOW: AtomicU64 = AtomicU64::new(100);
+static REPORTS: Mutex<Vec<(Option<u32>, Signo, u64)>> = Mutex::new(Vec::new());
+
+#[test]
+fn stale_signals() {
+    let env = TestEnv::builder().tid(TID).build();
+    let (proc, thr) = (env.proc, env.thread);
+    unsafe extern "C" fn test_handler(_: i32) {}
+    watchdog::set_time_source(|| NOW.load(Ordering::Relaxed));
+    watchdog::set_reporter(|tid, signo, age| REPORTS.lock().unwrap().push((tid, signo, age)));
+
+    let mut blocked = SignalSet::default();
+    blocked.add(Signo::SIGUSR2);
+    thr.set_blocked(blocked);
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    NOW.store(150, Ordering::Relaxed);
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGTERM, 0, 1));
+    // Blocked, so not deliverable.
+    let _ = thr.send_signal(SignalInfo::new_user(Signo::SIGUSR2, 0, 1));
+
+    NOW.store(200, Ordering::Relaxed);
+    proc.check_stale_signals(60);
+    assert_eq!(*REPORTS.lock().unwrap(), [(None, Signo::SIGUSR1, 100)]);
+
+    NOW.store(300, Ordering::Relaxed);
+    proc.check_stale_signals(60);
+    assert_eq!(
+        REPORTS.lock().unwrap()[1..],
+        [
+            (Some(TID), Signo::SIGTERM, 150),
+            (None, Signo::SIGUSR1, 200)
+        ]
+    );
+
+    // Delivered signals are forgotten, and a signal queued again is timed
+    // afresh.
+    while thr.dequeue_signal(&!SignalSet::default()).is_some() {}
+    let _ = proc.send_signal(SignalInfo::new_user(Signo::SIGUSR1, 0, 1));
+    REPORTS.lock().unwrap().clear();
+    proc.check_stale_signals(60);
+    assert!(REPORTS.lock().unwrap().is_empty());
+
+    // The last signal number is tracked as well.
+    thr.set_blocked(SignalSet::default());
+    proc.actions().lock()[Signo::SIGRT32].disposition = SignalDisposition::Handler(test_handler);
+    let _ = thr.send_signal(SignalInfo::new_kernel(Signo::SIGRT32));
+    NOW.store(400, Ordering::Relaxed);
+    proc.check_stale_signals(60);
+    assert_eq!(
+        REPORTS.lock().unwrap()[..],
+        [
+            (Some(TID), Signo::SIGRT32, 100),
+            (None, Signo::SIGUSR1, 100)
+        ]
+    );
+}